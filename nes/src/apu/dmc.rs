@@ -0,0 +1,230 @@
+use crate::save_state::{SaveState, SaveStateError, StateReader, StateWriter};
+
+// https://www.nesdev.org/wiki/APU_DMC
+// Timer periods in CPU cycles (NTSC)
+#[rustfmt::skip]
+const RATE_TABLE: [u16; 16] = [
+    428, 380, 340, 320, 286, 254, 226, 214, 190, 160, 142, 128, 106, 84, 72, 54,
+];
+
+/// Number of CPU cycles the CPU is halted for while the DMC fetches a sample byte
+pub const DMA_STALL_CYCLES: usize = 4;
+
+pub struct DMC {
+    pub irq_enabled: bool,
+    pub irq_flag: bool,
+    pub looping: bool,
+    pub timer_period: u16,
+    timer: u16,
+
+    pub output_level: u8,
+    pub sample_address: u16,
+    pub sample_length: u16,
+
+    // Memory reader
+    pub current_address: u16,
+    pub bytes_remaining: u16,
+    pub sample_buffer: Option<u8>,
+
+    // Output unit
+    pub shift_register: u8,
+    pub bits_remaining: u8,
+    pub silence: bool,
+}
+
+impl DMC {
+    pub fn new() -> Self {
+        DMC {
+            irq_enabled: false,
+            irq_flag: false,
+            looping: false,
+            timer_period: RATE_TABLE[0],
+            timer: 0,
+
+            output_level: 0,
+            sample_address: 0xC000,
+            sample_length: 1,
+
+            current_address: 0xC000,
+            bytes_remaining: 0,
+            sample_buffer: None,
+
+            shift_register: 0,
+            bits_remaining: 8,
+            silence: true,
+        }
+    }
+
+    /// ```plaintext
+    /// $4010
+    /// 7  bit  0
+    /// ---- ----
+    /// IL.. RRRR
+    /// ││   ││││
+    /// ││   ╘╧╧╧═ Rate index
+    /// │╘════════ Loop flag
+    /// ╘═════════ IRQ enabled flag
+    /// ```
+    pub fn write_control(&mut self, data: u8) {
+        self.irq_enabled = data & 0b1000_0000 != 0;
+        if !self.irq_enabled {
+            self.irq_flag = false;
+        }
+        self.looping = data & 0b0100_0000 != 0;
+        self.timer_period = RATE_TABLE[(data & 0b0000_1111) as usize];
+    }
+
+    /// ```plaintext
+    /// $4011
+    /// 7  bit  0
+    /// ---- ----
+    /// .DDD DDDD
+    ///  │││ ││││
+    ///  ╘╧╧═╧╧╧╧═ Output level
+    /// ```
+    pub fn write_output_level(&mut self, data: u8) {
+        self.output_level = data & 0b0111_1111;
+    }
+
+    /// Sample address = `$C000 + (A * 64)`
+    pub fn write_sample_address(&mut self, data: u8) {
+        self.sample_address = 0xC000 | ((data as u16) << 6);
+    }
+
+    /// Sample length = `(L * 16) + 1` bytes
+    pub fn write_sample_length(&mut self, data: u8) {
+        self.sample_length = ((data as u16) << 4) | 0x0001;
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.irq_flag = false;
+        if !enabled {
+            self.bytes_remaining = 0;
+        } else if self.bytes_remaining == 0 {
+            self.restart();
+        }
+    }
+
+    fn restart(&mut self) {
+        self.current_address = self.sample_address;
+        self.bytes_remaining = self.sample_length;
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.bytes_remaining > 0
+    }
+
+    /// The address the memory reader wants to fetch from, if the sample buffer needs refilling
+    pub fn dma_request(&self) -> Option<u16> {
+        if self.sample_buffer.is_none() && self.bytes_remaining > 0 {
+            Some(self.current_address)
+        } else {
+            None
+        }
+    }
+
+    /// Fills the sample buffer with a byte fetched by the `Bus` for a [`DMC::dma_request`]
+    pub fn dma_load(&mut self, data: u8) {
+        self.sample_buffer = Some(data);
+        self.current_address = if self.current_address == 0xFFFF {
+            0x8000
+        } else {
+            self.current_address + 1
+        };
+
+        self.bytes_remaining -= 1;
+        if self.bytes_remaining == 0 {
+            if self.looping {
+                self.restart();
+            } else if self.irq_enabled {
+                self.irq_flag = true;
+            }
+        }
+    }
+
+    /// Clocked every CPU cycle
+    pub fn clock_timer(&mut self) {
+        if self.timer == 0 {
+            self.timer = self.timer_period - 1;
+            self.clock_output_unit();
+        } else {
+            self.timer -= 1;
+        }
+    }
+
+    // https://www.nesdev.org/wiki/APU_DMC#Output_unit
+    fn clock_output_unit(&mut self) {
+        if !self.silence {
+            if self.shift_register & 0b0000_0001 != 0 {
+                if self.output_level <= 125 {
+                    self.output_level += 2;
+                }
+            } else if self.output_level >= 2 {
+                self.output_level -= 2;
+            }
+        }
+        self.shift_register >>= 1;
+
+        self.bits_remaining -= 1;
+        if self.bits_remaining == 0 {
+            self.bits_remaining = 8;
+            match self.sample_buffer.take() {
+                Some(sample) => {
+                    self.silence = false;
+                    self.shift_register = sample;
+                }
+                None => self.silence = true,
+            }
+        }
+    }
+
+    pub fn output(&self) -> u8 {
+        self.output_level
+    }
+}
+
+impl SaveState for DMC {
+    fn write_state(&self, state: &mut StateWriter) {
+        state.bool(self.irq_enabled);
+        state.bool(self.irq_flag);
+        state.bool(self.looping);
+        state.u16(self.timer_period);
+        state.u16(self.timer);
+
+        state.u8(self.output_level);
+        state.u16(self.sample_address);
+        state.u16(self.sample_length);
+
+        state.u16(self.current_address);
+        state.u16(self.bytes_remaining);
+        state.bool(self.sample_buffer.is_some());
+        state.u8(self.sample_buffer.unwrap_or(0));
+
+        state.u8(self.shift_register);
+        state.u8(self.bits_remaining);
+        state.bool(self.silence);
+    }
+
+    fn read_state(&mut self, state: &mut StateReader) -> Result<(), SaveStateError> {
+        self.irq_enabled = state.bool()?;
+        self.irq_flag = state.bool()?;
+        self.looping = state.bool()?;
+        self.timer_period = state.u16()?;
+        self.timer = state.u16()?;
+
+        self.output_level = state.u8()?;
+        self.sample_address = state.u16()?;
+        self.sample_length = state.u16()?;
+
+        self.current_address = state.u16()?;
+        self.bytes_remaining = state.u16()?;
+        let buffer_full: bool = state.bool()?;
+        let sample_buffer: u8 = state.u8()?;
+        self.sample_buffer = buffer_full.then_some(sample_buffer);
+
+        self.shift_register = state.u8()?;
+        self.bits_remaining = state.u8()?;
+        self.silence = state.bool()?;
+        Ok(())
+    }
+}
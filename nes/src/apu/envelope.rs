@@ -0,0 +1,86 @@
+use crate::save_state::{SaveState, SaveStateError, StateReader, StateWriter};
+
+// https://www.nesdev.org/wiki/APU_Envelope
+pub struct Envelope {
+    pub start: bool,
+    pub looping: bool,
+    pub constant_volume: bool,
+    pub volume: u8,
+    divider: u8,
+    decay_level: u8,
+}
+
+impl Envelope {
+    pub fn new() -> Self {
+        Envelope {
+            start: false,
+            looping: false,
+            constant_volume: false,
+            volume: 0,
+            divider: 0,
+            decay_level: 0,
+        }
+    }
+
+    /// Handles the lower 6 bits shared by `$4000`, `$4004` and `$400C`
+    /// ```plaintext
+    /// 7  bit  0
+    /// ---- ----
+    /// ..LC VVVV
+    ///   ││ ││││
+    ///   ││ ╘╧╧╧═ Volume / Envelope divider period
+    ///   │╘══════ Constant volume flag
+    ///   ╘═══════ Envelope loop (shared with length counter halt)
+    /// ```
+    pub fn write_control(&mut self, data: u8) {
+        self.looping = data & 0b0010_0000 != 0;
+        self.constant_volume = data & 0b0001_0000 != 0;
+        self.volume = data & 0b0000_1111;
+    }
+
+    pub fn clock(&mut self) {
+        if self.start {
+            self.start = false;
+            self.decay_level = 15;
+            self.divider = self.volume;
+        } else if self.divider == 0 {
+            self.divider = self.volume;
+            if self.decay_level > 0 {
+                self.decay_level -= 1;
+            } else if self.looping {
+                self.decay_level = 15;
+            }
+        } else {
+            self.divider -= 1;
+        }
+    }
+
+    pub fn output(&self) -> u8 {
+        if self.constant_volume {
+            self.volume
+        } else {
+            self.decay_level
+        }
+    }
+}
+
+impl SaveState for Envelope {
+    fn write_state(&self, state: &mut StateWriter) {
+        state.bool(self.start);
+        state.bool(self.looping);
+        state.bool(self.constant_volume);
+        state.u8(self.volume);
+        state.u8(self.divider);
+        state.u8(self.decay_level);
+    }
+
+    fn read_state(&mut self, state: &mut StateReader) -> Result<(), SaveStateError> {
+        self.start = state.bool()?;
+        self.looping = state.bool()?;
+        self.constant_volume = state.bool()?;
+        self.volume = state.u8()?;
+        self.divider = state.u8()?;
+        self.decay_level = state.u8()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,80 @@
+use std::f32::consts::PI;
+
+// https://www.nesdev.org/wiki/APU_Mixer
+// The NES's audio output passes through two first-order high-pass filters and a low-pass filter
+pub const HIGH_PASS_1_HZ: f32 = 90.0;
+pub const HIGH_PASS_2_HZ: f32 = 440.0;
+pub const LOW_PASS_HZ: f32 = 14_000.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FilterKind {
+    HighPass,
+    LowPass,
+}
+
+/// First-order RC filter
+pub struct Filter {
+    pub kind: FilterKind,
+    pub cutoff: f32,
+    alpha: f32,
+    prev_input: f32,
+    prev_output: f32,
+}
+
+impl Filter {
+    pub fn new(kind: FilterKind, cutoff: f32, sample_rate: f32) -> Self {
+        let rc: f32 = 1.0 / (2.0 * PI * cutoff);
+        let dt: f32 = 1.0 / sample_rate;
+        let alpha: f32 = match kind {
+            FilterKind::HighPass => rc / (rc + dt),
+            FilterKind::LowPass => dt / (rc + dt),
+        };
+
+        Filter {
+            kind,
+            cutoff,
+            alpha,
+            prev_input: 0.0,
+            prev_output: 0.0,
+        }
+    }
+
+    pub fn process(&mut self, input: f32) -> f32 {
+        let output: f32 = match self.kind {
+            FilterKind::HighPass => self.alpha * (self.prev_output + input - self.prev_input),
+            FilterKind::LowPass => self.prev_output + self.alpha * (input - self.prev_output),
+        };
+        self.prev_input = input;
+        self.prev_output = output;
+        output
+    }
+}
+
+pub struct FilterChain {
+    pub enabled: bool,
+    pub filters: Vec<Filter>,
+}
+
+impl FilterChain {
+    pub fn new(sample_rate: f32) -> Self {
+        FilterChain {
+            enabled: true,
+            filters: vec![
+                Filter::new(FilterKind::HighPass, HIGH_PASS_1_HZ, sample_rate),
+                Filter::new(FilterKind::HighPass, HIGH_PASS_2_HZ, sample_rate),
+                Filter::new(FilterKind::LowPass, LOW_PASS_HZ, sample_rate),
+            ],
+        }
+    }
+
+    pub fn process(&mut self, input: f32) -> f32 {
+        if !self.enabled {
+            return input;
+        }
+        self.filters
+            .iter_mut()
+            .fold(input, |sample: f32, filter: &mut Filter| {
+                filter.process(sample)
+            })
+    }
+}
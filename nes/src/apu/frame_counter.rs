@@ -0,0 +1,154 @@
+use crate::{
+    prelude::*,
+    save_state::{SaveState, SaveStateError, StateReader, StateWriter},
+};
+
+// https://www.nesdev.org/wiki/APU_Frame_Counter
+// CPU cycles at which each step of the sequences is clocked (NTSC)
+const STEP_1: usize = 7457;
+const STEP_2: usize = 14913;
+const STEP_3: usize = 22371;
+const STEP_4_IRQ: usize = 29828;
+const STEP_4: usize = 29829;
+const STEP_4_LENGTH: usize = 29830;
+const STEP_5: usize = 37281;
+const STEP_5_LENGTH: usize = 37282;
+
+bitflags! {
+    /// Which units the frame counter clocked on a given cycle
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct FrameClock: u8 {
+        /// Envelopes and the triangle's linear counter
+        const QUARTER = 0b0000_0001;
+        /// Length counters and sweep units
+        const HALF    = 0b0000_0010;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SequenceMode {
+    FourStep,
+    FiveStep,
+}
+
+pub struct FrameCounter {
+    pub mode: SequenceMode,
+    pub irq_inhibit: bool,
+    pub irq_flag: bool,
+    pub cycle: usize,
+    /// CPU cycles left until a `$4017` write resets the sequencer
+    reset_delay: Option<u8>,
+}
+
+impl FrameCounter {
+    pub fn new() -> Self {
+        FrameCounter {
+            mode: SequenceMode::FourStep,
+            irq_inhibit: false,
+            irq_flag: false,
+            cycle: 0,
+            reset_delay: None,
+        }
+    }
+
+    /// ```plaintext
+    /// $4017 (write)
+    /// 7  bit  0
+    /// ---- ----
+    /// MI.. ....
+    /// ││
+    /// │╘════════ IRQ inhibit flag
+    /// ╘═════════ Sequencer mode (0: 4-step, 1: 5-step)
+    /// ```
+    /// Returns the units that are clocked immediately by switching to the 5-step sequence
+    pub fn write_control(&mut self, data: u8, cpu_cycle: usize) -> FrameClock {
+        self.mode = if data & 0b1000_0000 != 0 {
+            SequenceMode::FiveStep
+        } else {
+            SequenceMode::FourStep
+        };
+        self.irq_inhibit = data & 0b0100_0000 != 0;
+        if self.irq_inhibit {
+            self.irq_flag = false;
+        }
+
+        // The reset happens 3 CPU cycles after a write during an APU cycle, and 4 otherwise
+        self.reset_delay = Some(if cpu_cycle.is_multiple_of(2) { 3 } else { 4 });
+
+        match self.mode {
+            SequenceMode::FourStep => FrameClock::empty(),
+            SequenceMode::FiveStep => FrameClock::QUARTER | FrameClock::HALF,
+        }
+    }
+
+    /// Clocked once per CPU cycle
+    pub fn tick(&mut self) -> FrameClock {
+        if let Some(delay) = self.reset_delay {
+            if delay <= 1 {
+                self.reset_delay = None;
+                self.cycle = 0;
+            } else {
+                self.reset_delay = Some(delay - 1);
+            }
+        }
+
+        self.cycle += 1;
+        match (self.mode, self.cycle) {
+            (_, STEP_1 | STEP_3) => FrameClock::QUARTER,
+            (_, STEP_2) => FrameClock::QUARTER | FrameClock::HALF,
+
+            (SequenceMode::FourStep, STEP_4_IRQ) => {
+                self.set_irq();
+                FrameClock::empty()
+            }
+            (SequenceMode::FourStep, STEP_4) => {
+                self.set_irq();
+                FrameClock::QUARTER | FrameClock::HALF
+            }
+            (SequenceMode::FourStep, STEP_4_LENGTH) => {
+                self.set_irq();
+                self.cycle = 0;
+                FrameClock::empty()
+            }
+
+            (SequenceMode::FiveStep, STEP_5) => FrameClock::QUARTER | FrameClock::HALF,
+            (SequenceMode::FiveStep, STEP_5_LENGTH) => {
+                self.cycle = 0;
+                FrameClock::empty()
+            }
+
+            _ => FrameClock::empty(),
+        }
+    }
+
+    fn set_irq(&mut self) {
+        if !self.irq_inhibit {
+            self.irq_flag = true;
+        }
+    }
+}
+
+impl SaveState for FrameCounter {
+    fn write_state(&self, state: &mut StateWriter) {
+        state.bool(self.mode == SequenceMode::FiveStep);
+        state.bool(self.irq_inhibit);
+        state.bool(self.irq_flag);
+        state.usize(self.cycle);
+        state.bool(self.reset_delay.is_some());
+        state.u8(self.reset_delay.unwrap_or(0));
+    }
+
+    fn read_state(&mut self, state: &mut StateReader) -> Result<(), SaveStateError> {
+        self.mode = match state.bool()? {
+            true => SequenceMode::FiveStep,
+            false => SequenceMode::FourStep,
+        };
+        self.irq_inhibit = state.bool()?;
+        self.irq_flag = state.bool()?;
+        self.cycle = state.usize()?;
+        let reset_pending: bool = state.bool()?;
+        let reset_delay: u8 = state.u8()?;
+        self.reset_delay = reset_pending.then_some(reset_delay);
+        Ok(())
+    }
+}
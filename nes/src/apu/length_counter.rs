@@ -0,0 +1,62 @@
+use crate::save_state::{SaveState, SaveStateError, StateReader, StateWriter};
+
+// https://www.nesdev.org/wiki/APU_Length_Counter
+#[rustfmt::skip]
+pub const LENGTH_TABLE: [u8; 32] = [
+    10, 254, 20,  2, 40,  4, 80,  6, 160,  8, 60, 10, 14, 12, 26, 14,
+    12,  16, 24, 18, 48, 20, 96, 22, 192, 24, 72, 26, 16, 28, 32, 30,
+];
+
+pub struct LengthCounter {
+    pub enabled: bool,
+    pub halt: bool,
+    pub counter: u8,
+}
+
+impl LengthCounter {
+    pub fn new() -> Self {
+        LengthCounter {
+            enabled: false,
+            halt: false,
+            counter: 0,
+        }
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.counter = 0;
+        }
+    }
+
+    pub fn load(&mut self, index: u8) {
+        if self.enabled {
+            self.counter = LENGTH_TABLE[(index & 0b0001_1111) as usize];
+        }
+    }
+
+    pub fn clock(&mut self) {
+        if !self.halt && self.counter > 0 {
+            self.counter -= 1;
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.counter > 0
+    }
+}
+
+impl SaveState for LengthCounter {
+    fn write_state(&self, state: &mut StateWriter) {
+        state.bool(self.enabled);
+        state.bool(self.halt);
+        state.u8(self.counter);
+    }
+
+    fn read_state(&mut self, state: &mut StateReader) -> Result<(), SaveStateError> {
+        self.enabled = state.bool()?;
+        self.halt = state.bool()?;
+        self.counter = state.u8()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,91 @@
+// https://www.nesdev.org/wiki/APU_Mixer#Lookup_Table
+const PULSE_TABLE: [f32; 31] = pulse_table();
+const TND_TABLE: [f32; 203] = tnd_table();
+
+const fn pulse_table() -> [f32; 31] {
+    let mut table: [f32; 31] = [0.0; 31];
+    let mut i: usize = 1;
+    while i < table.len() {
+        table[i] = 95.52 / (8128.0 / i as f32 + 100.0);
+        i += 1;
+    }
+    table
+}
+
+const fn tnd_table() -> [f32; 203] {
+    let mut table: [f32; 203] = [0.0; 203];
+    let mut i: usize = 1;
+    while i < table.len() {
+        table[i] = 163.67 / (24329.0 / i as f32 + 100.0);
+        i += 1;
+    }
+    table
+}
+
+/// Linearly interpolates between table entries, so channel gains don't have to be whole numbers
+fn lookup(table: &[f32], index: f32) -> f32 {
+    let index: f32 = index.clamp(0.0, (table.len() - 1) as f32);
+    let lower: usize = index as usize;
+    let upper: usize = (lower + 1).min(table.len() - 1);
+    let fraction: f32 = index - lower as f32;
+    table[lower] + (table[upper] - table[lower]) * fraction
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+    Pulse1,
+    Pulse2,
+    Triangle,
+    Noise,
+    DMC,
+}
+
+impl Channel {
+    pub const ALL: [Channel; 5] = [
+        Channel::Pulse1,
+        Channel::Pulse2,
+        Channel::Triangle,
+        Channel::Noise,
+        Channel::DMC,
+    ];
+}
+
+/// Combines the raw channel outputs, with per-channel mute, solo and gain applied beforehand
+pub struct Mixer {
+    pub gain: [f32; 5],
+    pub muted: [bool; 5],
+    pub solo: [bool; 5],
+}
+
+impl Mixer {
+    pub fn new() -> Self {
+        Mixer {
+            gain: [1.0; 5],
+            muted: [false; 5],
+            solo: [false; 5],
+        }
+    }
+
+    pub fn is_audible(&self, channel: Channel) -> bool {
+        let soloing: bool = self.solo.iter().any(|solo: &bool| *solo);
+        !self.muted[channel as usize] && (!soloing || self.solo[channel as usize])
+    }
+
+    fn level(&self, channel: Channel, output: u8) -> f32 {
+        if self.is_audible(channel) {
+            output as f32 * self.gain[channel as usize]
+        } else {
+            0.0
+        }
+    }
+
+    /// Takes the raw channel outputs in [`Channel`] order, returns a level in the range `0.0..=1.0`
+    pub fn mix(&self, outputs: [u8; 5]) -> f32 {
+        let pulse: f32 =
+            self.level(Channel::Pulse1, outputs[0]) + self.level(Channel::Pulse2, outputs[1]);
+        let tnd: f32 = 3.0 * self.level(Channel::Triangle, outputs[2])
+            + 2.0 * self.level(Channel::Noise, outputs[3])
+            + self.level(Channel::DMC, outputs[4]);
+        lookup(&PULSE_TABLE, pulse) + lookup(&TND_TABLE, tnd)
+    }
+}
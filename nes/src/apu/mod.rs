@@ -1,7 +1,262 @@
-pub struct APU;
+pub mod dmc;
+pub mod envelope;
+pub mod filters;
+pub mod frame_counter;
+pub mod length_counter;
+pub mod mixer;
+pub mod noise;
+pub mod pulse;
+pub mod resampler;
+pub mod triangle;
+
+use self::{
+    dmc::DMC,
+    frame_counter::{FrameClock, FrameCounter},
+    mixer::{Channel, Mixer},
+    noise::Noise,
+    pulse::{Pulse, PulseChannel},
+    resampler::Resampler,
+    triangle::Triangle,
+};
+use crate::{
+    memory::mem_map::*,
+    save_state::{SaveState, SaveStateError, StateReader, StateWriter},
+};
+
+pub const CPU_CLOCK_RATE_NTSC: f64 = 1_789_773.0;
+
+pub struct APU {
+    pub pulse1: Pulse,
+    pub pulse2: Pulse,
+    pub triangle: Triangle,
+    pub noise: Noise,
+    pub dmc: DMC,
+    pub frame_counter: FrameCounter,
+    pub mixer: Mixer,
+    pub cycles: usize,
+    /// Only collects samples once a host sample rate is set
+    pub resampler: Option<Resampler>,
+}
 
 impl APU {
     pub fn new() -> Self {
-        APU {}
+        APU {
+            pulse1: Pulse::new(PulseChannel::One),
+            pulse2: Pulse::new(PulseChannel::Two),
+            triangle: Triangle::new(),
+            noise: Noise::new(),
+            dmc: DMC::new(),
+            frame_counter: FrameCounter::new(),
+            mixer: Mixer::new(),
+            cycles: 0,
+            resampler: None,
+        }
+    }
+
+    /// Passing `None` stops collecting samples
+    pub fn set_sample_rate(&mut self, sample_rate: Option<f64>) {
+        self.resampler =
+            sample_rate.map(|sample_rate: f64| Resampler::new(CPU_CLOCK_RATE_NTSC, sample_rate));
+    }
+
+    /// Clocked once per CPU cycle
+    pub fn tick(&mut self) {
+        self.cycles += 1;
+
+        if self.cycles.is_multiple_of(2) {
+            self.pulse1.clock_timer();
+            self.pulse2.clock_timer();
+        }
+        self.triangle.clock_timer();
+        self.noise.clock_timer();
+        self.dmc.clock_timer();
+
+        let frame_clock: FrameClock = self.frame_counter.tick();
+        self.clock_frame(frame_clock);
+
+        let output: Option<f32> = self.resampler.is_some().then(|| self.output());
+        if let (Some(resampler), Some(output)) = (self.resampler.as_mut(), output) {
+            resampler.push(output);
+        }
+    }
+
+    fn clock_frame(&mut self, frame_clock: FrameClock) {
+        if frame_clock.contains(FrameClock::QUARTER) {
+            self.pulse1.clock_quarter_frame();
+            self.pulse2.clock_quarter_frame();
+            self.triangle.clock_quarter_frame();
+            self.noise.clock_quarter_frame();
+        }
+        if frame_clock.contains(FrameClock::HALF) {
+            self.pulse1.clock_half_frame();
+            self.pulse2.clock_half_frame();
+            self.triangle.clock_half_frame();
+            self.noise.clock_half_frame();
+        }
+    }
+
+    /// Level-triggered, stays asserted until acknowledged through `$4015`, `$4017` or `$4010`
+    pub fn irq_pending(&self) -> bool {
+        self.frame_counter.irq_flag || self.dmc.irq_flag
+    }
+
+    pub fn write_register(&mut self, addr: u16, data: u8) {
+        match addr {
+            MMIO_SQ1_VOL => self.pulse1.write_control(data),
+            MMIO_SQ1_SWEEP => self.pulse1.write_sweep(data),
+            MMIO_SQ1_LO => self.pulse1.write_timer_lo(data),
+            MMIO_SQ1_HI => self.pulse1.write_timer_hi(data),
+            MMIO_SQ2_VOL => self.pulse2.write_control(data),
+            MMIO_SQ2_SWEEP => self.pulse2.write_sweep(data),
+            MMIO_SQ2_LO => self.pulse2.write_timer_lo(data),
+            MMIO_SQ2_HI => self.pulse2.write_timer_hi(data),
+            MMIO_TRI_LINEAR => self.triangle.write_linear(data),
+            MMIO_TRI_LO => self.triangle.write_timer_lo(data),
+            MMIO_TRI_HI => self.triangle.write_timer_hi(data),
+            MMIO_NOISE_VOL => self.noise.write_control(data),
+            MMIO_NOISE_LO => self.noise.write_period(data),
+            MMIO_NOISE_HI => self.noise.write_length(data),
+            MMIO_DMC_FREQ => self.dmc.write_control(data),
+            MMIO_DMC_RAW => self.dmc.write_output_level(data),
+            MMIO_DMC_START => self.dmc.write_sample_address(data),
+            MMIO_DMC_LEN => self.dmc.write_sample_length(data),
+            MMIO_SND_CHN => self.write_status(data),
+            // `$4017` is the frame counter when written to
+            MMIO_JOY2 => {
+                let frame_clock: FrameClock = self.frame_counter.write_control(data, self.cycles);
+                self.clock_frame(frame_clock);
+            }
+            _ => {
+                // warn!("[APU] Ignoring write {:#04X} to {:#06X}", data, addr);
+            }
+        }
+    }
+
+    /// ```plaintext
+    /// $4015 (write)
+    /// 7  bit  0
+    /// ---- ----
+    /// ...D NT21
+    ///    │ ││││
+    ///    │ │││╘═ Enable Pulse 1
+    ///    │ ││╘══ Enable Pulse 2
+    ///    │ │╘═══ Enable Triangle
+    ///    │ ╘════ Enable Noise
+    ///    ╘══════ Enable DMC
+    /// ```
+    pub fn write_status(&mut self, data: u8) {
+        self.pulse1
+            .length_counter
+            .set_enabled(data & 0b0000_0001 != 0);
+        self.pulse2
+            .length_counter
+            .set_enabled(data & 0b0000_0010 != 0);
+        self.triangle
+            .length_counter
+            .set_enabled(data & 0b0000_0100 != 0);
+        self.noise
+            .length_counter
+            .set_enabled(data & 0b0000_1000 != 0);
+        self.dmc.set_enabled(data & 0b0001_0000 != 0);
+    }
+
+    /// ```plaintext
+    /// $4015 (read)
+    /// 7  bit  0
+    /// ---- ----
+    /// IF.D NT21
+    /// ││ │ ││││
+    /// ││ │ │││╘═ Pulse 1 length counter > 0
+    /// ││ │ ││╘══ Pulse 2 length counter > 0
+    /// ││ │ │╘═══ Triangle length counter > 0
+    /// ││ │ ╘════ Noise length counter > 0
+    /// ││ ╘══════ DMC bytes remaining > 0
+    /// │╘════════ Frame interrupt (cleared by the read)
+    /// ╘═════════ DMC interrupt
+    /// ```
+    pub fn read_status(&mut self) -> u8 {
+        let status: u8 = self.peek_status();
+        self.frame_counter.irq_flag = false;
+        status
+    }
+
+    /// What a read from `$4015` would return, without acknowledging the frame interrupt
+    pub fn peek_status(&self) -> u8 {
+        let mut status: u8 = 0x00;
+        if self.pulse1.length_counter.is_active() {
+            status |= 0b0000_0001;
+        }
+        if self.pulse2.length_counter.is_active() {
+            status |= 0b0000_0010;
+        }
+        if self.triangle.length_counter.is_active() {
+            status |= 0b0000_0100;
+        }
+        if self.noise.length_counter.is_active() {
+            status |= 0b0000_1000;
+        }
+        if self.dmc.is_active() {
+            status |= 0b0001_0000;
+        }
+        if self.frame_counter.irq_flag {
+            status |= 0b0100_0000;
+        }
+        if self.dmc.irq_flag {
+            status |= 0b1000_0000;
+        }
+        status
+    }
+
+    pub fn channel_output(&self, channel: Channel) -> u8 {
+        match channel {
+            Channel::Pulse1 => self.pulse1.output(),
+            Channel::Pulse2 => self.pulse2.output(),
+            Channel::Triangle => self.triangle.output(),
+            Channel::Noise => self.noise.output(),
+            Channel::DMC => self.dmc.output(),
+        }
+    }
+
+    pub fn set_channel_muted(&mut self, channel: Channel, muted: bool) {
+        self.mixer.muted[channel as usize] = muted;
+    }
+
+    /// While any channel is soloed, only soloed channels can be heard
+    pub fn set_channel_solo(&mut self, channel: Channel, solo: bool) {
+        self.mixer.solo[channel as usize] = solo;
+    }
+
+    pub fn set_channel_gain(&mut self, channel: Channel, gain: f32) {
+        self.mixer.gain[channel as usize] = gain.max(0.0);
+    }
+
+    /// Current mixed output in the range `0.0..=1.0`, before any filtering
+    pub fn output(&self) -> f32 {
+        self.mixer
+            .mix(Channel::ALL.map(|channel: Channel| self.channel_output(channel)))
+    }
+}
+
+/// Only the machine state is saved, the mixer and resampler are host-side audio settings
+impl SaveState for APU {
+    fn write_state(&self, state: &mut StateWriter) {
+        self.pulse1.write_state(state);
+        self.pulse2.write_state(state);
+        self.triangle.write_state(state);
+        self.noise.write_state(state);
+        self.dmc.write_state(state);
+        self.frame_counter.write_state(state);
+        state.usize(self.cycles);
+    }
+
+    fn read_state(&mut self, state: &mut StateReader) -> Result<(), SaveStateError> {
+        self.pulse1.read_state(state)?;
+        self.pulse2.read_state(state)?;
+        self.triangle.read_state(state)?;
+        self.noise.read_state(state)?;
+        self.dmc.read_state(state)?;
+        self.frame_counter.read_state(state)?;
+        self.cycles = state.usize()?;
+        Ok(())
     }
 }
@@ -0,0 +1,131 @@
+use super::{envelope::Envelope, length_counter::LengthCounter};
+use crate::save_state::{SaveState, SaveStateError, StateReader, StateWriter};
+
+// https://www.nesdev.org/wiki/APU_Noise
+// Timer periods in CPU cycles (NTSC)
+#[rustfmt::skip]
+const PERIOD_TABLE: [u16; 16] = [
+    4, 8, 16, 32, 64, 96, 128, 160, 202, 254, 380, 508, 762, 1016, 2034, 4068,
+];
+
+pub struct Noise {
+    pub envelope: Envelope,
+    pub length_counter: LengthCounter,
+    /// Short mode, feedback is taken from bit 6 instead of bit 1
+    pub mode: bool,
+    pub shift_register: u16,
+    pub timer_period: u16,
+    timer: u16,
+}
+
+impl Noise {
+    pub fn new() -> Self {
+        Noise {
+            envelope: Envelope::new(),
+            length_counter: LengthCounter::new(),
+            mode: false,
+            // The shift register is loaded with 1 on power-up
+            shift_register: 0b0000_0000_0000_0001,
+            timer_period: PERIOD_TABLE[0],
+            timer: 0,
+        }
+    }
+
+    /// ```plaintext
+    /// $400C
+    /// 7  bit  0
+    /// ---- ----
+    /// ..LC VVVV
+    ///   ││ ││││
+    ///   ││ ╘╧╧╧═ Volume / Envelope divider period
+    ///   │╘══════ Constant volume flag
+    ///   ╘═══════ Length counter halt / Envelope loop
+    /// ```
+    pub fn write_control(&mut self, data: u8) {
+        self.length_counter.halt = data & 0b0010_0000 != 0;
+        self.envelope.write_control(data);
+    }
+
+    /// ```plaintext
+    /// $400E
+    /// 7  bit  0
+    /// ---- ----
+    /// M... PPPP
+    /// │    ││││
+    /// │    ╘╧╧╧═ Timer period index
+    /// ╘═════════ Mode
+    /// ```
+    pub fn write_period(&mut self, data: u8) {
+        self.mode = data & 0b1000_0000 != 0;
+        self.timer_period = PERIOD_TABLE[(data & 0b0000_1111) as usize];
+    }
+
+    /// ```plaintext
+    /// $400F
+    /// 7  bit  0
+    /// ---- ----
+    /// LLLL L...
+    /// ││││ │
+    /// ╘╧╧╧═╧════ Length counter load
+    /// ```
+    pub fn write_length(&mut self, data: u8) {
+        self.length_counter.load(data >> 3);
+        self.envelope.start = true;
+    }
+
+    /// Clocked every CPU cycle
+    pub fn clock_timer(&mut self) {
+        if self.timer == 0 {
+            self.timer = self.timer_period - 1;
+            self.clock_shift_register();
+        } else {
+            self.timer -= 1;
+        }
+    }
+
+    // https://www.nesdev.org/wiki/APU_Noise#Linear-feedback_shift_register
+    pub fn clock_shift_register(&mut self) {
+        let tap: u8 = if self.mode { 6 } else { 1 };
+        let feedback: u16 =
+            (self.shift_register & 0x0001) ^ ((self.shift_register >> tap) & 0x0001);
+        self.shift_register >>= 1;
+        self.shift_register |= feedback << 14;
+    }
+
+    pub fn clock_quarter_frame(&mut self) {
+        self.envelope.clock();
+    }
+
+    pub fn clock_half_frame(&mut self) {
+        self.length_counter.clock();
+    }
+
+    pub fn output(&self) -> u8 {
+        if !self.length_counter.is_active() || self.shift_register & 0x0001 != 0 {
+            0
+        } else {
+            self.envelope.output()
+        }
+    }
+}
+
+impl SaveState for Noise {
+    fn write_state(&self, state: &mut StateWriter) {
+        self.envelope.write_state(state);
+        self.length_counter.write_state(state);
+        state.bool(self.mode);
+        state.u16(self.shift_register);
+        state.u16(self.timer_period);
+        state.u16(self.timer);
+    }
+
+    fn read_state(&mut self, state: &mut StateReader) -> Result<(), SaveStateError> {
+        self.envelope.read_state(state)?;
+        self.length_counter.read_state(state)?;
+        self.mode = state.bool()?;
+        self.shift_register = state.u16()?;
+        self.timer_period = state.u16()?;
+        self.timer = state.u16()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,217 @@
+use super::{envelope::Envelope, length_counter::LengthCounter};
+use crate::save_state::{SaveState, SaveStateError, StateReader, StateWriter};
+
+// https://www.nesdev.org/wiki/APU_Pulse
+#[rustfmt::skip]
+const DUTY_TABLE: [[u8; 8]; 4] = [
+    [0, 1, 0, 0, 0, 0, 0, 0], // 12.5%
+    [0, 1, 1, 0, 0, 0, 0, 0], // 25%
+    [0, 1, 1, 1, 1, 0, 0, 0], // 50%
+    [1, 0, 0, 1, 1, 1, 1, 1], // 25% negated
+];
+
+/// The two pulse channels are identical, except for how their sweep units negate the change
+/// amount. Pulse 1 uses ones' complement (`-c - 1`), while Pulse 2 uses two's complement (`-c`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PulseChannel {
+    One,
+    Two,
+}
+
+// https://www.nesdev.org/wiki/APU_Sweep
+pub struct Sweep {
+    pub enabled: bool,
+    pub period: u8,
+    pub negate: bool,
+    pub shift: u8,
+    pub reload: bool,
+    divider: u8,
+}
+
+impl Sweep {
+    pub fn new() -> Self {
+        Sweep {
+            enabled: false,
+            period: 0,
+            negate: false,
+            shift: 0,
+            reload: false,
+            divider: 0,
+        }
+    }
+}
+
+pub struct Pulse {
+    pub channel: PulseChannel,
+    pub envelope: Envelope,
+    pub sweep: Sweep,
+    pub length_counter: LengthCounter,
+    pub duty: u8,
+    pub duty_pos: u8,
+    pub timer_period: u16,
+    timer: u16,
+}
+
+impl Pulse {
+    pub fn new(channel: PulseChannel) -> Self {
+        Pulse {
+            channel,
+            envelope: Envelope::new(),
+            sweep: Sweep::new(),
+            length_counter: LengthCounter::new(),
+            duty: 0,
+            duty_pos: 0,
+            timer_period: 0,
+            timer: 0,
+        }
+    }
+
+    /// ```plaintext
+    /// $4000 / $4004
+    /// 7  bit  0
+    /// ---- ----
+    /// DDLC VVVV
+    /// ││││ ││││
+    /// ││││ ╘╧╧╧═ Volume / Envelope divider period
+    /// │││╘══════ Constant volume flag
+    /// ││╘═══════ Length counter halt / Envelope loop
+    /// ╘╧════════ Duty cycle
+    /// ```
+    pub fn write_control(&mut self, data: u8) {
+        self.duty = data >> 6;
+        self.length_counter.halt = data & 0b0010_0000 != 0;
+        self.envelope.write_control(data);
+    }
+
+    /// ```plaintext
+    /// $4001 / $4005
+    /// 7  bit  0
+    /// ---- ----
+    /// EPPP NSSS
+    /// ││││ ││││
+    /// ││││ │╘╧╧═ Shift count
+    /// ││││ ╘════ Negate
+    /// │╘╧╧══════ Divider period (minus one)
+    /// ╘═════════ Enabled
+    /// ```
+    pub fn write_sweep(&mut self, data: u8) {
+        self.sweep.enabled = data & 0b1000_0000 != 0;
+        self.sweep.period = (data >> 4) & 0b0000_0111;
+        self.sweep.negate = data & 0b0000_1000 != 0;
+        self.sweep.shift = data & 0b0000_0111;
+        self.sweep.reload = true;
+    }
+
+    pub fn write_timer_lo(&mut self, data: u8) {
+        self.timer_period = (self.timer_period & 0xFF00) | data as u16;
+    }
+
+    /// ```plaintext
+    /// $4003 / $4007
+    /// 7  bit  0
+    /// ---- ----
+    /// LLLL LTTT
+    /// ││││ │╘╧╧═ Timer high 3 bits
+    /// ╘╧╧╧═╧════ Length counter load
+    /// ```
+    pub fn write_timer_hi(&mut self, data: u8) {
+        self.timer_period = (self.timer_period & 0x00FF) | ((data as u16 & 0b0000_0111) << 8);
+        self.length_counter.load(data >> 3);
+        self.duty_pos = 0;
+        self.envelope.start = true;
+    }
+
+    /// Clocked every APU cycle (every other CPU cycle)
+    pub fn clock_timer(&mut self) {
+        if self.timer == 0 {
+            self.timer = self.timer_period;
+            self.duty_pos = self.duty_pos.wrapping_sub(1) & 0b0000_0111;
+        } else {
+            self.timer -= 1;
+        }
+    }
+
+    pub fn clock_quarter_frame(&mut self) {
+        self.envelope.clock();
+    }
+
+    pub fn clock_half_frame(&mut self) {
+        self.length_counter.clock();
+
+        if self.sweep.divider == 0
+            && self.sweep.enabled
+            && self.sweep.shift != 0
+            && !self.sweep_muting()
+        {
+            self.timer_period = self.sweep_target_period();
+        }
+        if self.sweep.divider == 0 || self.sweep.reload {
+            self.sweep.divider = self.sweep.period;
+            self.sweep.reload = false;
+        } else {
+            self.sweep.divider -= 1;
+        }
+    }
+
+    pub fn sweep_target_period(&self) -> u16 {
+        let change: u16 = self.timer_period >> self.sweep.shift;
+        if self.sweep.negate {
+            match self.channel {
+                PulseChannel::One => self.timer_period.wrapping_sub(change).wrapping_sub(1),
+                PulseChannel::Two => self.timer_period.wrapping_sub(change),
+            }
+        } else {
+            self.timer_period + change
+        }
+    }
+
+    /// The sweep unit mutes the channel whenever the current period is below 8, or the target
+    /// period overflows past `$7FF`, even when the sweep unit itself is disabled.
+    pub fn sweep_muting(&self) -> bool {
+        self.timer_period < 8 || (!self.sweep.negate && self.sweep_target_period() > 0x07FF)
+    }
+
+    pub fn output(&self) -> u8 {
+        if !self.length_counter.is_active()
+            || self.sweep_muting()
+            || DUTY_TABLE[self.duty as usize][self.duty_pos as usize] == 0
+        {
+            0
+        } else {
+            self.envelope.output()
+        }
+    }
+}
+
+impl SaveState for Pulse {
+    fn write_state(&self, state: &mut StateWriter) {
+        self.envelope.write_state(state);
+        state.bool(self.sweep.enabled);
+        state.u8(self.sweep.period);
+        state.bool(self.sweep.negate);
+        state.u8(self.sweep.shift);
+        state.bool(self.sweep.reload);
+        state.u8(self.sweep.divider);
+        self.length_counter.write_state(state);
+        state.u8(self.duty);
+        state.u8(self.duty_pos);
+        state.u16(self.timer_period);
+        state.u16(self.timer);
+    }
+
+    fn read_state(&mut self, state: &mut StateReader) -> Result<(), SaveStateError> {
+        self.envelope.read_state(state)?;
+        self.sweep.enabled = state.bool()?;
+        self.sweep.period = state.u8()?;
+        self.sweep.negate = state.bool()?;
+        self.sweep.shift = state.u8()?;
+        self.sweep.reload = state.bool()?;
+        self.sweep.divider = state.u8()?;
+        self.length_counter.read_state(state)?;
+        self.duty = state.u8()?;
+        self.duty_pos = state.u8()?;
+        self.timer_period = state.u16()?;
+        self.timer = state.u16()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,54 @@
+use super::filters::FilterChain;
+
+/// Downsamples the APU output from the CPU clock rate to a host sample rate by averaging every
+/// CPU cycle that falls within an output sample (a box filter), then runs it through the
+/// hardware's filter chain
+pub struct Resampler {
+    pub clock_rate: f64,
+    pub sample_rate: f64,
+    pub filters: FilterChain,
+    /// How many CPU cycles make up a single output sample
+    cycles_per_sample: f64,
+    cycle_counter: f64,
+    accumulator: f64,
+    accumulated: usize,
+    samples: Vec<f32>,
+}
+
+impl Resampler {
+    pub fn new(clock_rate: f64, sample_rate: f64) -> Self {
+        Resampler {
+            clock_rate,
+            sample_rate,
+            filters: FilterChain::new(sample_rate as f32),
+            cycles_per_sample: clock_rate / sample_rate,
+            cycle_counter: 0.0,
+            accumulator: 0.0,
+            accumulated: 0,
+            samples: Vec::with_capacity(sample_rate as usize / 30),
+        }
+    }
+
+    /// Feeds the output of a single CPU cycle
+    pub fn push(&mut self, input: f32) {
+        self.accumulator += input as f64;
+        self.accumulated += 1;
+        self.cycle_counter += 1.0;
+
+        if self.cycle_counter >= self.cycles_per_sample {
+            self.cycle_counter -= self.cycles_per_sample;
+            let sample: f32 = (self.accumulator / self.accumulated as f64) as f32;
+            self.samples.push(self.filters.process(sample));
+            self.accumulator = 0.0;
+            self.accumulated = 0;
+        }
+    }
+
+    pub fn pending_samples(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn drain_samples(&mut self) -> std::vec::Drain<'_, f32> {
+        self.samples.drain(..)
+    }
+}
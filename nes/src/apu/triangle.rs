@@ -0,0 +1,126 @@
+use super::length_counter::LengthCounter;
+use crate::save_state::{SaveState, SaveStateError, StateReader, StateWriter};
+
+// https://www.nesdev.org/wiki/APU_Triangle
+#[rustfmt::skip]
+const SEQUENCE_TABLE: [u8; 32] = [
+    15, 14, 13, 12, 11, 10,  9,  8,  7,  6,  5,  4,  3,  2,  1,  0,
+     0,  1,  2,  3,  4,  5,  6,  7,  8,  9, 10, 11, 12, 13, 14, 15,
+];
+
+pub struct Triangle {
+    pub length_counter: LengthCounter,
+    pub control: bool,
+    pub linear_reload_value: u8,
+    pub linear_counter: u8,
+    pub linear_reload: bool,
+    pub sequence_pos: u8,
+    pub timer_period: u16,
+    timer: u16,
+}
+
+impl Triangle {
+    pub fn new() -> Self {
+        Triangle {
+            length_counter: LengthCounter::new(),
+            control: false,
+            linear_reload_value: 0,
+            linear_counter: 0,
+            linear_reload: false,
+            sequence_pos: 0,
+            timer_period: 0,
+            timer: 0,
+        }
+    }
+
+    /// ```plaintext
+    /// $4008
+    /// 7  bit  0
+    /// ---- ----
+    /// CRRR RRRR
+    /// ││││ ││││
+    /// │╘╧╧═╧╧╧╧═ Linear counter reload value
+    /// ╘═════════ Control flag / Length counter halt
+    /// ```
+    pub fn write_linear(&mut self, data: u8) {
+        self.control = data & 0b1000_0000 != 0;
+        self.length_counter.halt = self.control;
+        self.linear_reload_value = data & 0b0111_1111;
+    }
+
+    pub fn write_timer_lo(&mut self, data: u8) {
+        self.timer_period = (self.timer_period & 0xFF00) | data as u16;
+    }
+
+    /// ```plaintext
+    /// $400B
+    /// 7  bit  0
+    /// ---- ----
+    /// LLLL LTTT
+    /// ││││ │╘╧╧═ Timer high 3 bits
+    /// ╘╧╧╧═╧════ Length counter load
+    /// ```
+    pub fn write_timer_hi(&mut self, data: u8) {
+        self.timer_period = (self.timer_period & 0x00FF) | ((data as u16 & 0b0000_0111) << 8);
+        self.length_counter.load(data >> 3);
+        self.linear_reload = true;
+    }
+
+    /// Clocked every CPU cycle
+    pub fn clock_timer(&mut self) {
+        if self.timer == 0 {
+            self.timer = self.timer_period;
+            if self.length_counter.is_active() && self.linear_counter > 0 {
+                self.sequence_pos = (self.sequence_pos + 1) & 0b0001_1111;
+            }
+        } else {
+            self.timer -= 1;
+        }
+    }
+
+    // https://www.nesdev.org/wiki/APU_Triangle#Linear_counter
+    pub fn clock_quarter_frame(&mut self) {
+        if self.linear_reload {
+            self.linear_counter = self.linear_reload_value;
+        } else if self.linear_counter > 0 {
+            self.linear_counter -= 1;
+        }
+        if !self.control {
+            self.linear_reload = false;
+        }
+    }
+
+    pub fn clock_half_frame(&mut self) {
+        self.length_counter.clock();
+    }
+
+    /// The triangle channel doesn't mute when halted, it holds the current step of the sequence
+    pub fn output(&self) -> u8 {
+        SEQUENCE_TABLE[self.sequence_pos as usize]
+    }
+}
+
+impl SaveState for Triangle {
+    fn write_state(&self, state: &mut StateWriter) {
+        self.length_counter.write_state(state);
+        state.bool(self.control);
+        state.u8(self.linear_reload_value);
+        state.u8(self.linear_counter);
+        state.bool(self.linear_reload);
+        state.u8(self.sequence_pos);
+        state.u16(self.timer_period);
+        state.u16(self.timer);
+    }
+
+    fn read_state(&mut self, state: &mut StateReader) -> Result<(), SaveStateError> {
+        self.length_counter.read_state(state)?;
+        self.control = state.bool()?;
+        self.linear_reload_value = state.u8()?;
+        self.linear_counter = state.u8()?;
+        self.linear_reload = state.bool()?;
+        self.sequence_pos = state.u8()?;
+        self.timer_period = state.u16()?;
+        self.timer = state.u16()?;
+        Ok(())
+    }
+}
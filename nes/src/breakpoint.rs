@@ -0,0 +1,480 @@
+//! Breakpoints and watchpoints
+//!
+//! `Bus` and `PPU` report their accesses as they happen, but only `NES::step` can see the CPU's
+//! registers, so it checks the conditions once the instruction is done. Execute breakpoints stop
+//! before the instruction runs, everything else stops right after the instruction that triggered it.
+
+use crate::{
+    RcRef,
+    cpu::{CPU, Flags, interrupt::InterruptType},
+    prelude::*,
+};
+use std::{fmt, ops::RangeInclusive, str::FromStr};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressSpace {
+    Cpu,
+    Ppu,
+}
+
+bitflags! {
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    pub struct Access: u8 {
+        const READ  = 0b01;
+        const WRITE = 0b10;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Trigger {
+    /// The CPU is about to run an instruction in the range
+    Execute(RangeInclusive<u16>),
+    Memory {
+        space: AddressSpace,
+        access: Access,
+        range: RangeInclusive<u16>,
+    },
+    Interrupt(InterruptType),
+}
+
+impl fmt::Display for Trigger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn range(range: &RangeInclusive<u16>) -> String {
+            match range.start() == range.end() {
+                true => format!("${:04X}", range.start()),
+                false => format!("${:04X}-${:04X}", range.start(), range.end()),
+            }
+        }
+
+        match self {
+            Trigger::Execute(addrs) => write!(f, "Exec {}", range(addrs)),
+            Trigger::Memory {
+                space,
+                access,
+                range: addrs,
+            } => {
+                let access: &str = match (
+                    access.contains(Access::READ),
+                    access.contains(Access::WRITE),
+                ) {
+                    (true, true) => "R/W",
+                    (true, false) => "Read",
+                    (false, _) => "Write",
+                };
+                write!(f, "{:?} {} {}", space, access, range(addrs))
+            }
+            Trigger::Interrupt(itype) => write!(f, "{:?}", itype),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Breakpoint {
+    pub trigger: Trigger,
+    pub condition: Option<Condition>,
+    pub enabled: bool,
+}
+
+impl Breakpoint {
+    pub fn new(trigger: Trigger) -> Self {
+        Breakpoint {
+            trigger,
+            condition: None,
+            enabled: true,
+        }
+    }
+
+    pub fn with_condition(mut self, condition: Condition) -> Self {
+        self.condition = Some(condition);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryAccess {
+    pub space: AddressSpace,
+    pub access: Access,
+    pub addr: u16,
+    pub value: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BreakpointHit {
+    /// Index into `Breakpoints::list()`
+    pub index: usize,
+    pub program_counter: u16,
+    /// The access that triggered a memory breakpoint
+    pub access: Option<MemoryAccess>,
+}
+
+#[derive(Debug, Default)]
+pub struct Breakpoints {
+    list: Vec<Breakpoint>,
+    /// Accesses the hooks skip entirely, so they cost next to nothing without watchpoints
+    cpu_watch: Access,
+    ppu_watch: Access,
+    /// Matches from the current instruction, whose conditions haven't been checked yet
+    pending: Vec<(usize, Option<MemoryAccess>)>,
+    hit: Option<BreakpointHit>,
+    /// The execute breakpoint that was just hit, so resuming doesn't stop on it again
+    resume_at: Option<u16>,
+}
+
+impl Breakpoints {
+    pub fn list(&self) -> &[Breakpoint] {
+        &self.list
+    }
+
+    pub fn add(&mut self, breakpoint: Breakpoint) -> usize {
+        self.list.push(breakpoint);
+        self.update_watches();
+        self.list.len() - 1
+    }
+
+    pub fn remove(&mut self, index: usize) -> Breakpoint {
+        let breakpoint: Breakpoint = self.list.remove(index);
+        self.update_watches();
+        breakpoint
+    }
+
+    pub fn set_enabled(&mut self, index: usize, enabled: bool) {
+        self.list[index].enabled = enabled;
+        self.update_watches();
+    }
+
+    /// Replaces every breakpoint, e.g. with the list from a debugger UI
+    pub fn set(&mut self, list: Vec<Breakpoint>) {
+        self.list = list;
+        // Hits and resumes belong to the old list, they would point at the wrong breakpoints
+        self.pending.clear();
+        self.hit = None;
+        self.resume_at = None;
+        self.update_watches();
+    }
+
+    pub fn clear(&mut self) {
+        self.set(Vec::new());
+    }
+
+    /// The first breakpoint that was hit since the last call
+    pub fn take_hit(&mut self) -> Option<BreakpointHit> {
+        self.hit.take()
+    }
+
+    fn update_watches(&mut self) {
+        self.cpu_watch = Access::empty();
+        self.ppu_watch = Access::empty();
+        for breakpoint in self.list.iter().filter(|bp: &&Breakpoint| bp.enabled) {
+            if let Trigger::Memory { space, access, .. } = &breakpoint.trigger {
+                match space {
+                    AddressSpace::Cpu => self.cpu_watch |= *access,
+                    AddressSpace::Ppu => self.ppu_watch |= *access,
+                }
+            }
+        }
+    }
+
+    pub fn watches(&self, space: AddressSpace, access: Access) -> bool {
+        match space {
+            AddressSpace::Cpu => self.cpu_watch.intersects(access),
+            AddressSpace::Ppu => self.ppu_watch.intersects(access),
+        }
+    }
+
+    pub(crate) fn on_access(&mut self, access: MemoryAccess) {
+        for (index, breakpoint) in self.list.iter().enumerate() {
+            if let Trigger::Memory {
+                space,
+                access: watched,
+                range,
+            } = &breakpoint.trigger
+                && breakpoint.enabled
+                && *space == access.space
+                && watched.intersects(access.access)
+                && range.contains(&access.addr)
+            {
+                self.pending.push((index, Some(access)));
+            }
+        }
+    }
+
+    pub(crate) fn on_interrupt(&mut self, itype: InterruptType) {
+        for (index, breakpoint) in self.list.iter().enumerate() {
+            if breakpoint.enabled && breakpoint.trigger == Trigger::Interrupt(itype) {
+                self.pending.push((index, None));
+            }
+        }
+    }
+
+    /// Runs before the instruction at the program counter, right after any interrupt was taken
+    pub(crate) fn check_before(&mut self, cpu: &CPU) -> bool {
+        let pc: u16 = cpu.program_counter;
+        let resuming: bool = self.resume_at.take() == Some(pc);
+        let hit: bool = self.check_pending(cpu, pc) || (!resuming && self.check_execute(cpu));
+        if hit {
+            self.resume_at = Some(pc);
+        }
+        hit
+    }
+
+    /// Runs after the instruction at `program_counter`, for the accesses it made
+    pub(crate) fn check_after(&mut self, cpu: &CPU, program_counter: u16) -> bool {
+        self.check_pending(cpu, program_counter)
+    }
+
+    fn check_execute(&mut self, cpu: &CPU) -> bool {
+        let pc: u16 = cpu.program_counter;
+        let index: Option<usize> = self.list.iter().position(|breakpoint: &Breakpoint| {
+            matches!(&breakpoint.trigger, Trigger::Execute(range) if range.contains(&pc))
+                && breakpoint.enabled
+                && breakpoint
+                    .condition
+                    .as_ref()
+                    .is_none_or(|condition: &Condition| condition.eval(cpu, None))
+        });
+        if let Some(index) = index {
+            self.report(BreakpointHit {
+                index,
+                program_counter: pc,
+                access: None,
+            });
+        }
+        index.is_some()
+    }
+
+    fn check_pending(&mut self, cpu: &CPU, program_counter: u16) -> bool {
+        if self.pending.is_empty() {
+            return false;
+        }
+
+        let pending: Vec<(usize, Option<MemoryAccess>)> = std::mem::take(&mut self.pending);
+        let hit: Option<&(usize, Option<MemoryAccess>)> =
+            pending
+                .iter()
+                .find(|(index, access): &&(usize, Option<MemoryAccess>)| {
+                    self.list[*index]
+                        .condition
+                        .as_ref()
+                        .is_none_or(|condition: &Condition| condition.eval(cpu, access.as_ref()))
+                });
+        if let Some((index, access)) = hit {
+            self.report(BreakpointHit {
+                index: *index,
+                program_counter,
+                access: *access,
+            });
+        }
+        hit.is_some()
+    }
+
+    fn report(&mut self, hit: BreakpointHit) {
+        debug!(
+            "Hit breakpoint {}: {}",
+            hit.index, self.list[hit.index].trigger
+        );
+        if self.hit.is_none() {
+            self.hit = Some(hit);
+        }
+    }
+}
+
+/// Called by `Bus` and `PPU` on every access. Debugger peeks never get here.
+pub(crate) fn watch(
+    breakpoints: &RcRef<Breakpoints>,
+    space: AddressSpace,
+    access: Access,
+    addr: u16,
+    value: u8,
+) {
+    if !breakpoints.borrow().watches(space, access) {
+        return;
+    }
+    breakpoints.borrow_mut().on_access(MemoryAccess {
+        space,
+        access,
+        addr,
+        value,
+    });
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operand {
+    A,
+    X,
+    Y,
+    SP,
+    PC,
+    P,
+    /// One bit of the status register
+    Flag(u8),
+    /// The byte that was read or written
+    Value,
+    /// The address that was read or written
+    Addr,
+    Number(u16),
+}
+
+impl Operand {
+    fn parse(text: &str) -> Result<Self, ConditionError> {
+        let text: String = text.trim().to_ascii_uppercase();
+        let number = |digits: &str, radix: u32| -> Result<Operand, ConditionError> {
+            u16::from_str_radix(digits, radix)
+                .map(Operand::Number)
+                .map_err(|_| ConditionError::UnknownOperand(text.clone()))
+        };
+
+        match text.as_str() {
+            "" => Err(ConditionError::MissingOperand),
+            "A" => Ok(Operand::A),
+            "X" => Ok(Operand::X),
+            "Y" => Ok(Operand::Y),
+            "SP" => Ok(Operand::SP),
+            "PC" => Ok(Operand::PC),
+            "P" => Ok(Operand::P),
+            "C" => Ok(Operand::Flag(Flags::CARRY.bits())),
+            "Z" => Ok(Operand::Flag(Flags::ZERO.bits())),
+            "I" => Ok(Operand::Flag(Flags::INTERRUPT_DISABLE.bits())),
+            "D" => Ok(Operand::Flag(Flags::DECIMAL_MODE.bits())),
+            "V" => Ok(Operand::Flag(Flags::OVERFLOW.bits())),
+            "N" => Ok(Operand::Flag(Flags::NEGATIVE.bits())),
+            "VALUE" => Ok(Operand::Value),
+            "ADDR" => Ok(Operand::Addr),
+            _ => match text.strip_prefix('$').or_else(|| text.strip_prefix("0X")) {
+                Some(digits) => number(digits, 16),
+                None => number(&text, 10),
+            },
+        }
+    }
+
+    fn eval(&self, cpu: &CPU, access: Option<&MemoryAccess>) -> u16 {
+        match self {
+            Operand::A => cpu.accumulator as u16,
+            Operand::X => cpu.index_x as u16,
+            Operand::Y => cpu.index_y as u16,
+            Operand::SP => cpu.stack_pointer as u16,
+            Operand::PC => cpu.program_counter,
+            Operand::P => cpu.status.bits() as u16,
+            Operand::Flag(bit) => (cpu.status.bits() & bit != 0) as u16,
+            Operand::Value => access.map_or(0, |access: &MemoryAccess| access.value as u16),
+            Operand::Addr => access.map_or(0, |access: &MemoryAccess| access.addr),
+            Operand::Number(value) => *value,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Equal,
+    NotEqual,
+    LessEqual,
+    GreaterEqual,
+    Less,
+    Greater,
+}
+
+impl Comparison {
+    /// Two character operators come first, so `<=` isn't taken for `<`
+    const ALL: [(&'static str, Comparison); 6] = [
+        ("==", Comparison::Equal),
+        ("!=", Comparison::NotEqual),
+        ("<=", Comparison::LessEqual),
+        (">=", Comparison::GreaterEqual),
+        ("<", Comparison::Less),
+        (">", Comparison::Greater),
+    ];
+
+    fn compare(&self, left: u16, right: u16) -> bool {
+        match self {
+            Comparison::Equal => left == right,
+            Comparison::NotEqual => left != right,
+            Comparison::LessEqual => left <= right,
+            Comparison::GreaterEqual => left >= right,
+            Comparison::Less => left < right,
+            Comparison::Greater => left > right,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Term {
+    left: Operand,
+    comparison: Comparison,
+    right: Operand,
+}
+
+impl Term {
+    fn parse(text: &str) -> Result<Self, ConditionError> {
+        for (symbol, comparison) in Comparison::ALL {
+            if let Some((left, right)) = text.split_once(symbol) {
+                return Ok(Term {
+                    left: Operand::parse(left)?,
+                    comparison,
+                    right: Operand::parse(right)?,
+                });
+            }
+        }
+        // A lone operand, like `C`, is true when it isn't zero
+        Ok(Term {
+            left: Operand::parse(text)?,
+            comparison: Comparison::NotEqual,
+            right: Operand::Number(0),
+        })
+    }
+}
+
+/// Comparisons of registers, flags and the accessed `VALUE`/`ADDR`, joined by `&&` and `||`,
+/// e.g. `A == $10 && X >= 3 || C`. `&&` binds tighter than `||`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Condition {
+    text: String,
+    any_of: Vec<Vec<Term>>,
+}
+
+impl Condition {
+    pub fn eval(&self, cpu: &CPU, access: Option<&MemoryAccess>) -> bool {
+        self.any_of.iter().any(|all_of: &Vec<Term>| {
+            all_of.iter().all(|term: &Term| {
+                term.comparison
+                    .compare(term.left.eval(cpu, access), term.right.eval(cpu, access))
+            })
+        })
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+impl FromStr for Condition {
+    type Err = ConditionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let any_of: Vec<Vec<Term>> = s
+            .split("||")
+            .map(|all_of: &str| all_of.split("&&").map(Term::parse).collect())
+            .collect::<Result<Vec<Vec<Term>>, ConditionError>>()?;
+        Ok(Condition {
+            text: s.trim().to_string(),
+            any_of,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConditionError {
+    MissingOperand,
+    UnknownOperand(String),
+}
+
+impl fmt::Display for ConditionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConditionError::MissingOperand => write!(f, "Missing operand"),
+            ConditionError::UnknownOperand(operand) => write!(f, "Unknown operand '{}'", operand),
+        }
+    }
+}
+
+impl std::error::Error for ConditionError {}
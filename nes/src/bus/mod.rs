@@ -1,10 +1,13 @@
 use crate::{
-    BoxMapper, RcRef,
-    apu::APU,
+    BoxMapper, BoxNESDevice, RcRef,
+    apu::{APU, dmc},
+    breakpoint::{self, Access, AddressSpace, Breakpoints},
     cpu::interrupt::{self, Interrupt},
-    memory::Memory,
+    memory::{Memory, mem_map::*},
     ppu::{PPU, renderer::Renderer},
     prelude::*,
+    save_state::{SaveState, SaveStateError, StateReader, StateWriter},
+    symbols::Symbols,
 };
 
 pub static mut QUIET_LOG: bool = false;
@@ -27,6 +30,8 @@ macro_rules! bus_trace {
 
 #[rustfmt::skip]
 impl NESAccess for Bus {
+    fn apu(&self) -> Ref<'_, APU> { self.apu.borrow() }
+    fn apu_mut(&self) -> RefMut<'_, APU> { self.apu.borrow_mut() }
     fn ppu(&self) -> Ref<'_, PPU> { self.ppu.borrow() }
     fn ppu_mut(&self) -> RefMut<'_, PPU> { self.ppu.borrow_mut() }
     fn mapper(&self) -> Ref<'_, BoxMapper> { self.mapper.borrow() }
@@ -35,6 +40,24 @@ impl NESAccess for Bus {
     fn memory_mut(&self) -> RefMut<'_, Memory> { self.memory.borrow_mut() }
     fn renderer(&self) -> Ref<'_, Renderer> { self.renderer.borrow() }
     fn renderer_mut(&self) -> RefMut<'_, Renderer> { self.renderer.borrow_mut() }
+    fn breakpoints(&self) -> Ref<'_, Breakpoints> { self.breakpoints.borrow() }
+    fn breakpoints_mut(&self) -> RefMut<'_, Breakpoints> { self.breakpoints.borrow_mut() }
+    fn device1(&self) -> Ref<'_, BoxNESDevice> {
+        assert!(self.device1.is_some(), "Bus tried to access `Device 1` before a reference was passed to it!");
+        self.device1.as_ref().unwrap().borrow()
+    }
+    fn device1_mut(&self) -> RefMut<'_, BoxNESDevice> {
+        assert!(self.device1.is_some(), "Bus tried to access `Device 1` before a reference was passed to it!");
+        self.device1.as_ref().unwrap().borrow_mut()
+    }
+    fn device2(&self) -> Ref<'_, BoxNESDevice> {
+        assert!(self.device2.is_some(), "Bus tried to access `Device 2` before a reference was passed to it!");
+        self.device2.as_ref().unwrap().borrow()
+    }
+    fn device2_mut(&self) -> RefMut<'_, BoxNESDevice> {
+        assert!(self.device2.is_some(), "Bus tried to access `Device 2` before a reference was passed to it!");
+        self.device2.as_ref().unwrap().borrow_mut()
+    }
 }
 
 pub struct Bus {
@@ -42,7 +65,13 @@ pub struct Bus {
     pub memory: RcRef<Memory>,
     pub mapper: RcRef<BoxMapper>,
     pub renderer: RcRef<Renderer>,
+    pub apu: RcRef<APU>,
     pub ppu: RcRef<PPU>,
+    pub breakpoints: RcRef<Breakpoints>,
+    /// Labels shown by the disassembler
+    pub symbols: Symbols,
+    device1: Option<RcRef<BoxNESDevice>>,
+    device2: Option<RcRef<BoxNESDevice>>,
     #[allow(clippy::type_complexity)]
     render_callback: Box<dyn FnMut()>,
 }
@@ -52,15 +81,21 @@ impl Bus {
         memory: RcRef<Memory>,
         mapper: RcRef<BoxMapper>,
         renderer: RcRef<Renderer>,
-        _apu: RcRef<APU>,
+        apu: RcRef<APU>,
         ppu: RcRef<PPU>,
+        breakpoints: RcRef<Breakpoints>,
     ) -> Bus {
         Bus {
             cpu_cycles: 0,
             memory,
             mapper,
             renderer,
+            apu,
             ppu,
+            breakpoints,
+            symbols: Symbols::default(),
+            device1: None,
+            device2: None,
             render_callback: Box::from(|| {}),
         }
     }
@@ -72,16 +107,42 @@ impl Bus {
         self.render_callback = Box::new(callback);
     }
 
+    pub fn connect_input_device(&mut self, port: u8, device: RcRef<BoxNESDevice>) {
+        assert!((1..=2).contains(&port));
+        match port {
+            1 => self.device1 = Some(device),
+            2 => self.device2 = Some(device),
+            _ => panic!("This shouldn't happen!"),
+        };
+    }
+
     pub fn tick(&mut self, cpu_cycles: usize) {
-        self.cpu_cycles += cpu_cycles;
+        let mut remaining_cycles: usize = cpu_cycles;
+
+        while remaining_cycles > 0 {
+            remaining_cycles -= 1;
+            self.cpu_cycles += 1;
+            self.apu_mut().tick();
+
+            for _ in 0..3 {
+                let nmi_before: bool = self.ppu().nmi_interrupt.is_some();
+                self.ppu_mut().tick(self.renderer_mut());
+                let nmi_after: bool = self.ppu().nmi_interrupt.is_some();
 
-        for _ in 0..cpu_cycles * 3 {
-            let nmi_before: bool = self.ppu().nmi_interrupt.is_some();
-            self.ppu_mut().tick(self.renderer_mut());
-            let nmi_after: bool = self.ppu().nmi_interrupt.is_some();
+                if !nmi_before && nmi_after {
+                    (self.render_callback)();
+                }
+            }
 
-            if !nmi_before && nmi_after {
-                (self.render_callback)();
+            // https://www.nesdev.org/wiki/APU_DMC#Memory_reader
+            let dma_request: Option<u16> = self.apu().dmc.dma_request();
+            if let Some(addr) = dma_request {
+                // Not a CPU access, so it doesn't trigger watchpoints
+                let byte: u8 = self.read_mapped(addr);
+                bus_trace!("[DMC] Fetched {:#04X} from {:#06X}", byte, addr);
+                self.apu_mut().dmc.dma_load(byte);
+                // The CPU is halted while the DMC steals the bus
+                remaining_cycles += dmc::DMA_STALL_CYCLES;
             }
         }
     }
@@ -90,6 +151,9 @@ impl Bus {
         if self.ppu_mut().poll_nmi_interrupt().is_some() {
             return Some(interrupt::NMI);
         }
+        if self.apu().irq_pending() || self.mapper().irq_pending() {
+            return Some(interrupt::IRQ);
+        }
         None
     }
 
@@ -107,10 +171,259 @@ impl Bus {
     }
 
     pub fn read(&mut self, addr: u16) -> u8 {
-        self.mapper_mut().read(addr)
+        let byte: u8 = self.read_mapped(addr);
+        breakpoint::watch(
+            &self.breakpoints,
+            AddressSpace::Cpu,
+            Access::READ,
+            addr,
+            byte,
+        );
+        byte
+    }
+
+    fn read_mapped(&mut self, addr: u16) -> u8 {
+        match addr {
+            RAM..=RAM_END => {
+                let mirror_down_addr: u16 = addr & 0b0000_0111_1111_1111;
+                let byte: u8 = self.memory().cpu_vram[mirror_down_addr as usize];
+                bus_trace!(
+                    "[RAM] Read {:#04X} from {:#06X} ({:#06X})",
+                    byte,
+                    addr,
+                    mirror_down_addr
+                );
+                byte
+            }
+
+            MMIO_PPUCTRL | MMIO_PPUMASK | MMIO_OAMADDR | MMIO_PPUSCROLL | MMIO_PPUADDR
+            | MMIO_OAMDMA => {
+                error!(
+                    "Attempted to read from write-only PPU address {:#06X}",
+                    addr
+                );
+                0
+            }
+            MMIO_PPUSTATUS => {
+                let byte: u8 = self.ppu_mut().read_status();
+                bus_trace!("[PPU] Read {:#04X} from {:#06X} (PPU Status)", byte, addr);
+                byte
+            }
+            MMIO_OAMDATA => {
+                let byte: u8 = self.ppu().read_oam_data();
+                bus_trace!("[PPU] Read {:#04X} from {:#06X} (PPU OAM Data)", byte, addr);
+                byte
+            }
+            MMIO_PPUDATA => {
+                let byte: u8 = self.ppu_mut().read_data();
+                bus_trace!("[PPU] Read {:#04X} from {:#06X} (PPU Data)", byte, addr);
+                byte
+            }
+            PPU_REGISTERS_MIRROR..=PPU_REGISTERS_END => {
+                let mirror_down_addr: u16 = addr & 0b0010_0000_0000_0111;
+                bus_trace!(
+                    "[PPU] Mirroring down read at {:#06X} to {:#06X}",
+                    addr,
+                    mirror_down_addr
+                );
+                self.read(mirror_down_addr)
+            }
+
+            MMIO_SND_CHN => {
+                let byte: u8 = self.apu_mut().read_status();
+                bus_trace!("[APU] Read {:#04X} from {:#06X} (APU Status)", byte, addr);
+                byte
+            }
+            APU_REGISTERS..MMIO_SND_CHN => {
+                // warn!("[APU] Ignoring bus read at {:#06X}", addr);
+                0
+            }
+
+            MMIO_JOY1 => {
+                if self.device1.is_some() {
+                    self.device1_mut().read()
+                } else {
+                    0x00
+                }
+            }
+            MMIO_JOY2 => {
+                if self.device2.is_some() {
+                    self.device2_mut().read()
+                } else {
+                    0x00
+                }
+            }
+
+            EXPANSION_ROM..=PRG_ROM_END => self.mapper_mut().cpu_read(addr),
+
+            _ => {
+                warn!("Ignoring bus read at {:#06X}", addr);
+                0
+            }
+        }
     }
 
     pub fn write(&mut self, addr: u16, data: u8) {
-        self.mapper_mut().write(addr, data);
+        breakpoint::watch(
+            &self.breakpoints,
+            AddressSpace::Cpu,
+            Access::WRITE,
+            addr,
+            data,
+        );
+        match addr {
+            RAM..=RAM_END => {
+                let mirror_down_addr: u16 = addr & 0b0000_0111_1111_1111;
+                self.memory_mut().cpu_vram[mirror_down_addr as usize] = data;
+                bus_trace!(
+                    "[RAM] Wrote {:#04X} to {:#06X} ({:#06X})",
+                    data,
+                    addr,
+                    mirror_down_addr
+                );
+            }
+
+            PPU_REGISTERS => {
+                self.ppu_mut().write_to_ctrl(data);
+                bus_trace!(
+                    "[PPU] Wrote {:#04X} to {:#06X} (PPU Control Register)",
+                    data,
+                    addr
+                );
+            }
+            MMIO_PPUMASK => {
+                self.ppu_mut().write_to_mask(data);
+                bus_trace!(
+                    "[PPU] Wrote {:#04X} to {:#06X} (PPU Mask Register)",
+                    data,
+                    addr
+                );
+            }
+            MMIO_PPUSTATUS => error!(
+                "[PPU] Attempted to write {:#04X} to PPU status register ({:#06X})",
+                data, MMIO_PPUSTATUS
+            ),
+            MMIO_OAMADDR => {
+                self.ppu_mut().write_to_oam_addr(data);
+                bus_trace!(
+                    "[PPU] Wrote {:#04X} to {:#06X} (PPU OAM Address)",
+                    data,
+                    addr
+                );
+            }
+            MMIO_OAMDATA => {
+                self.ppu_mut().write_to_oam_data(data);
+                bus_trace!("[PPU] Wrote {:#04X} to {:#06X} (PPU OAM Data)", data, addr);
+            }
+            MMIO_PPUSCROLL => {
+                self.ppu_mut().write_to_scroll(data);
+                bus_trace!(
+                    "[PPU] Wrote {:#04X} to {:#06X} (PPU Scroll Register)",
+                    data,
+                    addr
+                );
+            }
+            MMIO_PPUADDR => {
+                self.ppu_mut().write_to_ppu_addr(data);
+                bus_trace!("[PPU] Wrote {:#04X} to {:#06X} (PPU Address)", data, addr);
+            }
+            MMIO_PPUDATA => {
+                self.ppu_mut().write_to_data(data);
+                bus_trace!("[PPU] Wrote {:#04X} to {:#06X} (PPU Data)", data, addr);
+            }
+            PPU_REGISTERS_MIRROR..=PPU_REGISTERS_END => {
+                let mirror_down_addr: u16 = addr & 0b0010_0000_0000_0111;
+                bus_trace!(
+                    "[PPU] Mirroring down write at {:#06X} to {:#06X}",
+                    addr,
+                    mirror_down_addr
+                );
+                self.write(mirror_down_addr, data);
+            }
+
+            APU_REGISTERS..=MMIO_DMC_LEN | APU_REGISTERS_END => {
+                self.apu_mut().write_register(addr, data);
+                bus_trace!("[APU] Wrote {:#04X} to {:#06X}", data, addr);
+            }
+
+            // https://wiki.nesdev.com/w/index.php/PPU_programmer_reference#OAM_DMA_.28.244014.29_.3E_write
+            MMIO_OAMDMA => {
+                let mut buffer: [u8; 256] = [0; 256];
+                let hi: u16 = (data as u16) << 8;
+                for i in 0..256u16 {
+                    buffer[i as usize] = self.read(hi + i);
+                }
+
+                self.ppu_mut().write_oam_dma(&buffer);
+
+                // TODO: Handle this eventually
+                // let add_cycles: u16 = if bus.cpu_cycles % 2 == 1 { 514 } else { 513 };
+                // bus.tick(add_cycles); // TODO: This will cause weird effects as PPU will have 513/514 * 3 ticks
+            }
+
+            MMIO_JOY1 => {
+                if self.device1.is_some() {
+                    self.device1_mut().write(data);
+                }
+                if self.device2.is_some() {
+                    self.device2_mut().write(data);
+                }
+            }
+            // `$4017` is the APU frame counter when written to
+            MMIO_JOY2 => {
+                self.apu_mut().write_register(addr, data);
+                bus_trace!(
+                    "[APU] Wrote {:#04X} to {:#06X} (APU Frame Counter)",
+                    data,
+                    addr
+                );
+            }
+
+            EXPANSION_ROM..=PRG_ROM_END => self.mapper_mut().cpu_write(addr, data),
+
+            _ => warn!("Ignoring bus write at {:#06X}", addr),
+        }
+    }
+
+    /// What a CPU read from `addr` would return, without any of the read's side-effects.
+    /// Nothing is logged and no breakpoints are tripped, so debuggers can call it freely.
+    pub fn peek(&self, addr: u16) -> u8 {
+        match addr {
+            RAM..=RAM_END => self.memory().cpu_vram[(addr & 0b0000_0111_1111_1111) as usize],
+            PPU_REGISTERS..=PPU_REGISTERS_END => self.ppu().peek_register(addr),
+            MMIO_SND_CHN => self.apu().peek_status(),
+            MMIO_JOY1 if self.device1.is_some() => self.device1().peek(),
+            MMIO_JOY2 if self.device2.is_some() => self.device2().peek(),
+            EXPANSION_ROM..=PRG_ROM_END => self.mapper().cpu_peek(addr),
+            _ => 0,
+        }
+    }
+
+    /// Writes `data` straight into whatever storage `addr` maps to, PRG-ROM included.
+    /// Registers have no storage behind them, so pokes to them are dropped and this
+    /// returns `false`.
+    pub fn poke(&mut self, addr: u16, data: u8) -> bool {
+        match addr {
+            RAM..=RAM_END => {
+                self.memory_mut().cpu_vram[(addr & 0b0000_0111_1111_1111) as usize] = data;
+                true
+            }
+            EXPANSION_ROM..=PRG_ROM_END => self.mapper_mut().cpu_poke(addr, data),
+            _ => {
+                debug!("Ignoring poke to register {:#06X}", addr);
+                false
+            }
+        }
+    }
+}
+
+impl SaveState for Bus {
+    fn write_state(&self, state: &mut StateWriter) {
+        state.usize(self.cpu_cycles);
+    }
+
+    fn read_state(&mut self, state: &mut StateReader) -> Result<(), SaveStateError> {
+        self.cpu_cycles = state.usize()?;
+        Ok(())
     }
 }
@@ -19,7 +19,46 @@ macro_rules! opt_debug {
 pub enum Mirroring {
     Vertical,
     Horizontal,
-    None,
+    /// Every nametable maps to the first page of CIRAM
+    SingleScreenA,
+    /// Every nametable maps to the second page of CIRAM
+    SingleScreenB,
+    /// The cartridge adds 2 KiB of VRAM, so all four nametables are unique
+    FourScreen,
+    /// The mapper decides where each nametable access goes, see
+    /// [`Mapper::nametable_read`](crate::mapper::Mapper::nametable_read)
+    MapperControlled,
+}
+
+impl Mirroring {
+    // https://www.nesdev.org/wiki/Mirroring#Nametable_Mirroring
+    // Horizontal:
+    //   [ A ] [ a ]
+    //   [ B ] [ b ]
+    // Vertical:
+    //   [ A ] [ B ]
+    //   [ a ] [ b ]
+    // Single-Screen A/B:
+    //   [ A ] [ a ]
+    //   [ a ] [ a ]
+    // Four-Screen:
+    //   [ A ] [ B ]
+    //   [ C ] [ D ]
+    /// Maps a nametable address (`$2000-$3EFF`) to an index into the PPU's nametable VRAM.
+    /// Mapper-controlled mirroring is the mapper's to resolve, mappers that leave it to this
+    /// get all four nametables as if the cartridge had four-screen VRAM.
+    pub fn vram_index(&self, addr: u16) -> usize {
+        let vram_index: u16 = addr & 0x0FFF; // mirror down 0x3000-0x3EFF to 0x2000-0x2EFF
+        let offset: u16 = vram_index & 0x03FF;
+        let index: u16 = match self {
+            Mirroring::Vertical => vram_index & 0x07FF,
+            Mirroring::Horizontal => ((vram_index & 0x0800) >> 1) | offset,
+            Mirroring::SingleScreenA => offset,
+            Mirroring::SingleScreenB => 0x0400 | offset,
+            Mirroring::FourScreen | Mirroring::MapperControlled => vram_index,
+        };
+        index as usize
+    }
 }
 
 #[derive(Debug)]
@@ -30,6 +69,17 @@ pub enum ROMRegion {
     Dual,
 }
 
+impl ROMRegion {
+    /// Frames per second on the console the ROM was made for, dual-region ROMs run as NTSC
+    // https://www.nesdev.org/wiki/Cycle_reference_chart
+    pub fn frame_rate(&self) -> f64 {
+        match self {
+            ROMRegion::NTSC | ROMRegion::Dual => 60.0988,
+            ROMRegion::PAL => 50.007,
+        }
+    }
+}
+
 pub struct ROM {
     pub ines_ver: u8,
     pub mapper: u16,
@@ -56,17 +106,13 @@ impl ROM {
 
         let uses_bat_mem: bool = raw[6] & 0b0000_0010 != 0;
         let uses_trainer: bool = raw[6] & 0b0000_0100 != 0;
-        let alt_nametable_layout: bool = raw[6] & 0b0000_1000 != 0;
+        let four_screen: bool = raw[6] & 0b0000_1000 != 0;
         let vertical_mirroring: bool = raw[6] & 0b0000_0001 != 0;
-        let screen_mirroring: Mirroring =
-            match (alt_nametable_layout, vertical_mirroring) {
-                (true, _) => return Err(
-                    "This ROM uses the mapper specific \"Alternative Nametable Layout\" feature!"
-                        .to_string(),
-                ),
-                (false, true) => Mirroring::Vertical,
-                (false, false) => Mirroring::Horizontal,
-            };
+        let screen_mirroring: Mirroring = match (four_screen, vertical_mirroring) {
+            (true, _) => Mirroring::FourScreen,
+            (false, true) => Mirroring::Vertical,
+            (false, false) => Mirroring::Horizontal,
+        };
 
         if ines_ver == 1 {
             match raw[7] & 0b0000_0011 {
@@ -173,7 +219,7 @@ impl ROM {
                     0xC => return Err("The Famicom Network System is not supported!".to_string()),
                     _ => {
                         return Err(format!(
-                            "ROM provides invalid extended console type ({:#3X}) for iNES 2.0 format!",
+                            "ROM provides invalid extended console type ({:#X}) for iNES 2.0 format!",
                             raw[13] & 0b0000_1111
                         ));
                     }
@@ -6,7 +6,12 @@ use self::{
     interrupt::Interrupt,
     opcode::{AddressingMode::*, Instruction::*, OpCode},
 };
-use crate::{RcRef, bus::Bus, prelude::*};
+use crate::{
+    RcRef,
+    bus::Bus,
+    prelude::*,
+    save_state::{SaveState, SaveStateError, StateReader, StateWriter},
+};
 
 bitflags! {
     /// Status Register (P) - http://wiki.nesdev.com/w/index.php/Status_flags
@@ -87,6 +92,7 @@ impl CPU {
     }
 
     fn interrupt(&mut self, interrupt: Interrupt) {
+        self.bus().breakpoints_mut().on_interrupt(interrupt.itype);
         common::stack_push_u16(self, self.program_counter);
 
         let mut flag: Flags = self.status.clone();
@@ -108,7 +114,10 @@ impl CPU {
         }
 
         let pending_interrupt: Option<Interrupt> = self.bus_mut().poll_interrupts();
-        if let Some(interrupt) = pending_interrupt {
+        if let Some(interrupt) = pending_interrupt
+            // IRQs are masked by the interrupt disable flag, NMIs aren't
+            && !(interrupt == interrupt::IRQ && self.status.contains(Flags::INTERRUPT_DISABLE))
+        {
             self.interrupt(interrupt);
         }
     }
@@ -581,3 +590,28 @@ impl CPU {
         };
     }
 }
+
+impl SaveState for CPU {
+    fn write_state(&self, state: &mut StateWriter) {
+        state.bool(self.running);
+        state.u8(self.accumulator);
+        state.u8(self.index_x);
+        state.u8(self.index_y);
+        state.u8(self.stack_pointer);
+        state.u16(self.program_counter);
+        state.u8(self.status.bits());
+        state.bool(self.fresh);
+    }
+
+    fn read_state(&mut self, state: &mut StateReader) -> Result<(), SaveStateError> {
+        self.running = state.bool()?;
+        self.accumulator = state.u8()?;
+        self.index_x = state.u8()?;
+        self.index_y = state.u8()?;
+        self.stack_pointer = state.u8()?;
+        self.program_counter = state.u16()?;
+        self.status = Flags::from_bits_retain(state.u8()?);
+        self.fresh = state.bool()?;
+        Ok(())
+    }
+}
@@ -1,12 +1,13 @@
 use crate::{
     input_device::{NESDevice, NESDeviceButton, NESDeviceType},
     prelude::*,
+    save_state::{SaveState, SaveStateError, StateReader, StateWriter},
 };
 use std::any::Any;
 
 bitflags! {
     // https://wiki.nesdev.com/w/index.php/Controller_reading_code
-    #[derive(Clone, Copy, PartialEq)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     pub struct JoypadButton: u8 {
         const RIGHT    = 0b1000_0000;
         const LEFT     = 0b0100_0000;
@@ -55,17 +56,20 @@ impl Joypad {
 
 impl NESDevice for Joypad {
     fn read(&mut self) -> u8 {
-        if self.button_index > 7 {
-            return 1;
-        }
-        let response: u8 =
-            (self.button_status.bits() & (1 << self.button_index)) >> self.button_index;
+        let response: u8 = self.peek();
         if !self.strobe && self.button_index <= 7 {
             self.button_index += 1;
         }
         response
     }
 
+    fn peek(&self) -> u8 {
+        if self.button_index > 7 {
+            return 1;
+        }
+        (self.button_status.bits() & (1 << self.button_index)) >> self.button_index
+    }
+
     fn write(&mut self, data: u8) {
         self.strobe = data & 1 == 1;
         if self.strobe {
@@ -83,3 +87,17 @@ impl NESDevice for Joypad {
         }
     }
 }
+
+/// The pressed buttons belong to the player, not the machine, so they aren't saved
+impl SaveState for Joypad {
+    fn write_state(&self, state: &mut StateWriter) {
+        state.bool(self.strobe);
+        state.u8(self.button_index);
+    }
+
+    fn read_state(&mut self, state: &mut StateReader) -> Result<(), SaveStateError> {
+        self.strobe = state.bool()?;
+        self.button_index = state.u8()?;
+        Ok(())
+    }
+}
@@ -1,5 +1,5 @@
 pub mod joypad;
-use crate::BoxNESDevice;
+use crate::{BoxNESDevice, save_state::SaveState};
 use std::any::Any;
 
 #[derive(Debug, Clone, Copy)]
@@ -7,8 +7,10 @@ pub enum NESDeviceType {
     Joypad,
 }
 
-pub trait NESDevice {
+pub trait NESDevice: SaveState {
     fn read(&mut self) -> u8;
+    /// What [`NESDevice::read`] would return, without shifting the device's state
+    fn peek(&self) -> u8;
     fn write(&mut self, data: u8);
     fn get_type(&self) -> NESDeviceType;
     fn set_button_pressed_status(&mut self, button: Box<dyn NESDeviceButton>, pressed: bool);
@@ -9,19 +9,25 @@ pub mod prelude {
     };
 }
 pub mod apu;
+pub mod breakpoint;
 pub mod bus;
 pub mod cartridge;
 pub mod cpu;
 pub mod input_device;
 pub mod mapper;
 pub mod memory;
+pub mod movie;
 pub mod ppu;
+pub mod rewind;
+pub mod save_state;
+pub mod symbols;
 pub mod tools;
 #[cfg(test)]
 mod unit_tests;
 
 use crate::{
     apu::APU,
+    breakpoint::{BreakpointHit, Breakpoints},
     bus::Bus,
     cartridge::ROM,
     cpu::CPU,
@@ -29,7 +35,9 @@ use crate::{
     memory::Memory,
     ppu::{PPU, renderer::Renderer},
     prelude::*,
+    save_state::{SaveStateError, StateReader, StateWriter, read_chunk, write_chunk},
 };
+use std::collections::HashMap;
 
 pub type RcRef<T> = Rc<RefCell<T>>;
 pub type BoxNESDevice = Box<dyn NESDevice>;
@@ -38,6 +46,8 @@ pub type BoxMapper = Box<dyn Mapper>;
 pub const DO_BUS_TRACE: bool = false;
 pub const SCREEN_WIDTH: usize = 256;
 pub const SCREEN_HEIGHT: usize = 240;
+/// First scanline of vblank, right after the last visible frame was drawn
+pub const VBLANK_SCANLINE: usize = 241;
 
 #[rustfmt::skip]
 impl NESAccess for NES {
@@ -71,6 +81,8 @@ impl NESAccess for NES {
     }
     fn renderer(&self) -> Ref<'_, Renderer> { self.renderer.borrow() }
     fn renderer_mut(&self) -> RefMut<'_, Renderer> { self.renderer.borrow_mut() }
+    fn breakpoints(&self) -> Ref<'_, Breakpoints> { self.breakpoints.borrow() }
+    fn breakpoints_mut(&self) -> RefMut<'_, Breakpoints> { self.breakpoints.borrow_mut() }
 }
 
 pub struct NES {
@@ -83,10 +95,10 @@ pub struct NES {
 
     pub cpu: CPU,
     pub bus: Rc<RefCell<Bus>>,
-    /// ***CURRENTLY UNIMPLEMENTED***
     pub apu: Rc<RefCell<APU>>,
     pub ppu: Rc<RefCell<PPU>>,
     pub rom: Rc<RefCell<ROM>>,
+    pub breakpoints: Rc<RefCell<Breakpoints>>,
 }
 
 impl NES {
@@ -94,23 +106,21 @@ impl NES {
         let rom: RcRef<ROM> = Rc::new(RefCell::new(rom));
         let memory: RcRef<Memory> = Rc::new(RefCell::new(Memory::new(rom.borrow())));
         let renderer: RcRef<Renderer> = Rc::new(RefCell::new(Renderer::new()));
+        let breakpoints: RcRef<Breakpoints> = Rc::new(RefCell::new(Breakpoints::default()));
 
         let apu: RcRef<APU> = Rc::new(RefCell::new(APU::new()));
-        let ppu: RcRef<PPU> = Rc::new(RefCell::new({
-            let rom: RcRef<ROM> = rom.clone();
-            PPU::new(memory.clone(), rom.borrow().screen_mirroring)
-        }));
         let mapper: RcRef<BoxMapper> = Rc::new(RefCell::new(mapper::init_mapper(
             rom.borrow(),
             memory.clone(),
-            ppu.clone(),
         )));
+        let ppu: RcRef<PPU> = Rc::new(RefCell::new(PPU::new(mapper.clone(), breakpoints.clone())));
         let bus: RcRef<Bus> = Rc::new(RefCell::new(Bus::new(
             memory.clone(),
             mapper.clone(),
             renderer.clone(),
             apu.clone(),
             ppu.clone(),
+            breakpoints.clone(),
         )));
         let cpu: CPU = CPU::new(bus.clone());
 
@@ -127,6 +137,7 @@ impl NES {
             apu,
             ppu,
             rom,
+            breakpoints,
         }
     }
 
@@ -153,17 +164,47 @@ impl NES {
         }
     }
 
+    /// Runs one instruction. If a breakpoint is hit before it, nothing runs until the next call,
+    /// see `take_breakpoint_hit()`.
     pub fn step(&mut self, mut callback: impl FnMut(&mut CPU)) -> bool {
         self.cpu.pre_step();
+        if self.breakpoints_mut().check_before(&self.cpu) {
+            return self.cpu.running;
+        }
+
         callback(&mut self.cpu);
         if self.cpu.running {
+            let program_counter: u16 = self.cpu.program_counter;
             self.cpu.step();
+            self.breakpoints_mut()
+                .check_after(&self.cpu, program_counter);
             true
         } else {
             false
         }
     }
 
+    /// The first breakpoint that was hit since the last call
+    pub fn take_breakpoint_hit(&mut self) -> Option<BreakpointHit> {
+        self.breakpoints_mut().take_hit()
+    }
+
+    /// Steps until the PPU enters vblank, whether or not the ROM has NMIs enabled.
+    /// Returns `false` if the CPU stopped first.
+    pub fn step_frame(&mut self, mut callback: impl FnMut(&mut CPU)) -> bool {
+        let mut in_vblank: bool = self.ppu().scanline >= VBLANK_SCANLINE;
+        loop {
+            if !self.step(&mut callback) {
+                return false;
+            }
+            let was_in_vblank: bool = in_vblank;
+            in_vblank = self.ppu().scanline >= VBLANK_SCANLINE;
+            if in_vblank && !was_in_vblank {
+                return true;
+            }
+        }
+    }
+
     pub fn render_callback<F>(&self, callback: F)
     where
         F: FnMut(
@@ -188,19 +229,101 @@ impl NES {
         });
     }
 
+    /// Returns the battery-backed PRG-RAM, or `None` if the cartridge has no battery
+    pub fn battery_ram(&self) -> Option<Vec<u8>> {
+        match self.rom().uses_bat_mem {
+            true => Some(self.memory().prg_ram.clone()),
+            false => None,
+        }
+    }
+
+    /// Restores battery-backed PRG-RAM from a previous `battery_ram()`
+    pub fn load_battery_ram(&mut self, data: &[u8]) {
+        if !self.rom().uses_bat_mem {
+            warn!("Ignoring battery RAM for a cartridge without a battery");
+            return;
+        }
+
+        let mut memory: RefMut<Memory> = self.memory_mut();
+        if data.len() != memory.prg_ram.len() {
+            warn!(
+                "Battery RAM is {} bytes, but the cartridge has {} bytes of PRG-RAM",
+                data.len(),
+                memory.prg_ram.len()
+            );
+        }
+        let len: usize = data.len().min(memory.prg_ram.len());
+        memory.prg_ram[..len].copy_from_slice(&data[..len]);
+    }
+
+    /// Snapshots the whole machine, see [`save_state`](crate::save_state) for the format
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut state: StateWriter = StateWriter::new();
+        state.header(&self.rom());
+
+        write_chunk(&mut state, *b"CPU ", &self.cpu);
+        write_chunk(&mut state, *b"BUS ", &*self.bus());
+        write_chunk(&mut state, *b"PPU ", &*self.ppu());
+        write_chunk(&mut state, *b"REND", &*self.renderer());
+        write_chunk(&mut state, *b"APU ", &*self.apu());
+        write_chunk(&mut state, *b"MEM ", &*self.memory());
+        write_chunk(&mut state, *b"MAPR", &**self.mapper());
+        if self.device1.is_some() {
+            write_chunk(&mut state, *b"DEV1", &**self.device1());
+        }
+        if self.device2.is_some() {
+            write_chunk(&mut state, *b"DEV2", &**self.device2());
+        }
+
+        state.into_bytes()
+    }
+
+    /// Restores a state from `save_state()`. If it can't be loaded, the machine is left as it was.
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), SaveStateError> {
+        let backup: Vec<u8> = self.save_state();
+        let result: Result<(), SaveStateError> = self.read_state(data);
+        if let Err(err) = &result {
+            warn!("Failed to load save state: {}", err);
+            self.read_state(&backup)
+                .expect("Failed to restore the machine after a bad save state!");
+        }
+        result
+    }
+
+    fn read_state(&mut self, data: &[u8]) -> Result<(), SaveStateError> {
+        let mut state: StateReader = StateReader::new(data);
+        state.header(&self.rom())?;
+        let mut chunks: HashMap<[u8; 4], StateReader> = state.chunks()?;
+
+        read_chunk(&mut chunks, *b"CPU ", &mut self.cpu)?;
+        read_chunk(&mut chunks, *b"BUS ", &mut *self.bus_mut())?;
+        read_chunk(&mut chunks, *b"PPU ", &mut *self.ppu_mut())?;
+        read_chunk(&mut chunks, *b"REND", &mut *self.renderer_mut())?;
+        read_chunk(&mut chunks, *b"APU ", &mut *self.apu_mut())?;
+        read_chunk(&mut chunks, *b"MEM ", &mut *self.memory_mut())?;
+        read_chunk(&mut chunks, *b"MAPR", &mut **self.mapper_mut())?;
+        // Devices are plugged in by the host, so only restore the ones that are connected now
+        if self.device1.is_some() && chunks.contains_key(b"DEV1") {
+            read_chunk(&mut chunks, *b"DEV1", &mut **self.device1_mut())?;
+        }
+        if self.device2.is_some() && chunks.contains_key(b"DEV2") {
+            read_chunk(&mut chunks, *b"DEV2", &mut **self.device2_mut())?;
+        }
+        Ok(())
+    }
+
     pub fn connect_input_device(&mut self, port: u8, device_type: NESDeviceType) {
         assert!((1..=2).contains(&port));
 
         let device: RcRef<BoxNESDevice> =
             Rc::new(RefCell::new(input_device::new_device(device_type)));
         match port {
-            1 => self.device1 = Some(device),
-            2 => self.device2 = Some(device),
+            1 => self.device1 = Some(device.clone()),
+            2 => self.device2 = Some(device.clone()),
             _ => panic!("This shouldn't happen!"),
         }
 
-        self.mapper_mut()
-            .connect_input_device(port, self.device1.clone().unwrap());
+        self.bus_mut().connect_input_device(port, device);
     }
 }
 
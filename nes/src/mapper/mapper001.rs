@@ -1 +1,319 @@
-
+use super::Mapper;
+use crate::{
+    RcRef, bus_trace,
+    cartridge::{Mirroring, PRG_RAM_PAGE_SIZE, ROM},
+    memory::{Memory, mem_map::*},
+    prelude::*,
+    save_state::{SaveState, SaveStateError, StateReader, StateWriter},
+};
+
+#[rustfmt::skip]
+impl NESAccess for Mapper001 {
+    fn memory(&self) -> Ref<'_, Memory> { self.memory.borrow() }
+    fn memory_mut(&self) -> RefMut<'_, Memory> { self.memory.borrow_mut() }
+}
+
+const PRG_BANK_SIZE: usize = 0x4000; // 16 KiB
+const PRG_OUTER_BANK_SIZE: usize = 0x40000; // 256 KiB
+const CHR_BANK_SIZE: usize = 0x1000; // 4 KiB
+
+/// The boards that reuse the CHR bank registers for PRG-ROM/PRG-RAM banking, since they only
+/// have 8 KiB of CHR-RAM
+// https://www.nesdev.org/wiki/MMC1#SxROM_connection_variants
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum MMC1Board {
+    /// SKROM, SLROM, SGROM, ...
+    Standard,
+    /// CHR bank bit 4 disables PRG-RAM
+    SNROM,
+    /// CHR bank bit 3 selects an 8 KiB PRG-RAM bank (16 KiB PRG-RAM)
+    SOROM,
+    /// CHR bank bit 4 selects a 256 KiB PRG-ROM bank (512 KiB PRG-ROM)
+    SUROM,
+    /// CHR bank bit 4 selects a 256 KiB PRG-ROM bank and bits 2-3 an 8 KiB PRG-RAM bank
+    /// (32 KiB PRG-RAM)
+    SXROM,
+    /// SEROM, SHROM and SH1ROM have 32 KiB of PRG-ROM that can't be switched
+    SEROM,
+}
+
+impl MMC1Board {
+    // https://www.nesdev.org/wiki/NES_2.0_submappers#001:_MMC1
+    pub fn detect(rom: &ROM) -> Self {
+        let uses_chr_ram: bool = rom.chr_rom.is_empty();
+        match rom.submapper {
+            1 => MMC1Board::SUROM,
+            2 => MMC1Board::SOROM,
+            4 => MMC1Board::SXROM,
+            5 => MMC1Board::SEROM,
+            _ if !uses_chr_ram => MMC1Board::Standard,
+            _ if rom.prg_ram_size >= 4 * PRG_RAM_PAGE_SIZE => MMC1Board::SXROM,
+            _ if rom.prg_ram_size >= 2 * PRG_RAM_PAGE_SIZE => MMC1Board::SOROM,
+            _ if rom.prg_rom.len() > PRG_OUTER_BANK_SIZE => MMC1Board::SUROM,
+            _ => MMC1Board::SNROM,
+        }
+    }
+}
+
+// https://www.nesdev.org/wiki/MMC1
+pub struct Mapper001 {
+    memory: RcRef<Memory>,
+
+    pub board: MMC1Board,
+    /// MMC1A boards can't disable PRG-RAM
+    pub mmc1a: bool,
+    pub shift_register: u8,
+    pub shift_count: u8,
+    /// ```plaintext
+    /// Control ($8000-$9FFF)
+    /// 4bit0
+    /// -----
+    /// CPPMM
+    /// │││││
+    /// │││╘╧═ Mirroring (0: one-screen, lower bank; 1: one-screen, upper bank;
+    /// │││                2: vertical; 3: horizontal)
+    /// │╘╧═══ PRG-ROM bank mode (0, 1: switch 32 KiB at $8000, ignoring low bit of bank number;
+    /// │                         2: fix first bank at $8000 and switch 16 KiB bank at $C000;
+    /// │                         3: fix last bank at $C000 and switch 16 KiB bank at $8000)
+    /// ╘═════ CHR-ROM bank mode (0: switch 8 KiB at a time; 1: switch two separate 4 KiB banks)
+    /// ```
+    pub control: u8,
+    pub chr_bank_0: u8,
+    pub chr_bank_1: u8,
+    pub prg_bank: u8,
+}
+
+impl Mapper001 {
+    pub fn new(rom: &ROM, memory: RcRef<Memory>) -> Self {
+        let board: MMC1Board = MMC1Board::detect(rom);
+        debug!("MMC1 Board: {:?}", board);
+
+        // Most MMC1 games expect 8 KiB of PRG-RAM, even if the header doesn't declare any
+        if memory.borrow().prg_ram.is_empty() {
+            memory.borrow_mut().prg_ram = vec![0x00; PRG_RAM_PAGE_SIZE];
+        }
+
+        Mapper001 {
+            memory,
+
+            board,
+            mmc1a: rom.submapper == 3,
+            shift_register: 0b0000_0000,
+            shift_count: 0,
+            // PRG-ROM bank mode 3 on power-up, so the reset vector is in the fixed last bank
+            control: 0b0000_1100,
+            chr_bank_0: 0,
+            chr_bank_1: 0,
+            prg_bank: 0,
+        }
+    }
+
+    /// ```plaintext
+    /// Load register ($8000-$FFFF)
+    /// 7  bit  0
+    /// ---- ----
+    /// Rxxx xxxD
+    /// │       │
+    /// │       ╘═ Data bit to be shifted into the shift register, LSB first
+    /// ╘═════════ A write with bit set will reset the shift register and write Control with
+    ///            (Control OR $0C), locking PRG-ROM at $C000-$FFFF to the last bank
+    /// ```
+    fn write_load_register(&mut self, addr: u16, data: u8) {
+        if data & 0b1000_0000 != 0 {
+            self.shift_register = 0b0000_0000;
+            self.shift_count = 0;
+            self.control |= 0b0000_1100;
+            return;
+        }
+
+        self.shift_register |= (data & 0b0000_0001) << self.shift_count;
+        self.shift_count += 1;
+        if self.shift_count == 5 {
+            let value: u8 = self.shift_register;
+            // Bits 13 and 14 of the address select the internal register
+            match addr & 0b0110_0000_0000_0000 {
+                0x0000 => self.control = value,
+                0x2000 => self.chr_bank_0 = value,
+                0x4000 => self.chr_bank_1 = value,
+                _ => self.prg_bank = value,
+            }
+            bus_trace!(
+                "[MMC1] Wrote {:#04X} to register {}",
+                value,
+                (addr >> 13) & 0b0000_0011
+            );
+
+            self.shift_register = 0b0000_0000;
+            self.shift_count = 0;
+        }
+    }
+
+    fn chr_index(&self, addr: u16) -> usize {
+        let bank: usize = match (self.control & 0b0001_0000 != 0, addr) {
+            (false, _) => ((self.chr_bank_0 & 0b0001_1110) | ((addr >> 12) as u8 & 1)) as usize,
+            (true, 0x0000..=0x0FFF) => self.chr_bank_0 as usize,
+            (true, _) => self.chr_bank_1 as usize,
+        };
+        (bank * CHR_BANK_SIZE + (addr & 0x0FFF) as usize) % self.memory().chr_mem.len()
+    }
+
+    fn prg_rom_index(&self, addr: u16) -> usize {
+        if self.board == MMC1Board::SEROM {
+            return (addr - PRG_ROM) as usize % self.memory().prg_rom.len();
+        }
+
+        let outer_bank: usize = match self.board {
+            MMC1Board::SUROM | MMC1Board::SXROM => {
+                ((self.chr_bank_0 >> 4) & 0b0000_0001) as usize * PRG_OUTER_BANK_SIZE
+            }
+            _ => 0,
+        };
+        let bank: usize = (self.prg_bank & 0b0000_1111) as usize;
+        let offset: usize = (addr & 0x3FFF) as usize;
+
+        let inner_bank: usize = match ((self.control >> 2) & 0b0000_0011, addr) {
+            (0 | 1, PRG_ROM_BANK_LO..=PRG_ROM_BANK_LO_END) => bank & 0b0000_1110,
+            (0 | 1, _) => bank | 0b0000_0001,
+            (2, PRG_ROM_BANK_LO..=PRG_ROM_BANK_LO_END) => 0,
+            (2, _) => bank,
+            (_, PRG_ROM_BANK_LO..=PRG_ROM_BANK_LO_END) => bank,
+            (_, _) => 0b0000_1111,
+        };
+
+        (outer_bank + inner_bank * PRG_BANK_SIZE + offset) % self.memory().prg_rom.len()
+    }
+
+    /// Returns `None` while PRG-RAM is disabled
+    fn prg_ram_index(&self, addr: u16) -> Option<usize> {
+        let disabled: bool = (!self.mmc1a && self.prg_bank & 0b0001_0000 != 0)
+            || (self.board == MMC1Board::SNROM && self.chr_bank_0 & 0b0001_0000 != 0);
+        if disabled {
+            return None;
+        }
+
+        let bank: usize = match self.board {
+            MMC1Board::SOROM => ((self.chr_bank_0 >> 3) & 0b0000_0001) as usize,
+            MMC1Board::SXROM => ((self.chr_bank_0 >> 2) & 0b0000_0011) as usize,
+            _ => 0,
+        };
+        let index: usize = bank * PRG_RAM_PAGE_SIZE + (addr - SRAM) as usize;
+        Some(index % self.memory().prg_ram.len())
+    }
+}
+
+impl SaveState for Mapper001 {
+    fn write_state(&self, state: &mut StateWriter) {
+        state.u8(self.shift_register);
+        state.u8(self.shift_count);
+        state.u8(self.control);
+        state.u8(self.chr_bank_0);
+        state.u8(self.chr_bank_1);
+        state.u8(self.prg_bank);
+    }
+
+    fn read_state(&mut self, state: &mut StateReader) -> Result<(), SaveStateError> {
+        self.shift_register = state.u8()?;
+        self.shift_count = state.u8()?;
+        self.control = state.u8()?;
+        self.chr_bank_0 = state.u8()?;
+        self.chr_bank_1 = state.u8()?;
+        self.prg_bank = state.u8()?;
+        Ok(())
+    }
+}
+
+impl Mapper for Mapper001 {
+    fn cpu_read(&mut self, addr: u16) -> u8 {
+        match addr {
+            SRAM..=SRAM_END => match self.prg_ram_index(addr) {
+                Some(index) => {
+                    let byte: u8 = self.memory().prg_ram[index];
+                    bus_trace!(
+                        "[PRG-RAM] Read {:#04X} from {:#06X} ({:#06X})",
+                        byte,
+                        addr,
+                        index
+                    );
+                    byte
+                }
+                None => {
+                    bus_trace!("[PRG-RAM] Ignoring read from disabled {:#06X}", addr);
+                    0
+                }
+            },
+
+            PRG_ROM..=PRG_ROM_END => {
+                let index: usize = self.prg_rom_index(addr);
+                let byte: u8 = self.memory().prg_rom[index];
+                bus_trace!(
+                    "[PRG-ROM] Read {:#04X} from {:#06X} ({:#07X})",
+                    byte,
+                    addr,
+                    index
+                );
+                byte
+            }
+
+            _ => {
+                warn!("Ignoring cartridge read at {:#06X}", addr);
+                0
+            }
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, data: u8) {
+        match addr {
+            SRAM..=SRAM_END => match self.prg_ram_index(addr) {
+                Some(index) => {
+                    self.memory_mut().prg_ram[index] = data;
+                    bus_trace!(
+                        "[PRG-RAM] Wrote {:#04X} to {:#06X} ({:#06X})",
+                        data,
+                        addr,
+                        index
+                    );
+                }
+                None => {
+                    bus_trace!(
+                        "[PRG-RAM] Ignoring write {:#04X} to disabled {:#06X}",
+                        data,
+                        addr
+                    );
+                }
+            },
+
+            PRG_ROM..=PRG_ROM_END => self.write_load_register(addr, data),
+
+            _ => warn!("Ignoring cartridge write at {:#06X}", addr),
+        }
+    }
+
+    fn prg_rom_offset(&self, addr: u16) -> Option<usize> {
+        (addr >= PRG_ROM).then(|| self.prg_rom_index(addr))
+    }
+
+    fn prg_bank_size(&self) -> usize {
+        PRG_BANK_SIZE
+    }
+
+    fn prg_ram_offset(&self, addr: u16) -> Option<usize> {
+        match addr {
+            SRAM..=SRAM_END => self.prg_ram_index(addr),
+            _ => None,
+        }
+    }
+
+    fn chr_offset(&self, addr: u16) -> usize {
+        self.chr_index(addr)
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        match self.control & 0b0000_0011 {
+            0 => Mirroring::SingleScreenA,
+            1 => Mirroring::SingleScreenB,
+            2 => Mirroring::Vertical,
+            _ => Mirroring::Horizontal,
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use super::Mapper;
+use crate::{
+    RcRef, bus_trace,
+    cartridge::{Mirroring, PRG_ROM_PAGE_SIZE, ROM},
+    memory::{Memory, mem_map::*},
+    prelude::*,
+    save_state::{SaveState, SaveStateError, StateReader, StateWriter},
+};
+
+#[rustfmt::skip]
+impl NESAccess for Mapper002 {
+    fn memory(&self) -> Ref<'_, Memory> { self.memory.borrow() }
+    fn memory_mut(&self) -> RefMut<'_, Memory> { self.memory.borrow_mut() }
+}
+
+// https://www.nesdev.org/wiki/UxROM
+pub struct Mapper002 {
+    memory: RcRef<Memory>,
+    mirroring: Mirroring,
+
+    /// Submapper 2 ANDs every written value with the PRG-ROM byte at the same address
+    // https://www.nesdev.org/wiki/Bus_conflict
+    pub bus_conflicts: bool,
+    pub prg_bank: u8,
+}
+
+impl Mapper002 {
+    pub fn new(rom: &ROM, memory: RcRef<Memory>) -> Self {
+        Mapper002 {
+            memory,
+            mirroring: rom.screen_mirroring,
+
+            bus_conflicts: rom.submapper == 2,
+            prg_bank: 0,
+        }
+    }
+
+    fn prg_rom_index(&self, addr: u16) -> usize {
+        let bank_count: usize = self.memory().prg_rom.len() / PRG_ROM_PAGE_SIZE;
+        let bank: usize = match addr {
+            PRG_ROM_BANK_LO..=PRG_ROM_BANK_LO_END => self.prg_bank as usize % bank_count,
+            _ => bank_count - 1,
+        };
+        bank * PRG_ROM_PAGE_SIZE + (addr & 0x3FFF) as usize
+    }
+}
+
+impl SaveState for Mapper002 {
+    fn write_state(&self, state: &mut StateWriter) {
+        state.u8(self.prg_bank);
+    }
+
+    fn read_state(&mut self, state: &mut StateReader) -> Result<(), SaveStateError> {
+        self.prg_bank = state.u8()?;
+        Ok(())
+    }
+}
+
+impl Mapper for Mapper002 {
+    fn cpu_read(&mut self, addr: u16) -> u8 {
+        match addr {
+            PRG_ROM..=PRG_ROM_END => {
+                let index: usize = self.prg_rom_index(addr);
+                let byte: u8 = self.memory().prg_rom[index];
+                bus_trace!(
+                    "[PRG-ROM] Read {:#04X} from {:#06X} ({:#07X})",
+                    byte,
+                    addr,
+                    index
+                );
+                byte
+            }
+
+            _ => {
+                warn!("Ignoring cartridge read at {:#06X}", addr);
+                0
+            }
+        }
+    }
+
+    /// ```plaintext
+    /// Bank select ($8000-$FFFF)
+    /// 7  bit  0
+    /// ---- ----
+    /// xxxx PPPP
+    ///      ││││
+    ///      ╘╧╧╧═ Select 16 KiB PRG-ROM bank for $8000-$BFFF
+    ///            (UNROM uses bits 2-0; UOROM uses bits 3-0)
+    /// ```
+    fn cpu_write(&mut self, addr: u16, data: u8) {
+        match addr {
+            PRG_ROM..=PRG_ROM_END => {
+                let data: u8 = if self.bus_conflicts {
+                    data & self.cpu_read(addr)
+                } else {
+                    data
+                };
+                self.prg_bank = data;
+                bus_trace!("[UxROM] Selected PRG-ROM bank {}", data);
+            }
+
+            _ => warn!("Ignoring cartridge write at {:#06X}", addr),
+        }
+    }
+
+    fn prg_rom_offset(&self, addr: u16) -> Option<usize> {
+        (addr >= PRG_ROM).then(|| self.prg_rom_index(addr))
+    }
+
+    fn prg_bank_size(&self) -> usize {
+        PRG_ROM_PAGE_SIZE
+    }
+
+    fn chr_offset(&self, addr: u16) -> usize {
+        addr as usize % self.memory().chr_mem.len()
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+}
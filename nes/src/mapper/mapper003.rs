@@ -0,0 +1,114 @@
+use super::Mapper;
+use crate::{
+    RcRef, bus_trace,
+    cartridge::{CHR_ROM_PAGE_SIZE, Mirroring, ROM},
+    memory::{Memory, mem_map::*},
+    prelude::*,
+    save_state::{SaveState, SaveStateError, StateReader, StateWriter},
+};
+
+#[rustfmt::skip]
+impl NESAccess for Mapper003 {
+    fn memory(&self) -> Ref<'_, Memory> { self.memory.borrow() }
+    fn memory_mut(&self) -> RefMut<'_, Memory> { self.memory.borrow_mut() }
+}
+
+// https://www.nesdev.org/wiki/INES_Mapper_003
+pub struct Mapper003 {
+    memory: RcRef<Memory>,
+    mirroring: Mirroring,
+
+    /// Submapper 2 ANDs every written value with the PRG-ROM byte at the same address
+    // https://www.nesdev.org/wiki/Bus_conflict
+    pub bus_conflicts: bool,
+    pub chr_bank: u8,
+}
+
+impl Mapper003 {
+    pub fn new(rom: &ROM, memory: RcRef<Memory>) -> Self {
+        Mapper003 {
+            memory,
+            mirroring: rom.screen_mirroring,
+
+            bus_conflicts: rom.submapper == 2,
+            chr_bank: 0,
+        }
+    }
+
+    fn chr_index(&self, addr: u16) -> usize {
+        let index: usize = self.chr_bank as usize * CHR_ROM_PAGE_SIZE + addr as usize;
+        index % self.memory().chr_mem.len()
+    }
+}
+
+impl SaveState for Mapper003 {
+    fn write_state(&self, state: &mut StateWriter) {
+        state.u8(self.chr_bank);
+    }
+
+    fn read_state(&mut self, state: &mut StateReader) -> Result<(), SaveStateError> {
+        self.chr_bank = state.u8()?;
+        Ok(())
+    }
+}
+
+impl Mapper for Mapper003 {
+    fn cpu_read(&mut self, addr: u16) -> u8 {
+        match addr {
+            PRG_ROM..=PRG_ROM_END => {
+                // 16 KiB boards mirror $8000-$BFFF into $C000-$FFFF
+                let index: usize = (addr - PRG_ROM) as usize % self.memory().prg_rom.len();
+                let byte: u8 = self.memory().prg_rom[index];
+                bus_trace!(
+                    "[PRG-ROM] Read {:#04X} from {:#06X} ({:#06X})",
+                    byte,
+                    addr,
+                    index
+                );
+                byte
+            }
+
+            _ => {
+                warn!("Ignoring cartridge read at {:#06X}", addr);
+                0
+            }
+        }
+    }
+
+    /// ```plaintext
+    /// Bank select ($8000-$FFFF)
+    /// 7  bit  0
+    /// ---- ----
+    /// cccc ccCC
+    /// ││││ ││││
+    /// ╘╧╧╧═╧╧╧╧═ Select 8 KiB CHR-ROM bank for PPU $0000-$1FFF
+    ///            (CNROM only implements the lowest 2 bits)
+    /// ```
+    fn cpu_write(&mut self, addr: u16, data: u8) {
+        match addr {
+            PRG_ROM..=PRG_ROM_END => {
+                let data: u8 = if self.bus_conflicts {
+                    data & self.cpu_read(addr)
+                } else {
+                    data
+                };
+                self.chr_bank = data;
+                bus_trace!("[CNROM] Selected CHR-ROM bank {}", data);
+            }
+
+            _ => warn!("Ignoring cartridge write at {:#06X}", addr),
+        }
+    }
+
+    fn prg_rom_offset(&self, addr: u16) -> Option<usize> {
+        (addr >= PRG_ROM).then(|| (addr - PRG_ROM) as usize % self.memory().prg_rom.len())
+    }
+
+    fn chr_offset(&self, addr: u16) -> usize {
+        self.chr_index(addr)
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+}
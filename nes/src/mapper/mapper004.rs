@@ -0,0 +1,391 @@
+use super::Mapper;
+use crate::{
+    RcRef, bus_trace,
+    cartridge::{Mirroring, PRG_RAM_PAGE_SIZE, ROM},
+    memory::{Memory, mem_map::*},
+    prelude::*,
+    save_state::{SaveState, SaveStateError, StateReader, StateWriter},
+};
+
+#[rustfmt::skip]
+impl NESAccess for Mapper004 {
+    fn memory(&self) -> Ref<'_, Memory> { self.memory.borrow() }
+    fn memory_mut(&self) -> RefMut<'_, Memory> { self.memory.borrow_mut() }
+}
+
+const PRG_BANK_SIZE: usize = 0x2000; // 8 KiB
+const CHR_BANK_SIZE: usize = 0x0400; // 1 KiB
+const MMC6_PRG_RAM_SIZE: usize = 0x0400; // 1 KiB
+
+/// A rising edge of PPU A12 only clocks the IRQ counter after A12 has been low for at least
+/// 3 falling edges of M2, which filters out the rises during background fetches
+// https://www.nesdev.org/wiki/MMC3#IRQ_Specifics
+const A12_FILTER_PPU_CYCLES: usize = 3 * 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum MMC3Variant {
+    /// MMC3B/MMC3C, the IRQ fires whenever the counter is 0 after being clocked
+    MMC3,
+    /// MMC6 (StarTropics), with 1 KiB of internal PRG-RAM
+    MMC6,
+    /// MMC3A/NEC, the IRQ only fires when the counter gets decremented or reloaded to 0
+    MMC3A,
+}
+
+// https://www.nesdev.org/wiki/MMC3
+// https://www.nesdev.org/wiki/MMC6
+pub struct Mapper004 {
+    memory: RcRef<Memory>,
+    four_screen: bool,
+
+    pub variant: MMC3Variant,
+    /// ```plaintext
+    /// Bank select ($8000-$9FFE, even)
+    /// 7  bit  0
+    /// ---- ----
+    /// CPMx xRRR
+    /// │││   │││
+    /// │││   ╘╧╧═ Specify which bank register to update on next write to Bank Data register
+    /// │││          000: R0: Select 2 KiB CHR bank at PPU $0000-$07FF (or $1000-$17FF)
+    /// │││          001: R1: Select 2 KiB CHR bank at PPU $0800-$0FFF (or $1800-$1FFF)
+    /// │││          010: R2: Select 1 KiB CHR bank at PPU $1000-$13FF (or $0000-$03FF)
+    /// │││          011: R3: Select 1 KiB CHR bank at PPU $1400-$17FF (or $0400-$07FF)
+    /// │││          100: R4: Select 1 KiB CHR bank at PPU $1800-$1BFF (or $0800-$0BFF)
+    /// │││          101: R5: Select 1 KiB CHR bank at PPU $1C00-$1FFF (or $0C00-$0FFF)
+    /// │││          110: R6: Select 8 KiB PRG-ROM bank at $8000-$9FFF (or $C000-$DFFF)
+    /// │││          111: R7: Select 8 KiB PRG-ROM bank at $A000-$BFFF
+    /// ││╘═══════ PRG-RAM enable (MMC6 only)
+    /// │╘════════ PRG-ROM bank mode (0: $8000-$9FFF swappable, $C000-$DFFF fixed to second-last
+    /// │                             bank; 1: $C000-$DFFF swappable, $8000-$9FFF fixed to
+    /// │                             second-last bank)
+    /// ╘═════════ CHR A12 inversion (0: two 2 KiB banks at $0000-$0FFF, four 1 KiB banks at
+    ///                               $1000-$1FFF; 1: two 2 KiB banks at $1000-$1FFF, four
+    ///                               1 KiB banks at $0000-$0FFF)
+    /// ```
+    pub bank_select: u8,
+    pub bank_registers: [u8; 8],
+    pub horizontal_mirroring: bool,
+    /// ```plaintext
+    /// PRG-RAM protect ($A001-$BFFF, odd)
+    /// 7  bit  0
+    /// ---- ----
+    /// RWxx xxxx (MMC3)
+    /// HhLl xxxx (MMC6)
+    /// ││││
+    /// ││╘╧══════ MMC6: Enable reading (L) / writing (l) of $7000-$71FF
+    /// │╘════════ MMC3: Deny writes (0: allow; 1: deny)
+    /// │          MMC6: Enable writing of $7200-$73FF
+    /// ╘═════════ MMC3: PRG-RAM chip enable (0: disable; 1: enable)
+    ///            MMC6: Enable reading of $7200-$73FF
+    /// ```
+    pub prg_ram_protect: u8,
+
+    pub irq_latch: u8,
+    pub irq_counter: u8,
+    pub irq_reload: bool,
+    pub irq_enabled: bool,
+    pub irq_flag: bool,
+    a12_high: bool,
+    a12_low_cycle: usize,
+}
+
+impl Mapper004 {
+    pub fn new(rom: &ROM, memory: RcRef<Memory>) -> Self {
+        let variant: MMC3Variant = match rom.submapper {
+            1 => MMC3Variant::MMC6,
+            4 => MMC3Variant::MMC3A,
+            _ => MMC3Variant::MMC3,
+        };
+        debug!("MMC3 Variant: {:?}", variant);
+
+        if variant == MMC3Variant::MMC6 {
+            memory.borrow_mut().prg_ram = vec![0x00; MMC6_PRG_RAM_SIZE];
+        } else if memory.borrow().prg_ram.is_empty() {
+            // Most MMC3 games expect 8 KiB of PRG-RAM, even if the header doesn't declare any
+            memory.borrow_mut().prg_ram = vec![0x00; PRG_RAM_PAGE_SIZE];
+        }
+
+        Mapper004 {
+            memory,
+            four_screen: rom.screen_mirroring == Mirroring::FourScreen,
+
+            variant,
+            bank_select: 0b0000_0000,
+            bank_registers: [0, 2, 4, 5, 6, 7, 0, 1],
+            horizontal_mirroring: rom.screen_mirroring == Mirroring::Horizontal,
+            prg_ram_protect: 0b0000_0000,
+
+            irq_latch: 0,
+            irq_counter: 0,
+            irq_reload: false,
+            irq_enabled: false,
+            irq_flag: false,
+            a12_high: false,
+            a12_low_cycle: 0,
+        }
+    }
+
+    fn prg_rom_index(&self, addr: u16) -> usize {
+        let bank_count: usize = self.memory().prg_rom.len() / PRG_BANK_SIZE;
+        let second_last: usize = bank_count.saturating_sub(2);
+        let prg_mode: bool = self.bank_select & 0b0100_0000 != 0;
+
+        let bank: usize = match (addr, prg_mode) {
+            (0x8000..=0x9FFF, false) | (0xC000..=0xDFFF, true) => self.bank_registers[6] as usize,
+            (0x8000..=0x9FFF, true) | (0xC000..=0xDFFF, false) => second_last,
+            (0xA000..=0xBFFF, _) => self.bank_registers[7] as usize,
+            _ => bank_count - 1,
+        };
+        (bank % bank_count) * PRG_BANK_SIZE + (addr & 0x1FFF) as usize
+    }
+
+    fn chr_index(&self, addr: u16) -> usize {
+        // Swapping the pattern tables is the same as flipping A12 before decoding
+        let addr: u16 = match self.bank_select & 0b1000_0000 {
+            0 => addr,
+            _ => addr ^ 0x1000,
+        };
+
+        let bank: usize = match addr {
+            0x0000..=0x07FF => {
+                (self.bank_registers[0] & 0b1111_1110) as usize + (addr >> 10) as usize
+            }
+            0x0800..=0x0FFF => {
+                (self.bank_registers[1] & 0b1111_1110) as usize + ((addr >> 10) & 1) as usize
+            }
+            _ => self.bank_registers[2 + ((addr >> 10) & 0b0000_0011) as usize] as usize,
+        };
+        (bank * CHR_BANK_SIZE + (addr & 0x03FF) as usize) % self.memory().chr_mem.len()
+    }
+
+    /// MMC6 maps its 1 KiB of PRG-RAM to `$7000-$7FFF`, with separate read/write enables for
+    /// each 512 byte half
+    fn mmc6_prg_ram_access(&self, addr: u16, write: bool) -> Option<usize> {
+        if addr < 0x7000 || self.bank_select & 0b0010_0000 == 0 {
+            return None;
+        }
+
+        let index: usize = (addr & 0x03FF) as usize;
+        let (read_bit, write_bit): (u8, u8) = match index {
+            0x0000..=0x01FF => (0b0010_0000, 0b0001_0000),
+            _ => (0b1000_0000, 0b0100_0000),
+        };
+        let readable: bool = self.prg_ram_protect & read_bit != 0;
+        let writable: bool = self.prg_ram_protect & write_bit != 0;
+
+        match write {
+            true if readable && writable => Some(index),
+            false if readable => Some(index),
+            _ => None,
+        }
+    }
+
+    /// Returns `None` while PRG-RAM is disabled or write-protected
+    fn prg_ram_index(&self, addr: u16, write: bool) -> Option<usize> {
+        if self.variant == MMC3Variant::MMC6 {
+            return self.mmc6_prg_ram_access(addr, write);
+        }
+
+        let enabled: bool = self.prg_ram_protect & 0b1000_0000 != 0;
+        let write_protected: bool = self.prg_ram_protect & 0b0100_0000 != 0;
+        if !enabled || (write && write_protected) {
+            return None;
+        }
+        Some((addr - SRAM) as usize % self.memory().prg_ram.len())
+    }
+
+    // https://www.nesdev.org/wiki/MMC3#IRQ_Specifics
+    fn clock_irq_counter(&mut self) {
+        let previous: u8 = self.irq_counter;
+        let reloaded: bool = self.irq_reload;
+
+        if self.irq_counter == 0 || self.irq_reload {
+            self.irq_counter = self.irq_latch;
+            self.irq_reload = false;
+        } else {
+            self.irq_counter -= 1;
+        }
+
+        let triggers: bool = match self.variant {
+            MMC3Variant::MMC3A => self.irq_counter == 0 && (previous != 0 || reloaded),
+            _ => self.irq_counter == 0,
+        };
+        if triggers && self.irq_enabled {
+            self.irq_flag = true;
+        }
+    }
+
+    fn write_register(&mut self, addr: u16, data: u8) {
+        let even: bool = addr & 0b0000_0001 == 0;
+        match (addr, even) {
+            (0x8000..=0x9FFF, true) => self.bank_select = data,
+            (0x8000..=0x9FFF, false) => {
+                let register: usize = (self.bank_select & 0b0000_0111) as usize;
+                self.bank_registers[register] = match register {
+                    // R6 and R7 ignore the top two bits
+                    6 | 7 => data & 0b0011_1111,
+                    _ => data,
+                };
+            }
+            (0xA000..=0xBFFF, true) => self.horizontal_mirroring = data & 0b0000_0001 != 0,
+            (0xA000..=0xBFFF, false) => self.prg_ram_protect = data,
+            (0xC000..=0xDFFF, true) => self.irq_latch = data,
+            (0xC000..=0xDFFF, false) => {
+                self.irq_counter = 0;
+                self.irq_reload = true;
+            }
+            (_, true) => {
+                self.irq_enabled = false;
+                self.irq_flag = false;
+            }
+            (_, false) => self.irq_enabled = true,
+        }
+        bus_trace!("[MMC3] Wrote {:#04X} to {:#06X}", data, addr);
+    }
+}
+
+impl SaveState for Mapper004 {
+    fn write_state(&self, state: &mut StateWriter) {
+        state.u8(self.bank_select);
+        state.bytes(&self.bank_registers);
+        state.bool(self.horizontal_mirroring);
+        state.u8(self.prg_ram_protect);
+
+        state.u8(self.irq_latch);
+        state.u8(self.irq_counter);
+        state.bool(self.irq_reload);
+        state.bool(self.irq_enabled);
+        state.bool(self.irq_flag);
+        state.bool(self.a12_high);
+        state.usize(self.a12_low_cycle);
+    }
+
+    fn read_state(&mut self, state: &mut StateReader) -> Result<(), SaveStateError> {
+        self.bank_select = state.u8()?;
+        state.bytes(&mut self.bank_registers)?;
+        self.horizontal_mirroring = state.bool()?;
+        self.prg_ram_protect = state.u8()?;
+
+        self.irq_latch = state.u8()?;
+        self.irq_counter = state.u8()?;
+        self.irq_reload = state.bool()?;
+        self.irq_enabled = state.bool()?;
+        self.irq_flag = state.bool()?;
+        self.a12_high = state.bool()?;
+        self.a12_low_cycle = state.usize()?;
+        Ok(())
+    }
+}
+
+impl Mapper for Mapper004 {
+    fn cpu_read(&mut self, addr: u16) -> u8 {
+        match addr {
+            SRAM..=SRAM_END => match self.prg_ram_index(addr, false) {
+                Some(index) => {
+                    let byte: u8 = self.memory().prg_ram[index];
+                    bus_trace!(
+                        "[PRG-RAM] Read {:#04X} from {:#06X} ({:#06X})",
+                        byte,
+                        addr,
+                        index
+                    );
+                    byte
+                }
+                None => {
+                    bus_trace!("[PRG-RAM] Ignoring read from disabled {:#06X}", addr);
+                    0
+                }
+            },
+
+            PRG_ROM..=PRG_ROM_END => {
+                let index: usize = self.prg_rom_index(addr);
+                let byte: u8 = self.memory().prg_rom[index];
+                bus_trace!(
+                    "[PRG-ROM] Read {:#04X} from {:#06X} ({:#07X})",
+                    byte,
+                    addr,
+                    index
+                );
+                byte
+            }
+
+            _ => {
+                warn!("Ignoring cartridge read at {:#06X}", addr);
+                0
+            }
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, data: u8) {
+        match addr {
+            SRAM..=SRAM_END => match self.prg_ram_index(addr, true) {
+                Some(index) => {
+                    self.memory_mut().prg_ram[index] = data;
+                    bus_trace!(
+                        "[PRG-RAM] Wrote {:#04X} to {:#06X} ({:#06X})",
+                        data,
+                        addr,
+                        index
+                    );
+                }
+                None => {
+                    bus_trace!(
+                        "[PRG-RAM] Ignoring write {:#04X} to protected {:#06X}",
+                        data,
+                        addr
+                    );
+                }
+            },
+
+            PRG_ROM..=PRG_ROM_END => self.write_register(addr, data),
+
+            _ => warn!("Ignoring cartridge write at {:#06X}", addr),
+        }
+    }
+
+    fn prg_rom_offset(&self, addr: u16) -> Option<usize> {
+        (addr >= PRG_ROM).then(|| self.prg_rom_index(addr))
+    }
+
+    fn prg_bank_size(&self) -> usize {
+        PRG_BANK_SIZE
+    }
+
+    fn prg_ram_offset(&self, addr: u16) -> Option<usize> {
+        match addr {
+            SRAM..=SRAM_END => self.prg_ram_index(addr, false),
+            _ => None,
+        }
+    }
+
+    fn chr_offset(&self, addr: u16) -> usize {
+        self.chr_index(addr)
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        match (self.four_screen, self.horizontal_mirroring) {
+            (true, _) => Mirroring::FourScreen,
+            (false, true) => Mirroring::Horizontal,
+            (false, false) => Mirroring::Vertical,
+        }
+    }
+
+    fn ppu_bus_address(&mut self, addr: u16, ppu_cycle: usize) {
+        let a12_high: bool = addr & 0x1000 != 0;
+        match (self.a12_high, a12_high) {
+            (false, true) => {
+                if ppu_cycle.wrapping_sub(self.a12_low_cycle) >= A12_FILTER_PPU_CYCLES {
+                    self.clock_irq_counter();
+                }
+            }
+            (true, false) => self.a12_low_cycle = ppu_cycle,
+            _ => {}
+        }
+        self.a12_high = a12_high;
+    }
+
+    fn irq_pending(&self) -> bool {
+        self.irq_flag
+    }
+}
@@ -0,0 +1,120 @@
+use super::Mapper;
+use crate::{
+    RcRef, bus_trace,
+    cartridge::{Mirroring, ROM},
+    memory::{Memory, mem_map::*},
+    prelude::*,
+    save_state::{SaveState, SaveStateError, StateReader, StateWriter},
+};
+
+#[rustfmt::skip]
+impl NESAccess for Mapper007 {
+    fn memory(&self) -> Ref<'_, Memory> { self.memory.borrow() }
+    fn memory_mut(&self) -> RefMut<'_, Memory> { self.memory.borrow_mut() }
+}
+
+const PRG_BANK_SIZE: usize = 0x8000; // 32 KiB
+
+// https://www.nesdev.org/wiki/AxROM
+pub struct Mapper007 {
+    memory: RcRef<Memory>,
+
+    /// Submapper 2 (AMROM) ANDs every written value with the PRG-ROM byte at the same address
+    // https://www.nesdev.org/wiki/Bus_conflict
+    pub bus_conflicts: bool,
+    /// ```plaintext
+    /// Bank select ($8000-$FFFF)
+    /// 7  bit  0
+    /// ---- ----
+    /// xxxM xPPP
+    ///    │  │││
+    ///    │  ╘╧╧═ Select 32 KiB PRG-ROM bank for CPU $8000-$FFFF
+    ///    ╘══════ Select 1 KiB VRAM page for all 4 nametables
+    /// ```
+    pub bank_select: u8,
+}
+
+impl Mapper007 {
+    pub fn new(rom: &ROM, memory: RcRef<Memory>) -> Self {
+        Mapper007 {
+            memory,
+
+            bus_conflicts: rom.submapper == 2,
+            bank_select: 0,
+        }
+    }
+
+    fn prg_rom_index(&self, addr: u16) -> usize {
+        let bank: usize = (self.bank_select & 0b0000_0111) as usize;
+        (bank * PRG_BANK_SIZE + (addr - PRG_ROM) as usize) % self.memory().prg_rom.len()
+    }
+}
+
+impl SaveState for Mapper007 {
+    fn write_state(&self, state: &mut StateWriter) {
+        state.u8(self.bank_select);
+    }
+
+    fn read_state(&mut self, state: &mut StateReader) -> Result<(), SaveStateError> {
+        self.bank_select = state.u8()?;
+        Ok(())
+    }
+}
+
+impl Mapper for Mapper007 {
+    fn cpu_read(&mut self, addr: u16) -> u8 {
+        match addr {
+            PRG_ROM..=PRG_ROM_END => {
+                let index: usize = self.prg_rom_index(addr);
+                let byte: u8 = self.memory().prg_rom[index];
+                bus_trace!(
+                    "[PRG-ROM] Read {:#04X} from {:#06X} ({:#07X})",
+                    byte,
+                    addr,
+                    index
+                );
+                byte
+            }
+
+            _ => {
+                warn!("Ignoring cartridge read at {:#06X}", addr);
+                0
+            }
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, data: u8) {
+        match addr {
+            PRG_ROM..=PRG_ROM_END => {
+                let data: u8 = if self.bus_conflicts {
+                    data & self.cpu_read(addr)
+                } else {
+                    data
+                };
+                self.bank_select = data;
+                bus_trace!("[AxROM] Wrote {:#04X} to bank select", data);
+            }
+
+            _ => warn!("Ignoring cartridge write at {:#06X}", addr),
+        }
+    }
+
+    fn prg_rom_offset(&self, addr: u16) -> Option<usize> {
+        (addr >= PRG_ROM).then(|| self.prg_rom_index(addr))
+    }
+
+    fn prg_bank_size(&self) -> usize {
+        PRG_BANK_SIZE
+    }
+
+    fn chr_offset(&self, addr: u16) -> usize {
+        addr as usize % self.memory().chr_mem.len()
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        match self.bank_select & 0b0001_0000 {
+            0 => Mirroring::SingleScreenA,
+            _ => Mirroring::SingleScreenB,
+        }
+    }
+}
@@ -1,21 +1,141 @@
-mod mapper000;
-mod mapper001;
+pub mod mapper000;
+pub mod mapper001;
+pub mod mapper002;
+pub mod mapper003;
+pub mod mapper004;
+pub mod mapper007;
 
-use crate::{BoxMapper, BoxNESDevice, RcRef, cartridge::ROM, memory::Memory, ppu::PPU, prelude::*};
+use crate::{
+    BoxMapper, RcRef,
+    cartridge::{Mirroring, ROM},
+    memory::{Memory, mem_map::*},
+    prelude::*,
+    save_state::SaveState,
+    tools::NESAccess,
+};
 
-pub trait Mapper {
-    fn connect_input_device(&mut self, port: u8, device: RcRef<BoxNESDevice>);
-    fn poll_interrupt(&self) -> bool {
+/// Cartridge hardware, as seen from the CPU and PPU buses
+///
+/// The [`Bus`](crate::bus::Bus) decodes the system address space itself and only forwards
+/// accesses that reach the cartridge connector. Its registers are part of save states, while
+/// the PRG-RAM and CHR-RAM it maps are saved with [`Memory`].
+///
+/// Peeks and pokes go straight to the [`Memory`] the offsets point at, so debuggers never
+/// clock the mapper or log the access.
+pub trait Mapper: SaveState + NESAccess {
+    /// CPU reads from `$4020-$FFFF`
+    fn cpu_read(&mut self, addr: u16) -> u8;
+    /// CPU writes to `$4020-$FFFF`
+    fn cpu_write(&mut self, addr: u16, data: u8);
+    /// PPU reads from the pattern tables (`$0000-$1FFF`)
+    fn ppu_read(&mut self, addr: u16) -> u8 {
+        self.memory().chr_mem[self.chr_offset(addr)]
+    }
+    /// PPU writes to the pattern tables (`$0000-$1FFF`), ignored unless the cartridge has CHR-RAM
+    fn ppu_write(&mut self, addr: u16, data: u8) {
+        if self.memory().use_chr_ram {
+            let index: usize = self.chr_offset(addr);
+            self.memory_mut().chr_mem[index] = data;
+        } else {
+            error!(
+                "[PPU] Attempted to write {:#04X} to CHR-ROM {:#06X}",
+                data, addr
+            );
+        }
+    }
+    fn mirroring(&self) -> Mirroring;
+    /// PPU reads from the nametables (`$2000-$2FFF`), `vram` being the PPU's nametable VRAM
+    fn nametable_read(&mut self, addr: u16, vram: &[u8; 4096]) -> u8 {
+        vram[self.mirroring().vram_index(addr)]
+    }
+    /// PPU writes to the nametables (`$2000-$2FFF`), `vram` being the PPU's nametable VRAM
+    fn nametable_write(&mut self, addr: u16, data: u8, vram: &mut [u8; 4096]) {
+        vram[self.mirroring().vram_index(addr)] = data;
+    }
+    /// Called for every address the PPU drives onto its bus while rendering or through
+    /// `$2006`/`$2007`, so the mapper can watch address lines like A12
+    fn ppu_bus_address(&mut self, _addr: u16, _ppu_cycle: usize) {}
+    fn irq_pending(&self) -> bool {
         false
     }
-    fn signal_scanline(&mut self) {}
-    fn read(&mut self, addr: u16) -> u8;
-    fn write(&mut self, addr: u16, data: u8);
+    /// Where a CPU read from `$8000-$FFFF` lands in PRG-ROM, so debuggers can tell banks apart
+    fn prg_rom_offset(&self, _addr: u16) -> Option<usize> {
+        None
+    }
+    /// Size of the PRG-ROM banks the mapper switches, which symbol files number their banks in.
+    /// Boards without bank switching have a single 32 KiB bank.
+    fn prg_bank_size(&self) -> usize {
+        0x8000
+    }
+    /// Where a CPU read from `$6000-$7FFF` lands in PRG-RAM, `None` if nothing answers it
+    fn prg_ram_offset(&self, _addr: u16) -> Option<usize> {
+        None
+    }
+    /// Where a PPU read from the pattern tables (`$0000-$1FFF`) lands in CHR memory
+    fn chr_offset(&self, addr: u16) -> usize;
+
+    /// What a CPU read from `$4020-$FFFF` would return
+    fn cpu_peek(&self, addr: u16) -> u8 {
+        let memory: Ref<Memory> = self.memory();
+        let byte: Option<u8> = match addr {
+            SRAM..=SRAM_END => self
+                .prg_ram_offset(addr)
+                .map(|index: usize| memory.prg_ram[index]),
+            PRG_ROM..=PRG_ROM_END => self
+                .prg_rom_offset(addr)
+                .map(|index: usize| memory.prg_rom[index]),
+            _ => None,
+        };
+        byte.unwrap_or(0)
+    }
+    /// Writes PRG-RAM or PRG-ROM without touching the mapper's registers, returning `false` if
+    /// nothing is mapped at `addr`
+    fn cpu_poke(&mut self, addr: u16, data: u8) -> bool {
+        match addr {
+            SRAM..=SRAM_END => {
+                let index: Option<usize> = self.prg_ram_offset(addr);
+                if let Some(index) = index {
+                    self.memory_mut().prg_ram[index] = data;
+                }
+                index.is_some()
+            }
+            PRG_ROM..=PRG_ROM_END => {
+                let index: Option<usize> = self.prg_rom_offset(addr);
+                if let Some(index) = index {
+                    self.memory_mut().prg_rom[index] = data;
+                }
+                index.is_some()
+            }
+            _ => {
+                debug!("Nothing to poke at cartridge address {:#06X}", addr);
+                false
+            }
+        }
+    }
+    /// What a PPU read from the pattern tables (`$0000-$1FFF`) would return
+    fn ppu_peek(&self, addr: u16) -> u8 {
+        self.memory().chr_mem[self.chr_offset(addr)]
+    }
+    /// Writes CHR memory, even if it's ROM
+    fn ppu_poke(&mut self, addr: u16, data: u8) {
+        let index: usize = self.chr_offset(addr);
+        self.memory_mut().chr_mem[index] = data;
+    }
+    /// What a PPU read from the nametables (`$2000-$2FFF`) would return. Mappers that
+    /// override [`Mapper::nametable_read`] need to override this too.
+    fn nametable_peek(&self, addr: u16, vram: &[u8; 4096]) -> u8 {
+        vram[self.mirroring().vram_index(addr)]
+    }
 }
 
-pub fn init_mapper(rom: Ref<ROM>, memory: RcRef<Memory>, ppu: RcRef<PPU>) -> BoxMapper {
+pub fn init_mapper(rom: Ref<ROM>, memory: RcRef<Memory>) -> BoxMapper {
     match rom.mapper {
-        0 => Box::new(mapper000::Mapper000::new(memory, ppu)),
+        0 => Box::new(mapper000::Mapper000::new(&rom, memory)),
+        1 => Box::new(mapper001::Mapper001::new(&rom, memory)),
+        2 => Box::new(mapper002::Mapper002::new(&rom, memory)),
+        3 => Box::new(mapper003::Mapper003::new(&rom, memory)),
+        4 => Box::new(mapper004::Mapper004::new(&rom, memory)),
+        7 => Box::new(mapper007::Mapper007::new(&rom, memory)),
         _ => panic!(
             "Mapper {} ({}) is not supported!",
             rom.mapper, rom.submapper
@@ -1,6 +1,9 @@
 pub mod mem_map;
 
-use crate::cartridge::ROM;
+use crate::{
+    cartridge::{PRG_RAM_PAGE_SIZE, ROM},
+    save_state::{SaveState, SaveStateError, StateReader, StateWriter},
+};
 use std::cell::Ref;
 
 pub struct Memory {
@@ -13,7 +16,12 @@ pub struct Memory {
 
 impl Memory {
     pub fn new(rom: Ref<ROM>) -> Self {
-        let prg_ram: Vec<u8> = vec![0x00; rom.prg_ram_size];
+        // Old iNES headers rarely declare a PRG-RAM size, but a battery needs something to back
+        let prg_ram_size: usize = match rom.prg_ram_size {
+            0 if rom.uses_bat_mem => PRG_RAM_PAGE_SIZE,
+            size => size,
+        };
+        let prg_ram: Vec<u8> = vec![0x00; prg_ram_size];
         let (chr_mem, use_chr_ram): (Vec<u8>, bool) = match !rom.chr_rom.is_empty() {
             true => (rom.chr_rom.clone(), false),
             false => (vec![0x00; rom.chr_ram_size], true),
@@ -28,3 +36,22 @@ impl Memory {
         }
     }
 }
+
+impl SaveState for Memory {
+    fn write_state(&self, state: &mut StateWriter) {
+        state.bytes(&self.cpu_vram);
+        state.vec(&self.prg_ram);
+        if self.use_chr_ram {
+            state.vec(&self.chr_mem);
+        }
+    }
+
+    fn read_state(&mut self, state: &mut StateReader) -> Result<(), SaveStateError> {
+        state.bytes(&mut self.cpu_vram)?;
+        state.vec_into(&mut self.prg_ram, "PRG-RAM size")?;
+        if self.use_chr_ram {
+            state.vec_into(&mut self.chr_mem, "CHR-RAM size")?;
+        }
+        Ok(())
+    }
+}
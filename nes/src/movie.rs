@@ -0,0 +1,409 @@
+//! Input movies
+//!
+//! A movie is the controller input for every frame, starting from power-on or from a save
+//! state. Movies are stored in the FCEUX `.fm2` text format: a header of `key value` lines,
+//! followed by one line per frame:
+//!
+//! ```plaintext
+//! |c|RLDUTSBA|RLDUTSBA||
+//!  | |        |        +-- Port 2 (expansion port, unused)
+//!  | |        +----------- Controller 2
+//!  | +-------------------- Controller 1, '.' for released buttons
+//!  +---------------------- Commands (1 = soft reset, 2 = power cycle)
+//! ```
+//!
+//! Save states in FCEUX movies can't be loaded, so movies that start from one of our save states
+//! keep it in a separate `nesmurSavestate` key.
+
+// https://fceux.com/web/help/fm2.html
+
+use crate::{NES, cartridge::ROM, input_device::joypad::JoypadButton, prelude::*};
+use std::fmt::{self, Write};
+
+/// FCEUX version the movies claim to be made with, which decides how FCEUX plays them back
+const EMU_VERSION: u32 = 22020;
+/// Buttons in the order they appear in an input line, from bit 7 down to bit 0
+const BUTTON_CHARS: [char; 8] = ['R', 'L', 'D', 'U', 'T', 'S', 'B', 'A'];
+
+bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct MovieCommand: u8 {
+        const SOFT_RESET = 0b0000_0001;
+        const POWER      = 0b0000_0010;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MovieError {
+    UnsupportedVersion(String),
+    /// Anything but standard controllers, like a Zapper or a Four Score
+    UnsupportedDevice(&'static str),
+    /// The movie starts from an FCEUX save state
+    FCEUXSavestate,
+    BinaryInput,
+    InvalidValue(&'static str),
+    InvalidFrame(usize),
+}
+
+impl fmt::Display for MovieError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MovieError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported movie version {}", version)
+            }
+            MovieError::UnsupportedDevice(device) => write!(f, "Unsupported device: {}", device),
+            MovieError::FCEUXSavestate => {
+                write!(f, "Movies starting from FCEUX save states aren't supported")
+            }
+            MovieError::BinaryInput => write!(f, "Binary input logs aren't supported"),
+            MovieError::InvalidValue(key) => write!(f, "Invalid value for '{}'", key),
+            MovieError::InvalidFrame(line) => write!(f, "Invalid input on line {}", line),
+        }
+    }
+}
+
+impl std::error::Error for MovieError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MovieFrame {
+    pub commands: MovieCommand,
+    pub joypads: [JoypadButton; 2],
+}
+
+impl MovieFrame {
+    pub fn new(joypads: [JoypadButton; 2]) -> Self {
+        MovieFrame {
+            commands: MovieCommand::empty(),
+            joypads,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Movie {
+    pub rom_filename: String,
+    /// MD5 of the PRG-ROM followed by the CHR-ROM
+    pub rom_checksum: [u8; 16],
+    pub guid: String,
+    pub pal: bool,
+    pub rerecord_count: u32,
+    /// Whether controller 2 is plugged in
+    pub joypad2: bool,
+    pub comments: Vec<String>,
+    /// Our save state the movie starts from, or `None` to start from power-on
+    pub start_state: Option<Vec<u8>>,
+    pub frames: Vec<MovieFrame>,
+}
+
+impl Movie {
+    pub fn new(rom: &ROM, rom_filename: &str, start_state: Option<Vec<u8>>) -> Self {
+        Movie {
+            rom_filename: rom_filename.to_string(),
+            rom_checksum: rom_checksum(rom),
+            guid: new_guid(),
+            pal: matches!(rom.region, crate::cartridge::ROMRegion::PAL),
+            rerecord_count: 0,
+            joypad2: false,
+            comments: Vec::new(),
+            start_state,
+            frames: Vec::new(),
+        }
+    }
+
+    pub fn from_fm2(text: &str) -> Result<Self, MovieError> {
+        let mut movie: Movie = Movie {
+            rom_filename: String::new(),
+            rom_checksum: [0x00; 16],
+            guid: String::new(),
+            pal: false,
+            rerecord_count: 0,
+            joypad2: false,
+            comments: Vec::new(),
+            start_state: None,
+            frames: Vec::new(),
+        };
+        let mut joypad1: bool = true;
+
+        for (index, line) in text.lines().enumerate() {
+            let line: &str = line.trim_end_matches('\r');
+            if line.starts_with('|') {
+                let frame: MovieFrame = parse_frame(line, [joypad1, movie.joypad2])
+                    .ok_or(MovieError::InvalidFrame(index + 1))?;
+                movie.frames.push(frame);
+                continue;
+            }
+
+            let (key, value): (&str, &str) = line.split_once(' ').unwrap_or((line, ""));
+            match key {
+                "version" if value != "3" => {
+                    return Err(MovieError::UnsupportedVersion(value.to_string()));
+                }
+                "binary" if value == "1" => return Err(MovieError::BinaryInput),
+                "romFilename" => movie.rom_filename = value.to_string(),
+                "romChecksum" => {
+                    movie.rom_checksum = decode_bytes(value)
+                        .and_then(|bytes: Vec<u8>| bytes.try_into().ok())
+                        .ok_or(MovieError::InvalidValue("romChecksum"))?;
+                }
+                "guid" => movie.guid = value.to_string(),
+                "palFlag" => movie.pal = parse_flag(value, "palFlag")?,
+                "rerecordCount" => {
+                    movie.rerecord_count = value
+                        .parse()
+                        .map_err(|_| MovieError::InvalidValue("rerecordCount"))?;
+                }
+                "comment" => movie.comments.push(value.to_string()),
+                "fourscore" if parse_flag(value, "fourscore")? => {
+                    return Err(MovieError::UnsupportedDevice("Four Score"));
+                }
+                "port0" => joypad1 = parse_port(value, "port0")?,
+                "port1" => movie.joypad2 = parse_port(value, "port1")?,
+                "port2" if value != "0" => {
+                    return Err(MovieError::UnsupportedDevice("Expansion port device"));
+                }
+                "savestate" if !value.is_empty() => return Err(MovieError::FCEUXSavestate),
+                "nesmurSavestate" => {
+                    movie.start_state = Some(
+                        decode_bytes(value).ok_or(MovieError::InvalidValue("nesmurSavestate"))?,
+                    );
+                }
+                _ => {}
+            }
+        }
+        Ok(movie)
+    }
+
+    pub fn to_fm2(&self) -> String {
+        let mut text: String = String::new();
+        let _ = writeln!(text, "version 3");
+        let _ = writeln!(text, "emuVersion {}", EMU_VERSION);
+        let _ = writeln!(text, "rerecordCount {}", self.rerecord_count);
+        let _ = writeln!(text, "palFlag {}", self.pal as u8);
+        let _ = writeln!(text, "romFilename {}", self.rom_filename);
+        let _ = writeln!(
+            text,
+            "romChecksum base64:{}",
+            encode_base64(&self.rom_checksum)
+        );
+        let _ = writeln!(text, "guid {}", self.guid);
+        let _ = writeln!(text, "fourscore 0");
+        let _ = writeln!(text, "microphone 0");
+        let _ = writeln!(text, "port0 1");
+        let _ = writeln!(text, "port1 {}", self.joypad2 as u8);
+        let _ = writeln!(text, "port2 0");
+        let _ = writeln!(text, "FDS 0");
+        let _ = writeln!(text, "NewPPU 0");
+        for comment in self.comments.iter() {
+            let _ = writeln!(text, "comment {}", comment);
+        }
+        if let Some(state) = self.start_state.as_ref() {
+            let _ = writeln!(text, "nesmurSavestate base64:{}", encode_base64(state));
+        }
+
+        for frame in self.frames.iter() {
+            let _ = write!(text, "|{}|", frame.commands.bits());
+            write_joypad(&mut text, frame.joypads[0]);
+            text.push('|');
+            if self.joypad2 {
+                write_joypad(&mut text, frame.joypads[1]);
+            }
+            text.push_str("||\n");
+        }
+        text
+    }
+
+    /// Whether the movie was recorded with this ROM
+    pub fn matches_rom(&self, rom: &ROM) -> bool {
+        self.rom_checksum == rom_checksum(rom)
+    }
+}
+
+pub fn rom_checksum(rom: &ROM) -> [u8; 16] {
+    let mut context: md5::Context = md5::Context::new();
+    context.consume(&rom.prg_rom);
+    context.consume(&rom.chr_rom);
+    context.finalize().0
+}
+
+/// Plays a movie back one frame at a time
+pub struct MoviePlayer {
+    pub movie: Movie,
+    pub frame: usize,
+}
+
+impl MoviePlayer {
+    /// Puts the machine into the movie's starting state. `power_on` is a save state of the
+    /// machine right after it was powered on.
+    pub fn start(
+        nes: &mut NES,
+        movie: Movie,
+        power_on: &[u8],
+    ) -> Result<Self, crate::save_state::SaveStateError> {
+        if !movie.matches_rom(&nes.rom()) {
+            warn!(
+                "Movie was recorded with a different ROM ({})",
+                movie.rom_filename
+            );
+        }
+        nes.load_state(movie.start_state.as_deref().unwrap_or(power_on))?;
+        Ok(MoviePlayer { movie, frame: 0 })
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.frame >= self.movie.frames.len()
+    }
+
+    /// Applies the next frame's input, call it at the start of every frame
+    pub fn next_frame(&mut self, nes: &mut NES) {
+        let Some(frame) = self.movie.frames.get(self.frame).copied() else {
+            return;
+        };
+        self.frame += 1;
+
+        if frame.commands.contains(MovieCommand::SOFT_RESET) {
+            nes.reset();
+        }
+        if frame.commands.contains(MovieCommand::POWER) {
+            warn!(
+                "Power cycling isn't supported in movies, ignoring it on frame {}",
+                self.frame
+            );
+        }
+        set_joypads(nes, frame.joypads);
+    }
+}
+
+/// Presses exactly the given buttons on the connected joypads
+pub fn set_joypads(nes: &mut NES, joypads: [JoypadButton; 2]) {
+    if nes.device1.is_some() {
+        let mut device: RefMut<crate::BoxNESDevice> = nes.device1_mut();
+        device.set_button_pressed_status(Box::new(JoypadButton::all()), false);
+        device.set_button_pressed_status(Box::new(joypads[0]), true);
+    }
+    if nes.device2.is_some() {
+        let mut device: RefMut<crate::BoxNESDevice> = nes.device2_mut();
+        device.set_button_pressed_status(Box::new(JoypadButton::all()), false);
+        device.set_button_pressed_status(Box::new(joypads[1]), true);
+    }
+}
+
+fn parse_flag(value: &str, key: &'static str) -> Result<bool, MovieError> {
+    match value {
+        "0" => Ok(false),
+        "1" => Ok(true),
+        _ => Err(MovieError::InvalidValue(key)),
+    }
+}
+
+/// Whether a joypad is plugged into the port
+fn parse_port(value: &str, key: &'static str) -> Result<bool, MovieError> {
+    match value {
+        "0" => Ok(false),
+        "1" => Ok(true),
+        "2" => Err(MovieError::UnsupportedDevice("Zapper")),
+        _ => Err(MovieError::InvalidValue(key)),
+    }
+}
+
+fn parse_frame(line: &str, connected: [bool; 2]) -> Option<MovieFrame> {
+    let mut fields: std::str::Split<char> = line[1..].split('|');
+    let commands: u8 = fields.next()?.trim().parse().ok()?;
+    let mut frame: MovieFrame = MovieFrame {
+        commands: MovieCommand::from_bits_truncate(commands),
+        joypads: [JoypadButton::empty(); 2],
+    };
+
+    for (port, connected) in connected.iter().enumerate() {
+        let field: &str = fields.next()?;
+        if !connected {
+            continue;
+        }
+        if field.chars().count() != BUTTON_CHARS.len() {
+            return None;
+        }
+        for (bit, char) in field.chars().enumerate() {
+            if char != '.' && char != ' ' {
+                frame.joypads[port].insert(JoypadButton::from_bits_retain(0b1000_0000 >> bit));
+            }
+        }
+    }
+    Some(frame)
+}
+
+fn write_joypad(text: &mut String, joypad: JoypadButton) {
+    for (bit, char) in BUTTON_CHARS.iter().enumerate() {
+        match joypad.bits() & (0b1000_0000 >> bit) != 0 {
+            true => text.push(*char),
+            false => text.push('.'),
+        }
+    }
+}
+
+/// A GUID that is different for every movie, built from the current time
+fn new_guid() -> String {
+    let nanos: u128 = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |time: std::time::Duration| time.as_nanos());
+    let hash: [u8; 16] = md5::compute(nanos.to_le_bytes()).0;
+    let hex: String = hash
+        .iter()
+        .map(|byte: &u8| format!("{:02X}", byte))
+        .collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+const BASE64_CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut text: String = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group: u32 = (chunk[0] as u32) << 16
+            | (chunk.get(1).copied().unwrap_or(0) as u32) << 8
+            | chunk.get(2).copied().unwrap_or(0) as u32;
+        for i in 0..4 {
+            match i <= chunk.len() {
+                true => text.push(BASE64_CHARS[(group >> (18 - 6 * i)) as usize & 0x3F] as char),
+                false => text.push('='),
+            }
+        }
+    }
+    text
+}
+
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let mut bytes: Vec<u8> = Vec::with_capacity(text.len() / 4 * 3);
+    let mut group: u32 = 0;
+    let mut bits: u32 = 0;
+    for char in text.trim_end_matches('=').bytes() {
+        let value: u32 = BASE64_CHARS.iter().position(|c: &u8| *c == char)? as u32;
+        group = group << 6 | value;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((group >> bits) as u8);
+        }
+    }
+    Some(bytes)
+}
+
+/// Binary values in FM2 headers are either `base64:...` or `0x...` hex
+fn decode_bytes(value: &str) -> Option<Vec<u8>> {
+    if let Some(base64) = value.strip_prefix("base64:") {
+        return decode_base64(base64);
+    }
+    let hex: &str = value.strip_prefix("0x")?;
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
@@ -7,19 +7,27 @@ use self::{
     registers::{AddrRegister, ControlRegister, MaskRegister, StatusRegister},
     renderer::Renderer,
 };
-use crate::{RcRef, Region, cartridge::Mirroring, memory::Memory, prelude::*};
+use crate::{
+    BoxMapper, RcRef, Region,
+    breakpoint::{self, Access, AddressSpace, Breakpoints},
+    prelude::*,
+    save_state::{SaveState, SaveStateError, StateReader, StateWriter},
+};
 
 #[rustfmt::skip]
 impl NESAccess for PPU {
-    fn memory(&self) -> Ref<'_, Memory> { self.memory.borrow() }
-    fn memory_mut(&self) -> RefMut<'_, Memory> { self.memory.borrow_mut() }
+    fn mapper(&self) -> Ref<'_, BoxMapper> { self.mapper.borrow() }
+    fn mapper_mut(&self) -> RefMut<'_, BoxMapper> { self.mapper.borrow_mut() }
+    fn breakpoints(&self) -> Ref<'_, Breakpoints> { self.breakpoints.borrow() }
+    fn breakpoints_mut(&self) -> RefMut<'_, Breakpoints> { self.breakpoints.borrow_mut() }
 }
 
 pub struct PPU {
-    memory: RcRef<Memory>,
+    mapper: RcRef<BoxMapper>,
+    breakpoints: RcRef<Breakpoints>,
 
-    pub vram: [u8; 2048],
-    pub mirroring: Mirroring,
+    /// 2 KiB of CIRAM, followed by the 2 KiB a four-screen cartridge adds
+    pub vram: [u8; 4096],
     pub oam_addr: u8,
     pub oam_data: [u8; 256],
     pub palette_table: [u8; 32],
@@ -43,11 +51,11 @@ pub struct PPU {
 }
 
 impl PPU {
-    pub fn new(memory: RcRef<Memory>, mirroring: Mirroring) -> Self {
+    pub fn new(mapper: RcRef<BoxMapper>, breakpoints: RcRef<Breakpoints>) -> Self {
         PPU {
-            memory,
-            vram: [0x00; 2048],
-            mirroring,
+            mapper,
+            breakpoints,
+            vram: [0x00; 4096],
             oam_addr: 0x00,
             oam_data: [0x00; 256],
             palette_table: [0x00; 32],
@@ -122,25 +130,6 @@ impl PPU {
         false
     }
 
-    // Horizontal:
-    //   [ A ] [ a ]
-    //   [ B ] [ b ]
-    // Vertical:
-    //   [ A ] [ B ]
-    //   [ a ] [ b ]
-    pub fn mirror_vram_addr(&self, addr: u16) -> u16 {
-        let mirrored_vram: u16 = addr & 0b0010_1111_1111_1111; // mirror down 0x3000-0x3EFF to 0x2000 - 0x2EFF
-        let vram_index: u16 = mirrored_vram - 0x2000; // to vram vector
-        let name_table: u16 = vram_index / 0x0400;
-        match (&self.mirroring, name_table) {
-            (Mirroring::Vertical, 2) | (Mirroring::Vertical, 3) => vram_index - 0x0800,
-            (Mirroring::Horizontal, 2) => vram_index - 0x0400,
-            (Mirroring::Horizontal, 1) => vram_index - 0x0400,
-            (Mirroring::Horizontal, 3) => vram_index - 0x0800,
-            _ => vram_index,
-        }
-    }
-
     fn increment_vram_addr(&mut self) {
         self.vram_addr.increment(self.ctrl.vram_addr_increment());
     }
@@ -217,6 +206,8 @@ impl PPU {
             if self.register_latch {
                 self.temp_addr.set_lo_byte(value);
                 self.vram_addr = self.temp_addr;
+                let addr: u16 = self.vram_addr.get();
+                self.mapper_mut().ppu_bus_address(addr, self.cycles);
             } else {
                 self.temp_addr.set_hi_byte(value);
             }
@@ -226,24 +217,25 @@ impl PPU {
 
     pub fn write_to_data(&mut self, value: u8) {
         let addr: u16 = self.vram_addr.get();
+        self.mapper_mut().ppu_bus_address(addr, self.cycles);
         self.raw_write_to_data(addr, value);
         self.increment_vram_addr();
     }
 
     pub fn raw_write_to_data(&mut self, addr: u16, value: u8) {
+        breakpoint::watch(
+            &self.breakpoints,
+            AddressSpace::Ppu,
+            Access::WRITE,
+            addr,
+            value,
+        );
         match addr {
-            0..=0x1FFF => {
-                if self.memory().use_chr_ram {
-                    self.memory_mut().chr_mem[addr as usize] = value;
-                } else {
-                    error!(
-                        "[PPU] Attempted to write {:#04X} to CHR-ROM {:#06X}",
-                        value, addr
-                    );
-                }
-            }
+            0..=0x1FFF => self.mapper_mut().ppu_write(addr, value),
             0x2000..=0x2FFF => {
-                self.vram[self.mirror_vram_addr(addr) as usize] = value;
+                self.mapper
+                    .borrow_mut()
+                    .nametable_write(addr, value, &mut self.vram);
             }
             0x3000..=0x3EFF => {
                 error!("PPU Addr {:#06X} shouldn't be used in reality", addr)
@@ -264,6 +256,7 @@ impl PPU {
 
     pub fn read_data(&mut self) -> u8 {
         let addr: u16 = self.vram_addr.get();
+        self.mapper_mut().ppu_bus_address(addr, self.cycles);
 
         self.increment_vram_addr();
 
@@ -276,10 +269,30 @@ impl PPU {
         }
     }
 
+    /// A read done by the renderer, which the mapper sees on the PPU bus while rendering is on
+    pub fn fetch(&mut self, addr: u16) -> u8 {
+        if self.mask.rendering() {
+            self.mapper_mut().ppu_bus_address(addr, self.cycles);
+        }
+        self.raw_read_data(addr)
+    }
+
     pub fn raw_read_data(&mut self, addr: u16) -> u8 {
+        let value: u8 = self.read_mapped(addr);
+        breakpoint::watch(
+            &self.breakpoints,
+            AddressSpace::Ppu,
+            Access::READ,
+            addr,
+            value,
+        );
+        value
+    }
+
+    fn read_mapped(&mut self, addr: u16) -> u8 {
         match addr {
-            0..=0x1FFF => self.memory().chr_mem[addr as usize],
-            0x2000..=0x2FFF => self.vram[self.mirror_vram_addr(addr) as usize],
+            0..=0x1FFF => self.mapper_mut().ppu_read(addr),
+            0x2000..=0x2FFF => self.mapper_mut().nametable_read(addr, &self.vram),
             0x3000..=0x3EFF => {
                 error!("PPU Addr {:#06X} shouldn't be used in reality", addr);
                 0
@@ -302,4 +315,113 @@ impl PPU {
             self.oam_addr = self.oam_addr.wrapping_add(1);
         }
     }
+
+    /// What a PPU read from `addr` would return, without clocking the mapper or
+    /// tripping breakpoints
+    pub fn peek(&self, addr: u16) -> u8 {
+        let addr: u16 = addr & 0x3FFF;
+        match addr {
+            0..=0x1FFF => self.mapper().ppu_peek(addr),
+            // $3000-$3EFF mirrors $2000-$2EFF
+            0x2000..=0x3EFF => self.mapper().nametable_peek(addr & 0x2FFF, &self.vram),
+            _ => self.palette_table[Self::palette_index(addr)],
+        }
+    }
+
+    /// Writes whatever `addr` maps to, including CHR-ROM
+    pub fn poke(&mut self, addr: u16, value: u8) {
+        let addr: u16 = addr & 0x3FFF;
+        match addr {
+            0..=0x1FFF => self.mapper_mut().ppu_poke(addr, value),
+            0x2000..=0x3EFF => {
+                self.mapper
+                    .borrow_mut()
+                    .nametable_write(addr & 0x2FFF, value, &mut self.vram);
+            }
+            _ => self.palette_table[Self::palette_index(addr)] = value,
+        }
+    }
+
+    /// What a CPU read from PPU register `addr` would return, without clearing VBlank,
+    /// the write latch or advancing `vram_addr`
+    pub fn peek_register(&self, addr: u16) -> u8 {
+        match addr & 0x2007 {
+            0x2002 => self.status.snapshot(),
+            0x2004 => self.read_oam_data(),
+            0x2007 => {
+                let addr: u16 = self.vram_addr.get();
+                if addr <= 0x3EFF {
+                    self.internal_data_buf
+                } else {
+                    self.peek(addr)
+                }
+            }
+            _ => 0,
+        }
+    }
+
+    // https://www.nesdev.org/wiki/PPU_palettes#Memory_Map
+    fn palette_index(addr: u16) -> usize {
+        let index: usize = (addr & 0x1F) as usize;
+        // $3F10/$3F14/$3F18/$3F1C are mirrors of $3F00/$3F04/$3F08/$3F0C
+        if index & 0x13 == 0x10 {
+            index & !0x10
+        } else {
+            index
+        }
+    }
+}
+
+impl SaveState for PPU {
+    fn write_state(&self, state: &mut StateWriter) {
+        state.bytes(&self.vram);
+        state.u8(self.oam_addr);
+        state.bytes(&self.oam_data);
+        state.bytes(&self.palette_table);
+
+        state.bool(self.register_latch);
+        state.u8(self.ctrl.bits());
+        state.u8(self.mask.bits());
+        state.u8(self.status.bits());
+        state.u8(self.vram_addr.get_hi_byte());
+        state.u8(self.vram_addr.get_lo_byte());
+        state.u8(self.temp_addr.get_hi_byte());
+        state.u8(self.temp_addr.get_lo_byte());
+        state.u8(self.scroll_fine_x);
+        state.u8(self.internal_data_buf);
+
+        state.usize(self.scanline);
+        state.bool(self.odd_frame);
+        state.usize(self.dot);
+        state.usize(self.cycles);
+        state.bool(self.nmi_interrupt.is_some());
+        state.u8(self.nmi_interrupt.unwrap_or(0));
+    }
+
+    fn read_state(&mut self, state: &mut StateReader) -> Result<(), SaveStateError> {
+        state.bytes(&mut self.vram)?;
+        self.oam_addr = state.u8()?;
+        state.bytes(&mut self.oam_data)?;
+        state.bytes(&mut self.palette_table)?;
+
+        self.register_latch = state.bool()?;
+        self.ctrl = ControlRegister::from_bits_retain(state.u8()?);
+        self.mask = MaskRegister::from_bits_retain(state.u8()?);
+        self.status = StatusRegister::from_bits_retain(state.u8()?);
+        self.vram_addr.set_hi_byte(state.u8()?);
+        self.vram_addr.set_lo_byte(state.u8()?);
+        self.temp_addr.set_hi_byte(state.u8()?);
+        self.temp_addr.set_lo_byte(state.u8()?);
+        self.scroll_fine_x = state.u8()?;
+        self.internal_data_buf = state.u8()?;
+
+        self.scanline = state.usize()?;
+        self.odd_frame = state.bool()?;
+        self.dot = state.usize()?;
+        self.cycles = state.usize()?;
+        let nmi_pending: bool = state.bool()?;
+        let nmi_interrupt: u8 = state.u8()?;
+        self.nmi_interrupt = nmi_pending.then_some(nmi_interrupt);
+        Ok(())
+    }
 }
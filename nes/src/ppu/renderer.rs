@@ -1,6 +1,10 @@
-use super::{PPU, palette, sprite::Sprite};
+use super::{
+    PPU, palette,
+    sprite::{Sprite, SpriteTileIndex},
+};
 use crate::{
     SCREEN_HEIGHT, SCREEN_WIDTH,
+    save_state::{SaveState, SaveStateError, StateReader, StateWriter},
     tools::{BitPlane, nth_bit},
 };
 use std::hash::{Hash, Hasher};
@@ -90,7 +94,9 @@ impl Renderer {
                 }
             }
             257 => self.eval_sprites(ppu),
-            321 => self.load_sprites(ppu),
+            // https://www.nesdev.org/wiki/PPU_rendering#Cycles_257-320
+            258..=320 if (ppu.dot - 257) % 8 == 4 => self.fetch_sprite((ppu.dot - 257) / 8, ppu),
+            321 => self.primary_oam = self.secondary_oam.clone(),
             _ => {}
         }
     }
@@ -117,13 +123,13 @@ impl Renderer {
                     self.reload_shift_registers();
                 }
                 2 => {
-                    self.nametable_entry = ppu.raw_read_data(self.scratch_address);
+                    self.nametable_entry = ppu.fetch(self.scratch_address);
                 }
                 3 => {
                     self.scratch_address = ppu.vram_addr.get_attribute_addr();
                 }
                 4 => {
-                    self.attribute_entry = ppu.raw_read_data(self.scratch_address);
+                    self.attribute_entry = ppu.fetch(self.scratch_address);
                     if ppu.vram_addr.get_coarse_y() & 2 != 0 {
                         self.attribute_entry >>= 4;
                     }
@@ -136,13 +142,13 @@ impl Renderer {
                         + ppu.vram_addr.get_tile_offset(self.nametable_entry);
                 }
                 6 => {
-                    self.background_latch.lo = ppu.raw_read_data(self.scratch_address);
+                    self.background_latch.lo = ppu.fetch(self.scratch_address);
                 }
                 7 => {
                     self.scratch_address += 8;
                 }
                 0 => {
-                    self.background_latch.hi = ppu.raw_read_data(self.scratch_address);
+                    self.background_latch.hi = ppu.fetch(self.scratch_address);
 
                     if ppu.mask.rendering() {
                         ppu.vram_addr.scroll_x();
@@ -151,7 +157,7 @@ impl Renderer {
                 _ => panic!("This shouldn't happen!"),
             },
             256 => {
-                self.background_latch.hi = ppu.raw_read_data(self.scratch_address);
+                self.background_latch.hi = ppu.fetch(self.scratch_address);
                 if ppu.mask.rendering() {
                     ppu.vram_addr.scroll_y();
                 }
@@ -177,10 +183,10 @@ impl Renderer {
                 self.scratch_address = ppu.vram_addr.get_nametable_addr();
             }
             338 => {
-                self.nametable_entry = ppu.raw_read_data(self.scratch_address);
+                self.nametable_entry = ppu.fetch(self.scratch_address);
             }
             340 => {
-                self.nametable_entry = ppu.raw_read_data(self.scratch_address);
+                self.nametable_entry = ppu.fetch(self.scratch_address);
                 if pre && ppu.mask.rendering() && ppu.odd_frame {
                     ppu.dot += 1;
                 }
@@ -273,14 +279,34 @@ impl Renderer {
         }
     }
 
+    /// Fetches the pattern data of a secondary OAM slot. Empty slots still fetch tile `$FF`,
+    /// which mappers watching the PPU bus rely on
+    pub fn fetch_sprite(&mut self, slot: usize, ppu: &mut PPU) {
+        match self.secondary_oam.get_mut(slot) {
+            Some(sprite) => {
+                let tile_address: u16 = sprite.tile_address(ppu.scanline, ppu.ctrl);
+                sprite.data_lo = ppu.fetch(tile_address);
+                sprite.data_hi = ppu.fetch(tile_address + 8);
+            }
+            None => {
+                let tile_index: SpriteTileIndex = SpriteTileIndex(0xFF);
+                let tile_address: u16 = if ppu.ctrl.sprite_size() == 16 {
+                    tile_index.base() + tile_index.large_offset()
+                } else {
+                    ppu.ctrl.sprite_pattern_addr() + tile_index.small_offset()
+                };
+                ppu.fetch(tile_address);
+                ppu.fetch(tile_address + 8);
+            }
+        }
+    }
+
+    /// Fetches every slot at once and makes them visible to the next scanline
     pub fn load_sprites(&mut self, ppu: &mut PPU) {
-        let mut sprites: Vec<Sprite> = self.secondary_oam.clone();
-        for sprite in sprites.iter_mut() {
-            let tile_address: u16 = sprite.tile_address(ppu.scanline, ppu.ctrl);
-            sprite.data_lo = ppu.raw_read_data(tile_address);
-            sprite.data_hi = ppu.raw_read_data(tile_address + 8);
+        for slot in 0..8 {
+            self.fetch_sprite(slot, ppu);
         }
-        self.primary_oam = sprites;
+        self.primary_oam = self.secondary_oam.clone();
     }
 
     pub fn reload_shift_registers(&mut self) {
@@ -310,3 +336,83 @@ impl Renderer {
         self.pixels[pixel_index] = palette::NTSC[rgb_index];
     }
 }
+
+fn write_sprites(state: &mut StateWriter, sprites: &[Sprite]) {
+    state.u8(sprites.len() as u8);
+    for sprite in sprites {
+        state.u8(sprite.x);
+        state.u8(sprite.y);
+        state.bool(sprite.status_flip_y);
+        state.bool(sprite.status_flip_x);
+        state.bool(sprite.status_behind_background);
+        state.u8(sprite.status_palette);
+        state.u8(sprite.tile_index.0);
+        state.u8(sprite.data_lo);
+        state.u8(sprite.data_hi);
+        state.u8(sprite.oam_index as u8);
+    }
+}
+
+fn read_sprites(state: &mut StateReader, sprites: &mut Vec<Sprite>) -> Result<(), SaveStateError> {
+    let count: u8 = state.u8()?;
+    if count > 8 {
+        return Err(SaveStateError::InvalidValue("sprite count"));
+    }
+
+    sprites.clear();
+    for _ in 0..count {
+        sprites.push(Sprite {
+            x: state.u8()?,
+            y: state.u8()?,
+            status_flip_y: state.bool()?,
+            status_flip_x: state.bool()?,
+            status_behind_background: state.bool()?,
+            status_palette: state.u8()?,
+            tile_index: SpriteTileIndex(state.u8()?),
+            data_lo: state.u8()?,
+            data_hi: state.u8()?,
+            oam_index: state.u8()? as usize,
+        });
+    }
+    Ok(())
+}
+
+impl SaveState for Renderer {
+    fn write_state(&self, state: &mut StateWriter) {
+        state.u8(self.background_latch.hi);
+        state.u8(self.background_latch.lo);
+        state.u16(self.background_shift.hi);
+        state.u16(self.background_shift.lo);
+        state.u8(self.attribute_latch.hi);
+        state.u8(self.attribute_latch.lo);
+        state.u8(self.attribute_shift.hi);
+        state.u8(self.attribute_shift.lo);
+        state.u16(self.scratch_address);
+        state.u8(self.nametable_entry);
+        state.u8(self.attribute_entry);
+        write_sprites(state, &self.primary_oam);
+        write_sprites(state, &self.secondary_oam);
+        // The frame being drawn, so a state loaded mid-frame finishes the same picture
+        for pixel in self.pixels.iter() {
+            state.bytes(&[pixel.0, pixel.1, pixel.2]);
+        }
+    }
+
+    fn read_state(&mut self, state: &mut StateReader) -> Result<(), SaveStateError> {
+        self.background_latch = BitPlane::new(state.u8()?, state.u8()?);
+        self.background_shift = BitPlane::new(state.u16()?, state.u16()?);
+        self.attribute_latch = BitPlane::new(state.u8()?, state.u8()?);
+        self.attribute_shift = BitPlane::new(state.u8()?, state.u8()?);
+        self.scratch_address = state.u16()?;
+        self.nametable_entry = state.u8()?;
+        self.attribute_entry = state.u8()?;
+        read_sprites(state, &mut self.primary_oam)?;
+        read_sprites(state, &mut self.secondary_oam)?;
+        for pixel in self.pixels.iter_mut() {
+            let mut rgb: [u8; 3] = [0x00; 3];
+            state.bytes(&mut rgb)?;
+            *pixel = RGB(rgb[0], rgb[1], rgb[2]);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,149 @@
+//! Rewind buffer
+//!
+//! Holds the newest save state as-is, and every older one as its difference to the state after
+//! it. The difference is the XOR of both states, with runs of unchanged bytes compressed:
+//!
+//! ```plaintext
+//! Delta
+//!   u32       Length of the older state
+//! Run (repeated until the older state is complete)
+//!   u16       Unchanged bytes
+//!   u16       Changed bytes
+//!   [u8]      Changed bytes, XORed with the newer state
+//! ```
+//!
+//! Rewinding only ever walks from the newest state backwards, so the oldest deltas can be
+//! dropped to stay within the memory budget.
+
+use std::collections::VecDeque;
+
+/// A literal run ends once this many unchanged bytes follow it
+const MIN_UNCHANGED_RUN: usize = 4;
+
+pub struct RewindBuffer {
+    /// Budget in bytes for the newest state and all deltas
+    pub capacity: usize,
+    newest: Option<Vec<u8>>,
+    deltas: VecDeque<Vec<u8>>,
+    size: usize,
+}
+
+impl RewindBuffer {
+    pub fn new(capacity: usize) -> Self {
+        RewindBuffer {
+            capacity,
+            newest: None,
+            deltas: VecDeque::new(),
+            size: 0,
+        }
+    }
+
+    /// Number of states that can be rewound to
+    pub fn len(&self) -> usize {
+        self.deltas.len() + self.newest.is_some() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.newest.is_none()
+    }
+
+    /// Bytes currently used
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn clear(&mut self) {
+        self.newest = None;
+        self.deltas.clear();
+        self.size = 0;
+    }
+
+    pub fn push(&mut self, state: Vec<u8>) {
+        if let Some(previous) = self.newest.take() {
+            let delta: Vec<u8> = encode_delta(&state, &previous);
+            self.size = self.size - previous.len() + delta.len();
+            self.deltas.push_back(delta);
+        }
+        self.size += state.len();
+        self.newest = Some(state);
+
+        while self.size > self.capacity
+            && let Some(oldest) = self.deltas.pop_front()
+        {
+            self.size -= oldest.len();
+        }
+    }
+
+    /// Takes the newest state, making the one before it the newest
+    pub fn pop(&mut self) -> Option<Vec<u8>> {
+        let newest: Vec<u8> = self.newest.take()?;
+        self.size -= newest.len();
+
+        if let Some(delta) = self.deltas.pop_back() {
+            let previous: Vec<u8> = decode_delta(&newest, &delta);
+            self.size = self.size - delta.len() + previous.len();
+            self.newest = Some(previous);
+        }
+        Some(newest)
+    }
+}
+
+fn xor_at(newer: &[u8], older: &[u8], index: usize) -> u8 {
+    older[index] ^ newer.get(index).copied().unwrap_or(0)
+}
+
+/// Encodes `older` relative to `newer`
+pub fn encode_delta(newer: &[u8], older: &[u8]) -> Vec<u8> {
+    let mut delta: Vec<u8> = Vec::new();
+    delta.extend_from_slice(&(older.len() as u32).to_le_bytes());
+
+    let mut index: usize = 0;
+    while index < older.len() {
+        let unchanged_start: usize = index;
+        while index < older.len()
+            && index - unchanged_start < u16::MAX as usize
+            && xor_at(newer, older, index) == 0
+        {
+            index += 1;
+        }
+
+        let changed_start: usize = index;
+        while index < older.len() && index - changed_start < u16::MAX as usize {
+            let unchanged_run: bool = index + MIN_UNCHANGED_RUN <= older.len()
+                && (index..index + MIN_UNCHANGED_RUN).all(|i: usize| xor_at(newer, older, i) == 0);
+            if unchanged_run {
+                break;
+            }
+            index += 1;
+        }
+
+        delta.extend_from_slice(&((changed_start - unchanged_start) as u16).to_le_bytes());
+        delta.extend_from_slice(&((index - changed_start) as u16).to_le_bytes());
+        delta.extend((changed_start..index).map(|i: usize| xor_at(newer, older, i)));
+    }
+    delta
+}
+
+/// Rebuilds the older state from `newer` and the delta from [`encode_delta`]
+pub fn decode_delta(newer: &[u8], delta: &[u8]) -> Vec<u8> {
+    let read_u16 =
+        |pos: usize| -> usize { u16::from_le_bytes([delta[pos], delta[pos + 1]]) as usize };
+
+    let len: usize = u32::from_le_bytes([delta[0], delta[1], delta[2], delta[3]]) as usize;
+    let mut older: Vec<u8> = newer.to_vec();
+    older.resize(len, 0);
+
+    let mut pos: usize = 4;
+    let mut index: usize = 0;
+    while pos < delta.len() {
+        index += read_u16(pos);
+        let changed: usize = read_u16(pos + 2);
+        pos += 4;
+        for byte in &delta[pos..pos + changed] {
+            older[index] ^= byte;
+            index += 1;
+        }
+        pos += changed;
+    }
+    older
+}
@@ -0,0 +1,275 @@
+//! Save states
+//!
+//! A save state is a small header followed by one chunk per component, all little-endian:
+//!
+//! ```plaintext
+//! Header
+//!   [u8; 8]   Magic ("NESMURSS")
+//!   u16       Format version
+//!   u64       ROM hash (FNV-1a of PRG-ROM followed by CHR-ROM)
+//! Chunk (repeated until the end of the state)
+//!   [u8; 4]   Tag ("CPU ", "PPU ", "MAPR", ...)
+//!   u32       Payload length
+//!   [u8]      Payload
+//! ```
+//!
+//! Unknown chunks are skipped when loading. Host-side audio settings (mixer, filters and the
+//! resampler) aren't part of the machine, so they aren't saved.
+
+use crate::cartridge::ROM;
+use std::{collections::HashMap, fmt};
+
+pub const MAGIC: [u8; 8] = *b"NESMURSS";
+pub const VERSION: u16 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SaveStateError {
+    InvalidMagic,
+    UnsupportedVersion(u16),
+    ROMMismatch,
+    MissingChunk([u8; 4]),
+    UnexpectedEnd,
+    /// A chunk had bytes left over after its component was loaded
+    TrailingData([u8; 4]),
+    InvalidValue(&'static str),
+}
+
+impl fmt::Display for SaveStateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveStateError::InvalidMagic => write!(f, "Not a save state"),
+            SaveStateError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported save state version {}", version)
+            }
+            SaveStateError::ROMMismatch => write!(f, "Save state was made with a different ROM"),
+            SaveStateError::MissingChunk(tag) => {
+                write!(f, "Missing '{}' chunk", String::from_utf8_lossy(tag))
+            }
+            SaveStateError::UnexpectedEnd => write!(f, "Save state ended unexpectedly"),
+            SaveStateError::TrailingData(tag) => {
+                write!(
+                    f,
+                    "Trailing data in '{}' chunk",
+                    String::from_utf8_lossy(tag)
+                )
+            }
+            SaveStateError::InvalidValue(what) => write!(f, "Invalid value for {}", what),
+        }
+    }
+}
+
+impl std::error::Error for SaveStateError {}
+
+/// Components that are part of a save state
+pub trait SaveState {
+    fn write_state(&self, state: &mut StateWriter);
+    fn read_state(&mut self, state: &mut StateReader) -> Result<(), SaveStateError>;
+}
+
+// https://en.wikipedia.org/wiki/Fowler%E2%80%93Noll%E2%80%93Vo_hash_function
+pub fn rom_hash(rom: &ROM) -> u64 {
+    const FNV_OFFSET_BASIS: u64 = 0xCBF2_9CE4_8422_2325;
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01B3;
+
+    let mut hash: u64 = FNV_OFFSET_BASIS;
+    for byte in rom.prg_rom.iter().chain(rom.chr_rom.iter()) {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+pub struct StateWriter {
+    buffer: Vec<u8>,
+}
+
+impl StateWriter {
+    pub fn new() -> Self {
+        StateWriter { buffer: Vec::new() }
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buffer
+    }
+
+    pub fn header(&mut self, rom: &ROM) {
+        self.bytes(&MAGIC);
+        self.u16(VERSION);
+        self.u64(rom_hash(rom));
+    }
+
+    pub fn chunk(&mut self, tag: [u8; 4], write: impl FnOnce(&mut StateWriter)) {
+        let mut chunk: StateWriter = StateWriter::new();
+        write(&mut chunk);
+
+        self.bytes(&tag);
+        self.u32(chunk.buffer.len() as u32);
+        self.bytes(&chunk.buffer);
+    }
+
+    pub fn u8(&mut self, value: u8) {
+        self.buffer.push(value);
+    }
+
+    pub fn u16(&mut self, value: u16) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    pub fn u32(&mut self, value: u32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    pub fn u64(&mut self, value: u64) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    pub fn usize(&mut self, value: usize) {
+        self.u64(value as u64);
+    }
+
+    pub fn bool(&mut self, value: bool) {
+        self.u8(value as u8);
+    }
+
+    /// Fixed-size data, read back with [`StateReader::bytes`]
+    pub fn bytes(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Length-prefixed data, read back with [`StateReader::vec`]
+    pub fn vec(&mut self, bytes: &[u8]) {
+        self.u32(bytes.len() as u32);
+        self.bytes(bytes);
+    }
+}
+
+pub struct StateReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> StateReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        StateReader { data, pos: 0 }
+    }
+
+    pub fn header(&mut self, rom: &ROM) -> Result<(), SaveStateError> {
+        let mut magic: [u8; 8] = [0x00; 8];
+        self.bytes(&mut magic)
+            .map_err(|_| SaveStateError::InvalidMagic)?;
+        if magic != MAGIC {
+            return Err(SaveStateError::InvalidMagic);
+        }
+
+        let version: u16 = self.u16()?;
+        if version != VERSION {
+            return Err(SaveStateError::UnsupportedVersion(version));
+        }
+
+        if self.u64()? != rom_hash(rom) {
+            return Err(SaveStateError::ROMMismatch);
+        }
+        Ok(())
+    }
+
+    /// Splits the rest of the state into its chunks
+    pub fn chunks(mut self) -> Result<HashMap<[u8; 4], StateReader<'a>>, SaveStateError> {
+        let mut chunks: HashMap<[u8; 4], StateReader<'a>> = HashMap::new();
+        while self.pos < self.data.len() {
+            let mut tag: [u8; 4] = [0x00; 4];
+            self.bytes(&mut tag)?;
+            let len: usize = self.u32()? as usize;
+            chunks.insert(tag, StateReader::new(self.take(len)?));
+        }
+        Ok(chunks)
+    }
+
+    /// Errors if anything is left over, which means the chunk doesn't match the component
+    pub fn finish(&self, tag: [u8; 4]) -> Result<(), SaveStateError> {
+        match self.pos == self.data.len() {
+            true => Ok(()),
+            false => Err(SaveStateError::TrailingData(tag)),
+        }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], SaveStateError> {
+        let end: usize = self
+            .pos
+            .checked_add(len)
+            .filter(|end: &usize| *end <= self.data.len())
+            .ok_or(SaveStateError::UnexpectedEnd)?;
+        let slice: &'a [u8] = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    pub fn u8(&mut self) -> Result<u8, SaveStateError> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn u16(&mut self) -> Result<u16, SaveStateError> {
+        let mut bytes: [u8; 2] = [0x00; 2];
+        self.bytes(&mut bytes)?;
+        Ok(u16::from_le_bytes(bytes))
+    }
+
+    pub fn u32(&mut self) -> Result<u32, SaveStateError> {
+        let mut bytes: [u8; 4] = [0x00; 4];
+        self.bytes(&mut bytes)?;
+        Ok(u32::from_le_bytes(bytes))
+    }
+
+    pub fn u64(&mut self) -> Result<u64, SaveStateError> {
+        let mut bytes: [u8; 8] = [0x00; 8];
+        self.bytes(&mut bytes)?;
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    pub fn usize(&mut self) -> Result<usize, SaveStateError> {
+        Ok(self.u64()? as usize)
+    }
+
+    pub fn bool(&mut self) -> Result<bool, SaveStateError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(SaveStateError::InvalidValue("bool")),
+        }
+    }
+
+    pub fn bytes(&mut self, bytes: &mut [u8]) -> Result<(), SaveStateError> {
+        bytes.copy_from_slice(self.take(bytes.len())?);
+        Ok(())
+    }
+
+    pub fn vec(&mut self) -> Result<Vec<u8>, SaveStateError> {
+        let len: usize = self.u32()? as usize;
+        Ok(self.take(len)?.to_vec())
+    }
+
+    /// Reads length-prefixed data into `bytes`, which must be the same length
+    pub fn vec_into(&mut self, bytes: &mut [u8], what: &'static str) -> Result<(), SaveStateError> {
+        let len: usize = self.u32()? as usize;
+        if len != bytes.len() {
+            return Err(SaveStateError::InvalidValue(what));
+        }
+        bytes.copy_from_slice(self.take(len)?);
+        Ok(())
+    }
+}
+
+pub fn write_chunk<T: SaveState + ?Sized>(state: &mut StateWriter, tag: [u8; 4], component: &T) {
+    state.chunk(tag, |chunk: &mut StateWriter| component.write_state(chunk));
+}
+
+pub fn read_chunk<T: SaveState + ?Sized>(
+    chunks: &mut HashMap<[u8; 4], StateReader>,
+    tag: [u8; 4],
+    component: &mut T,
+) -> Result<(), SaveStateError> {
+    let mut chunk: StateReader = chunks
+        .remove(&tag)
+        .ok_or(SaveStateError::MissingChunk(tag))?;
+    component.read_state(&mut chunk)?;
+    chunk.finish(tag)
+}
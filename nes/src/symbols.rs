@@ -0,0 +1,376 @@
+//! Names for addresses, shown by the disassembler and `tools::trace`
+//!
+//! Symbols are kept per bank, a bank being one of the PRG-ROM banks the mapper switches
+//! ([`Mapper::prg_bank_size`](crate::mapper::Mapper::prg_bank_size)), counted from the start of
+//! the ROM. A 32 KiB ROM without bank switching is all bank 0. Addresses below `$8000` aren't
+//! banked, so their symbols are shared by every bank.
+//!
+//! Reads the TOML format from `docs/future_dev/symbols_example.toml`, ca65/ld65 debug files
+//! (`.dbg`) and FCEUX name lists (`.nl`).
+
+use crate::{cartridge::PRG_ROM_PAGE_SIZE, memory::mem_map::PRG_ROM, prelude::*};
+use std::{
+    collections::HashMap,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+/// FCEUX numbers the banks of its name lists in 16 KiB pages, whatever the mapper
+const NL_BANK_SIZE: usize = PRG_ROM_PAGE_SIZE;
+/// ld65's output file offsets count the iNES header
+const INES_HEADER_SIZE: usize = 16;
+
+type Key = (Option<usize>, u16);
+
+fn key(bank: Option<usize>, addr: u16) -> Key {
+    (bank.filter(|_| addr >= PRG_ROM), addr)
+}
+
+/// Hex with an optional `$` or `0x`, like `D925`
+fn parse_hex(text: &str) -> Option<u16> {
+    let text: &str = text.trim();
+    let digits: &str = text
+        .strip_prefix('$')
+        .or_else(|| text.strip_prefix("0x"))
+        .unwrap_or(text);
+    u16::from_str_radix(digits, 16).ok()
+}
+
+#[derive(Debug)]
+pub enum SymbolError {
+    Io(PathBuf, std::io::Error),
+    Toml(toml::de::Error),
+    /// The key, e.g. `bank.0.label.D925`
+    InvalidValue(String),
+    InvalidLine(usize),
+    UnknownFormat(PathBuf),
+}
+
+impl fmt::Display for SymbolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SymbolError::Io(path, err) => write!(f, "Couldn't read {:?}: {}", path, err),
+            SymbolError::Toml(err) => write!(f, "Invalid TOML: {}", err),
+            SymbolError::InvalidValue(key) => write!(f, "Invalid value for '{}'", key),
+            SymbolError::InvalidLine(line) => write!(f, "Invalid symbol on line {}", line),
+            SymbolError::UnknownFormat(path) => write!(f, "Unknown symbol file {:?}", path),
+        }
+    }
+}
+
+impl std::error::Error for SymbolError {}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Symbols {
+    labels: HashMap<Key, String>,
+    /// Replaces the operand of the instruction at the address, e.g. `LOW(TEST_BFlag_BRK)`
+    consts: HashMap<Key, String>,
+    comments: HashMap<Key, String>,
+}
+
+impl Symbols {
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.labels.len() + self.consts.len() + self.comments.len()
+    }
+
+    pub fn label(&self, bank: Option<usize>, addr: u16) -> Option<&str> {
+        self.labels.get(&key(bank, addr)).map(String::as_str)
+    }
+
+    pub fn constant(&self, bank: Option<usize>, addr: u16) -> Option<&str> {
+        self.consts.get(&key(bank, addr)).map(String::as_str)
+    }
+
+    pub fn comment(&self, bank: Option<usize>, addr: u16) -> Option<&str> {
+        self.comments.get(&key(bank, addr)).map(String::as_str)
+    }
+
+    pub fn add_label(&mut self, bank: Option<usize>, addr: u16, name: &str) {
+        self.labels.insert(key(bank, addr), name.to_string());
+    }
+
+    pub fn add_constant(&mut self, bank: Option<usize>, addr: u16, text: &str) {
+        self.consts.insert(key(bank, addr), text.to_string());
+    }
+
+    pub fn add_comment(&mut self, bank: Option<usize>, addr: u16, text: &str) {
+        self.comments.insert(key(bank, addr), text.to_string());
+    }
+
+    /// Symbols from `other` win where both name the same address
+    pub fn merge(&mut self, other: Symbols) {
+        self.labels.extend(other.labels);
+        self.consts.extend(other.consts);
+        self.comments.extend(other.comments);
+    }
+
+    /// Loads a `.toml`, `.dbg` or `.nl` file, `bank_size` being the mapper's PRG-ROM bank size
+    pub fn load(path: &Path, bank_size: usize) -> Result<Self, SymbolError> {
+        let read = |path: &Path| -> Result<String, SymbolError> {
+            std::fs::read_to_string(path)
+                .map_err(|err: std::io::Error| SymbolError::Io(path.to_path_buf(), err))
+        };
+
+        match path
+            .extension()
+            .and_then(|ext: &std::ffi::OsStr| ext.to_str())
+        {
+            Some("toml") => {
+                let text: String = read(path)?;
+                let mut symbols: Symbols = Symbols::from_toml(&text)?;
+                // `symbols.toml` lists the banks kept in `symbols.0.toml`, `symbols.1.toml`...
+                for bank in toml_bank_files(&text)? {
+                    let bank_path: PathBuf = path.with_extension(format!("{}.toml", bank));
+                    symbols.merge(Symbols::from_toml(&read(&bank_path)?)?);
+                }
+                Ok(symbols)
+            }
+            Some("dbg") => Symbols::from_dbg(&read(path)?, bank_size),
+            Some("nl") => {
+                // `game.nes.ram.nl` or `game.nes.1.nl`, with the bank in hex
+                let bank: Option<usize> = path
+                    .file_stem()
+                    .and_then(|stem: &std::ffi::OsStr| Path::new(stem).extension())
+                    .and_then(|bank: &std::ffi::OsStr| bank.to_str())
+                    .and_then(|bank: &str| usize::from_str_radix(bank, 16).ok());
+                Symbols::from_nl(&read(path)?, bank, bank_size)
+            }
+            _ => Err(SymbolError::UnknownFormat(path.to_path_buf())),
+        }
+    }
+
+    /// Loads every symbol file next to a ROM: `game.toml`, `game.dbg` and FCEUX's
+    /// `game.nes.ram.nl`/`game.nes.0.nl`...
+    pub fn load_for_rom(rom_path: &Path, bank_size: usize) -> Result<Self, SymbolError> {
+        let mut paths: Vec<PathBuf> = vec![
+            rom_path.with_extension("toml"),
+            rom_path.with_extension("dbg"),
+        ];
+        if let Some(rom_name) = rom_path
+            .file_name()
+            .and_then(|name: &std::ffi::OsStr| name.to_str())
+            && let Some(dir) = rom_path.parent()
+            && let Ok(entries) = std::fs::read_dir(if dir.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                dir
+            })
+        {
+            let prefix: String = format!("{}.", rom_name);
+            let mut name_lists: Vec<PathBuf> = entries
+                .filter_map(|entry: std::io::Result<std::fs::DirEntry>| entry.ok())
+                .map(|entry: std::fs::DirEntry| entry.path())
+                .filter(|path: &PathBuf| {
+                    path.file_name()
+                        .and_then(|name: &std::ffi::OsStr| name.to_str())
+                        .is_some_and(|name: &str| {
+                            name.starts_with(&prefix) && name.ends_with(".nl")
+                        })
+                })
+                .collect();
+            name_lists.sort();
+            paths.extend(name_lists);
+        }
+
+        let mut symbols: Symbols = Symbols::default();
+        for path in paths.iter().filter(|path: &&PathBuf| path.is_file()) {
+            let loaded: Symbols = Symbols::load(path, bank_size)?;
+            info!("Loaded {} symbols from {:?}", loaded.len(), path);
+            symbols.merge(loaded);
+        }
+        Ok(symbols)
+    }
+
+    /// One file of the format in `docs/future_dev/symbols_example.toml`
+    pub fn from_toml(text: &str) -> Result<Self, SymbolError> {
+        let table: toml::Table = text.parse().map_err(SymbolError::Toml)?;
+        let mut symbols: Symbols = Symbols::default();
+        let Some(banks) = table.get("bank") else {
+            return Ok(symbols);
+        };
+
+        let invalid = |key: String| -> SymbolError { SymbolError::InvalidValue(key) };
+        let banks: &toml::Table = banks
+            .as_table()
+            .ok_or_else(|| invalid("bank".to_string()))?;
+        for (bank, kinds) in banks {
+            let bank_num: usize = bank
+                .parse()
+                .map_err(|_| invalid(format!("bank.{}", bank)))?;
+            let kinds: &toml::Table = kinds
+                .as_table()
+                .ok_or_else(|| invalid(format!("bank.{}", bank)))?;
+
+            for (kind, entries) in kinds {
+                let add: fn(&mut Symbols, Option<usize>, u16, &str) = match kind.as_str() {
+                    "label" => Symbols::add_label,
+                    "const" => Symbols::add_constant,
+                    "comments" => Symbols::add_comment,
+                    _ => {
+                        warn!("Ignoring unknown symbol table 'bank.{}.{}'", bank, kind);
+                        continue;
+                    }
+                };
+                let entries: &toml::Table = entries
+                    .as_table()
+                    .ok_or_else(|| invalid(format!("bank.{}.{}", bank, kind)))?;
+                for (addr, text) in entries {
+                    let entry_key = || -> String { format!("bank.{}.{}.{}", bank, kind, addr) };
+                    let addr: u16 = parse_hex(addr).ok_or_else(|| invalid(entry_key()))?;
+                    let text: &str = text.as_str().ok_or_else(|| invalid(entry_key()))?;
+                    add(&mut symbols, Some(bank_num), addr, text);
+                }
+            }
+        }
+        Ok(symbols)
+    }
+
+    /// An FCEUX name list, `bank` is its 16 KiB page or `None` for the `.ram.nl` file.
+    /// Each line is `$C000#Label#Comment`, arrays like `$0300/20#Buffer#` are named at their start.
+    // https://fceux.com/web/help/Debugger.html
+    pub fn from_nl(text: &str, bank: Option<usize>, bank_size: usize) -> Result<Self, SymbolError> {
+        let mut symbols: Symbols = Symbols::default();
+        for (index, line) in text.lines().enumerate() {
+            let line: &str = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let mut fields: std::str::SplitN<char> = line.splitn(3, '#');
+            let addr: u16 = fields
+                .next()
+                .and_then(|addr: &str| addr.trim().strip_prefix('$'))
+                .and_then(|addr: &str| parse_hex(addr.split('/').next().unwrap()))
+                .ok_or(SymbolError::InvalidLine(index + 1))?;
+            let name: &str = fields.next().unwrap_or("").trim();
+            let comment: &str = fields.next().unwrap_or("").trim();
+            let bank: Option<usize> = bank.map(|page: usize| {
+                (page * NL_BANK_SIZE + addr as usize % NL_BANK_SIZE) / bank_size
+            });
+            if !name.is_empty() {
+                symbols.add_label(bank, addr, name);
+            }
+            if !comment.is_empty() {
+                symbols.add_comment(bank, addr, comment);
+            }
+        }
+        Ok(symbols)
+    }
+
+    /// A ca65/ld65 debug file from `ld65 --dbgfile`. Only labels are imported, equates are often
+    /// plain numbers rather than addresses. The banks of labels in ROM come from the offsets of
+    /// their segments in the linked `.nes` file.
+    // https://cc65.github.io/doc/debugging.html
+    pub fn from_dbg(text: &str, bank_size: usize) -> Result<Self, SymbolError> {
+        // Segment id to its start address and PRG-ROM offset
+        let mut segments: HashMap<usize, (usize, Option<usize>)> = HashMap::new();
+        let mut labels: Vec<(String, usize, Option<usize>)> = Vec::new();
+
+        for (index, line) in text.lines().enumerate() {
+            let Some((kind, fields)) = line.split_once(char::is_whitespace) else {
+                continue;
+            };
+            if kind != "seg" && kind != "sym" {
+                continue;
+            }
+
+            let fields: HashMap<&str, &str> = dbg_fields(fields);
+            let number = |name: &str| -> Result<Option<usize>, SymbolError> {
+                match fields.get(name) {
+                    Some(value) => dbg_number(value)
+                        .map(Some)
+                        .ok_or(SymbolError::InvalidLine(index + 1)),
+                    None => Ok(None),
+                }
+            };
+            let id: usize = number("id")?.ok_or(SymbolError::InvalidLine(index + 1))?;
+
+            match kind {
+                "seg" => {
+                    let start: usize =
+                        number("start")?.ok_or(SymbolError::InvalidLine(index + 1))?;
+                    let prg_offset: Option<usize> = number("ooffs")?
+                        .and_then(|offset: usize| offset.checked_sub(INES_HEADER_SIZE));
+                    segments.insert(id, (start, prg_offset));
+                }
+                _ if fields.get("type") == Some(&"lab") => {
+                    let (Some(name), Some(value)) = (fields.get("name"), number("val")?) else {
+                        return Err(SymbolError::InvalidLine(index + 1));
+                    };
+                    labels.push((name.to_string(), value, number("seg")?));
+                }
+                _ => {}
+            }
+        }
+
+        let mut symbols: Symbols = Symbols::default();
+        for (name, value, segment) in labels {
+            let Ok(addr) = u16::try_from(value) else {
+                continue;
+            };
+            let bank: Option<usize> = segment.and_then(|id: usize| segments.get(&id)).and_then(
+                |(start, prg_offset): &(usize, Option<usize>)| {
+                    Some((prg_offset.as_ref()? + value.checked_sub(*start)?) / bank_size)
+                },
+            );
+            symbols.add_label(bank, addr, &name);
+        }
+        Ok(symbols)
+    }
+}
+
+/// The banks a `multifile` TOML keeps in separate files
+fn toml_bank_files(text: &str) -> Result<Vec<usize>, SymbolError> {
+    let table: toml::Table = text.parse().map_err(SymbolError::Toml)?;
+    if !table
+        .get("multifile")
+        .and_then(toml::Value::as_bool)
+        .unwrap_or(false)
+    {
+        return Ok(Vec::new());
+    }
+
+    let invalid = || -> SymbolError { SymbolError::InvalidValue("banks_covered".to_string()) };
+    table
+        .get("banks_covered")
+        .and_then(toml::Value::as_array)
+        .ok_or_else(invalid)?
+        .iter()
+        .map(|bank: &toml::Value| {
+            bank.as_integer()
+                .and_then(|bank: i64| usize::try_from(bank).ok())
+                .ok_or_else(invalid)
+        })
+        .collect()
+}
+
+/// `id=0,name="CODE",start=0x008000`, commas inside quotes are part of the value
+fn dbg_fields(text: &str) -> HashMap<&str, &str> {
+    let mut fields: HashMap<&str, &str> = HashMap::new();
+    let mut in_quotes: bool = false;
+    let mut start: usize = 0;
+    for (index, char) in text.char_indices().chain([(text.len(), ',')]) {
+        match char {
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => {
+                if let Some((name, value)) = text[start..index].split_once('=') {
+                    fields.insert(name.trim(), value.trim().trim_matches('"'));
+                }
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    fields
+}
+
+fn dbg_number(text: &str) -> Option<usize> {
+    match text.strip_prefix("0x") {
+        Some(digits) => usize::from_str_radix(digits, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
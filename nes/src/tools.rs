@@ -1,23 +1,15 @@
 use crate::{
     BoxMapper, BoxNESDevice,
     apu::APU,
+    breakpoint::Breakpoints,
     bus::Bus,
     cartridge::ROM,
-    cpu::CPU,
+    cpu::{CPU, opcode::OpCode},
     memory::Memory,
     ppu::{PPU, renderer::Renderer},
-    prelude::*,
+    symbols::Symbols,
 };
-use std::{
-    cell::{Ref, RefMut},
-    sync::LazyLock,
-};
-
-pub static NON_READABLE_ADDR: LazyLock<Vec<u16>> = LazyLock::new(|| {
-    vec![
-        0x2000, 0x2001, 0x2002, 0x2003, 0x2004, 0x2005, 0x2006, 0x2007, 0x4016, 0x4017,
-    ]
-});
+use std::cell::{Ref, RefMut};
 
 #[rustfmt::skip]
 pub trait NESAccess {
@@ -39,6 +31,8 @@ pub trait NESAccess {
     fn device2_mut(&self) -> RefMut<'_, BoxNESDevice> { panic!("Mutable access to `Device 2` is prohibited") }
     fn renderer(&self) -> Ref<'_, Renderer> { panic!("Access to `Renderer` is prohibited") }
     fn renderer_mut(&self) -> RefMut<'_, Renderer> { panic!("Mutable access to `Renderer` is prohibited") }
+    fn breakpoints(&self) -> Ref<'_, Breakpoints> { panic!("Access to `Breakpoints` is prohibited") }
+    fn breakpoints_mut(&self) -> RefMut<'_, Breakpoints> { panic!("Mutable access to `Breakpoints` is prohibited") }
 }
 
 pub fn nth_bit<T: Into<u16>, U: Into<u16>>(x: T, n: U) -> u8 {
@@ -94,71 +88,103 @@ pub fn format_byte_size(bytes: usize) -> String {
     }
 }
 
-pub fn trace(cpu: &CPU) -> String {
-    use crate::cpu::opcode::{AddressingMode::*, Instruction::*, OpCode, decode_opcode};
+/// An instruction decoded from memory without running it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Disassembly {
+    pub addr: u16,
+    pub bytes: Vec<u8>,
+    pub opcode: &'static OpCode,
+    /// The operand as it's written in assembly, e.g. `$0200,X`, `#$10` or `TEST_BFlag`
+    pub operand: String,
+    /// The symbol naming `addr`, if any
+    pub label: Option<String>,
+    pub comment: Option<String>,
+}
 
-    let prev_bus_quiet_log: bool = crate::bus::get_quiet_log();
-    crate::bus::set_quiet_log(true);
+impl Disassembly {
+    pub fn hex(&self) -> String {
+        self.bytes
+            .iter()
+            .map(|byte: &u8| format!("{:02X}", byte))
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
 
-    let opbyte: u8 = cpu.bus_mut().read(cpu.program_counter);
-    let opcode: &'static OpCode = decode_opcode(opbyte);
+    /// Unofficial opcodes are marked with a `*`, like in nestest's log
+    pub fn undoc_marker(&self) -> &'static str {
+        use crate::cpu::opcode::Instruction::*;
 
-    let begin: u16 = cpu.program_counter;
-    let mut hex_dump: Vec<u8> = vec![];
-    hex_dump.push(opbyte);
+        match self.opcode.instruction {
+            NOP_ALT | SLO | RLA | SRE | RRA | SAX | LAX | DCP | ISC | ANC | ALR | ARR | XAA
+            | AXS | SBC_NOP | AHX | SHY | SHX | TAS | LAS | KIL => "*",
+            _ => " ",
+        }
+    }
 
-    let (mem_addr, stored_value): (u16, u8) = match opcode.mode {
-        Implicit | Accumulator | Immediate => (0, 0),
-        _ => {
-            let (addr, _): (u16, bool) = opcode.get_absolute_address(cpu, begin + 1);
+    /// `LDA $0200,X`
+    pub fn asm(&self) -> String {
+        format!("{} {}", self.opcode.mnemonic, self.operand)
+            .trim_end()
+            .to_string()
+    }
 
-            if !NON_READABLE_ADDR.contains(&addr) {
-                (addr, cpu.bus_mut().read(addr))
-            } else {
-                (addr, 0)
-            }
-        }
+    pub fn next_addr(&self) -> u16 {
+        self.addr.wrapping_add(self.bytes.len() as u16)
+    }
+}
+
+impl std::fmt::Display for Disassembly {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let line: String = format!(
+            "{:04X}  {:8} {}{}",
+            self.addr,
+            self.hex(),
+            self.undoc_marker(),
+            self.asm()
+        );
+        write!(f, "{}", line.trim_end())
+    }
+}
+
+pub fn disassemble(cpu: &CPU, addr: u16) -> Disassembly {
+    use crate::cpu::opcode::{AddressingMode::*, decode_opcode};
+
+    let bus: Ref<Bus> = cpu.bus();
+    let opcode: &'static OpCode = decode_opcode(bus.peek(addr));
+    let bytes: Vec<u8> = (0..opcode.len as u16)
+        .map(|offset: u16| bus.peek(addr.wrapping_add(offset)))
+        .collect();
+    let symbols: &Symbols = &bus.symbols;
+    let bank = |addr: u16| -> Option<usize> {
+        let mapper: Ref<BoxMapper> = bus.mapper();
+        mapper
+            .prg_rom_offset(addr)
+            .map(|offset: usize| offset / mapper.prg_bank_size())
     };
+    let label =
+        |target: u16| -> Option<String> { symbols.label(bank(target), target).map(str::to_string) };
+    // Replaces the operand's value, e.g. `LDA #LOW(TEST_BFlag_BRK)`
+    let constant: Option<String> = symbols.constant(bank(addr), addr).map(str::to_string);
 
-    let tmp: String = match opcode.len {
-        1 => match opcode.byte {
-            0x0A | 0x2A | 0x4A | 0x6A => String::from("A "),
-            _ => String::from(""),
-        },
+    let operand: String = match opcode.len {
         2 => {
-            let address: u8 = cpu.bus_mut().read(begin + 1);
-            hex_dump.push(address);
-
+            let value: u8 = bytes[1];
+            let zero_page: String = constant
+                .clone()
+                .or_else(|| label(value as u16))
+                .unwrap_or_else(|| format!("${:02X}", value));
             match opcode.mode {
-                Immediate => format!("#${:02X}", address),
-                ZeroPage => format!("${:02X} = {:02X}", mem_addr, stored_value),
-                ZeroPage_X => format!(
-                    "${:02X},X @ {:02X} = {:02X}",
-                    address, mem_addr, stored_value
-                ),
-                ZeroPage_Y => format!(
-                    "${:02X},Y @ {:02X} = {:02X}",
-                    address, mem_addr, stored_value
-                ),
-                Indirect_X => format!(
-                    "(${:02X},X) @ {:02X} = {:04X} = {:02X}",
-                    address,
-                    address.wrapping_add(cpu.index_x),
-                    mem_addr,
-                    stored_value
-                ),
-                Indirect_Y => format!(
-                    "(${:02X}),Y = {:04X} @ {:04X} = {:02X}",
-                    address,
-                    mem_addr.wrapping_sub(cpu.index_y as u16),
-                    mem_addr,
-                    stored_value
-                ),
+                Immediate => format!("#{}", constant.unwrap_or_else(|| format!("${:02X}", value))),
+                ZeroPage => zero_page,
+                ZeroPage_X => format!("{},X", zero_page),
+                ZeroPage_Y => format!("{},Y", zero_page),
+                Indirect_X => format!("({},X)", zero_page),
+                Indirect_Y => format!("({}),Y", zero_page),
                 Relative => {
-                    // assuming local jumps: BNE, BVS, etc....
-                    let address: usize =
-                        (begin as usize + 2).wrapping_add((address as i8) as usize);
-                    format!("${:04X}", address)
+                    let target: u16 = addr.wrapping_add(2).wrapping_add(value as i8 as u16);
+                    constant
+                        .or_else(|| label(target))
+                        .unwrap_or_else(|| format!("${:04X}", target))
                 }
                 _ => panic!(
                     "Unexpected addressing mode {:?} has operand-len of 2. code {:02X}",
@@ -167,87 +193,106 @@ pub fn trace(cpu: &CPU) -> String {
             }
         }
         3 => {
-            let address_lo: u8 = cpu.bus_mut().read(begin + 1);
-            let address_hi: u8 = cpu.bus_mut().read(begin + 2);
-            hex_dump.push(address_lo);
-            hex_dump.push(address_hi);
-
-            let address: u16 = cpu.bus_mut().read_u16(begin + 1);
-
+            let value: u16 = bytes_to_u16(&[bytes[1], bytes[2]]);
+            let absolute: String = constant
+                .or_else(|| label(value))
+                .unwrap_or_else(|| format!("${:04X}", value));
             match opcode.mode {
-                Indirect => {
-                    if opcode.byte == 0x6C {
-                        // jmp indirect
-                        let jmp_addr: u16 = if address & 0x00FF == 0x00FF {
-                            let mut temp_bus: RefMut<Bus> = cpu.bus_mut();
-                            tools::bytes_to_u16(&[
-                                temp_bus.read(address),
-                                temp_bus.read(address & 0xFF00),
-                            ])
-                        } else {
-                            cpu.bus_mut().read_u16(address)
-                        };
-                        format!("(${:04X}) = {:04X}", address, jmp_addr)
-                    } else {
-                        format!("${:04X}", address)
-                    }
-                }
-                Absolute => match opcode.byte {
-                    0x4C | 0x20 => {
-                        format!("${:04X}", mem_addr)
-                    }
-                    _ => {
-                        format!("${:04X} = {:02X}", mem_addr, stored_value)
-                    }
-                },
-                Absolute_X => format!(
-                    "${:04X},X @ {:04X} = {:02X}",
-                    address, mem_addr, stored_value
-                ),
-                Absolute_Y => format!(
-                    "${:04X},Y @ {:04X} = {:02X}",
-                    address, mem_addr, stored_value
-                ),
+                Absolute => absolute,
+                Absolute_X => format!("{},X", absolute),
+                Absolute_Y => format!("{},Y", absolute),
+                Indirect => format!("({})", absolute),
                 _ => panic!(
                     "Unexpected addressing mode {:?} has operand-len of 3. code {:02X}",
                     opcode.mode, opcode.byte
                 ),
             }
         }
-        _ => String::from(""),
+        _ => match opcode.mode {
+            Accumulator => String::from("A"),
+            _ => String::new(),
+        },
     };
 
-    let undoc_marker: &'static str = match opcode.instruction {
-        NOP_ALT | SLO | RLA | SRE | RRA | SAX | LAX | DCP | ISC | ANC | ALR | ARR | XAA | AXS
-        | SBC_NOP | AHX | SHY | SHX | TAS | LAS | KIL => "*",
-        _ => " ",
+    Disassembly {
+        addr,
+        bytes,
+        opcode,
+        operand,
+        label: label(addr),
+        comment: symbols.comment(bank(addr), addr).map(str::to_string),
+    }
+}
+
+pub fn trace(cpu: &CPU) -> String {
+    use crate::cpu::opcode::AddressingMode::*;
+
+    let mut disassembly: Disassembly = disassemble(cpu, cpu.program_counter);
+    let opcode: &'static OpCode = disassembly.opcode;
+    let bus: Ref<Bus> = cpu.bus();
+    let operand: u16 = match opcode.len {
+        2 => disassembly.bytes[1] as u16,
+        3 => bytes_to_u16(&[disassembly.bytes[1], disassembly.bytes[2]]),
+        _ => 0,
     };
-    let hex_str: String = hex_dump
-        .iter()
-        .map(|z| format!("{:02X}", z))
-        .collect::<Vec<String>>()
-        .join(" ");
-    let asm_str: String = format!(
-        "{:04X}  {:8} {}{} {}",
-        begin, hex_str, undoc_marker, opcode.mnemonic, tmp
-    )
-    .trim()
-    .to_string();
+    let zero_page_ptr = |ptr: u8| -> u16 {
+        bytes_to_u16(&[bus.peek(ptr as u16), bus.peek(ptr.wrapping_add(1) as u16)])
+    };
+
+    // Worked out from peeks, as the CPU's own addressing would read the bus
+    let mem_addr: u16 = match opcode.mode {
+        ZeroPage => operand,
+        ZeroPage_X => (operand as u8).wrapping_add(cpu.index_x) as u16,
+        ZeroPage_Y => (operand as u8).wrapping_add(cpu.index_y) as u16,
+        Absolute => operand,
+        Absolute_X => operand.wrapping_add(cpu.index_x as u16),
+        Absolute_Y => operand.wrapping_add(cpu.index_y as u16),
+        Indirect_X => zero_page_ptr((operand as u8).wrapping_add(cpu.index_x)),
+        Indirect_Y => zero_page_ptr(operand as u8).wrapping_add(cpu.index_y as u16),
+        _ => 0,
+    };
+    let stored_value: u8 = bus.peek(mem_addr);
+
+    // The values the operand points at right now
+    let annotation: String = match opcode.mode {
+        ZeroPage => format!(" = {:02X}", stored_value),
+        ZeroPage_X | ZeroPage_Y => format!(" @ {:02X} = {:02X}", mem_addr, stored_value),
+        Indirect_X => format!(
+            " @ {:02X} = {:04X} = {:02X}",
+            disassembly.bytes[1].wrapping_add(cpu.index_x),
+            mem_addr,
+            stored_value
+        ),
+        Indirect_Y => format!(
+            " = {:04X} @ {:04X} = {:02X}",
+            mem_addr.wrapping_sub(cpu.index_y as u16),
+            mem_addr,
+            stored_value
+        ),
+        Indirect if opcode.byte == 0x6C => {
+            // jmp indirect, which doesn't carry into the high byte of the pointer
+            let hi_addr: u16 = (operand & 0xFF00) | (operand as u8).wrapping_add(1) as u16;
+            let jmp_addr: u16 = bytes_to_u16(&[bus.peek(operand), bus.peek(hi_addr)]);
+            format!(" = {:04X}", jmp_addr)
+        }
+        Absolute if !matches!(opcode.byte, 0x4C | 0x20) => format!(" = {:02X}", stored_value),
+        Absolute_X | Absolute_Y => format!(" @ {:04X} = {:02X}", mem_addr, stored_value),
+        _ => String::new(),
+    };
+    disassembly.operand += &annotation;
 
-    crate::bus::set_quiet_log(prev_bus_quiet_log);
     format!(
         "{:47} A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X} PPU:{:>3},{:>3} CYC:{}",
-        asm_str,
+        disassembly.to_string(),
         cpu.accumulator,
         cpu.index_x,
         cpu.index_y,
         cpu.status,
         cpu.stack_pointer,
-        cpu.bus().ppu().scanline,
-        cpu.bus().ppu().dot,
-        cpu.bus().cpu_cycles
+        bus.ppu().scanline,
+        bus.ppu().dot,
+        bus.cpu_cycles
     )
-    .to_ascii_uppercase()
 }
 
 pub fn format_mem(memory: &[u8], start_addr: u16, end_addr: u16) -> String {
@@ -0,0 +1,357 @@
+use super::*;
+use crate::{
+    apu::{
+        APU, dmc,
+        filters::FilterChain,
+        frame_counter::SequenceMode,
+        mixer::{Channel, Mixer},
+        pulse::PulseChannel,
+        resampler::Resampler,
+    },
+    cpu::interrupt,
+    memory::mem_map::*,
+};
+
+#[test]
+fn test_length_counter_load_and_status() {
+    let mut apu: APU = APU::new();
+
+    // Length counter isn't loaded while the channel is disabled
+    apu.write_register(MMIO_SQ1_HI, 0b0000_1000);
+    assert_eq!(apu.read_status(), 0b0000_0000);
+
+    apu.write_register(MMIO_SND_CHN, 0b0000_0011);
+    apu.write_register(MMIO_SQ1_HI, 0b0000_1000);
+    assert_eq!(apu.pulse1.length_counter.counter, 254);
+    assert_eq!(apu.read_status(), 0b0000_0001);
+
+    apu.write_register(MMIO_SND_CHN, 0b0000_0010);
+    assert_eq!(apu.pulse1.length_counter.counter, 0);
+    assert_eq!(apu.read_status(), 0b0000_0000);
+}
+
+#[test]
+fn test_sweep_negate_difference() {
+    let mut apu: APU = APU::new();
+    assert_eq!(apu.pulse1.channel, PulseChannel::One);
+    assert_eq!(apu.pulse2.channel, PulseChannel::Two);
+
+    // Enabled, negate, shift 1
+    apu.write_register(MMIO_SQ1_SWEEP, 0b1000_1001);
+    apu.write_register(MMIO_SQ2_SWEEP, 0b1000_1001);
+    apu.write_register(MMIO_SQ1_LO, 0x00);
+    apu.write_register(MMIO_SQ1_HI, 0x01);
+    apu.write_register(MMIO_SQ2_LO, 0x00);
+    apu.write_register(MMIO_SQ2_HI, 0x01);
+
+    // Ones' complement on pulse 1, two's complement on pulse 2
+    assert_eq!(apu.pulse1.sweep_target_period(), 0x0100 - 0x0080 - 1);
+    assert_eq!(apu.pulse2.sweep_target_period(), 0x0100 - 0x0080);
+}
+
+#[test]
+fn test_sweep_muting() {
+    let mut apu: APU = APU::new();
+
+    // Period below 8 always mutes
+    apu.write_register(MMIO_SQ1_LO, 0x07);
+    apu.write_register(MMIO_SQ1_HI, 0x00);
+    assert!(apu.pulse1.sweep_muting());
+
+    // Target period overflowing $7FF mutes, even with the sweep unit disabled
+    apu.write_register(MMIO_SQ1_SWEEP, 0b0000_0001);
+    apu.write_register(MMIO_SQ1_LO, 0x00);
+    apu.write_register(MMIO_SQ1_HI, 0x06);
+    assert!(apu.pulse1.sweep_muting());
+
+    apu.write_register(MMIO_SQ1_SWEEP, 0b0000_0010);
+    assert!(!apu.pulse1.sweep_muting());
+}
+
+#[test]
+fn test_pulse_output_square_wave() {
+    let mut apu: APU = APU::new();
+
+    apu.write_register(MMIO_SND_CHN, 0b0000_0001);
+    // 50% duty, halt, constant volume 15
+    apu.write_register(MMIO_SQ1_VOL, 0b1011_1111);
+    apu.write_register(MMIO_SQ1_LO, 0x08);
+    apu.write_register(MMIO_SQ1_HI, 0x00);
+
+    let mut samples: Vec<u8> = Vec::new();
+    for _ in 0..(9 * 2 * 16) {
+        apu.tick();
+        samples.push(apu.pulse1.output());
+    }
+
+    assert!(samples.contains(&15));
+    assert!(samples.contains(&0));
+    // One sequencer step lasts 18 CPU cycles
+    let high: isize = samples.iter().filter(|sample| **sample == 15).count() as isize;
+    assert!((high - samples.len() as isize / 2).abs() <= 18);
+}
+
+#[test]
+fn test_envelope_decay() {
+    let mut apu: APU = APU::new();
+
+    apu.write_register(MMIO_SND_CHN, 0b0000_0001);
+    // Envelope period 0, not looping
+    apu.write_register(MMIO_SQ1_VOL, 0b1000_0000);
+    apu.write_register(MMIO_SQ1_LO, 0x08);
+    apu.write_register(MMIO_SQ1_HI, 0x08);
+
+    apu.pulse1.clock_quarter_frame();
+    assert_eq!(apu.pulse1.envelope.output(), 15);
+    for _ in 0..15 {
+        apu.pulse1.clock_quarter_frame();
+    }
+    assert_eq!(apu.pulse1.envelope.output(), 0);
+    apu.pulse1.clock_quarter_frame();
+    assert_eq!(apu.pulse1.envelope.output(), 0);
+}
+
+#[test]
+fn test_triangle_linear_counter() {
+    let mut apu: APU = APU::new();
+
+    apu.write_register(MMIO_SND_CHN, 0b0000_0100);
+    // Control clear, reload value 4
+    apu.write_register(MMIO_TRI_LINEAR, 0b0000_0100);
+    apu.write_register(MMIO_TRI_LO, 0x10);
+    apu.write_register(MMIO_TRI_HI, 0x08);
+    assert!(apu.triangle.linear_reload);
+
+    apu.triangle.clock_quarter_frame();
+    assert_eq!(apu.triangle.linear_counter, 4);
+    assert!(!apu.triangle.linear_reload);
+    for _ in 0..4 {
+        apu.triangle.clock_quarter_frame();
+    }
+    assert_eq!(apu.triangle.linear_counter, 0);
+
+    // The sequencer is frozen while the linear counter is 0
+    let sequence_pos: u8 = apu.triangle.sequence_pos;
+    for _ in 0..0x100 {
+        apu.triangle.clock_timer();
+    }
+    assert_eq!(apu.triangle.sequence_pos, sequence_pos);
+}
+
+#[test]
+fn test_triangle_sequence() {
+    let mut apu: APU = APU::new();
+
+    apu.write_register(MMIO_SND_CHN, 0b0000_0100);
+    apu.write_register(MMIO_TRI_LINEAR, 0b1111_1111);
+    apu.write_register(MMIO_TRI_LO, 0x00);
+    apu.write_register(MMIO_TRI_HI, 0x08);
+    apu.triangle.clock_quarter_frame();
+
+    let mut samples: Vec<u8> = Vec::new();
+    for _ in 0..32 {
+        apu.triangle.clock_timer();
+        samples.push(apu.triangle.output());
+    }
+    assert_eq!(
+        samples[0..16],
+        [14, 13, 12, 11, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0, 0]
+    );
+    assert_eq!(
+        samples[16..32],
+        [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 15]
+    );
+}
+
+#[test]
+fn test_noise_lfsr_modes() {
+    let mut apu: APU = APU::new();
+
+    // Long mode repeats every 32767 clocks
+    apu.write_register(MMIO_NOISE_LO, 0b0000_0000);
+    let initial: u16 = apu.noise.shift_register;
+    let mut period: usize = 0;
+    loop {
+        apu.noise.clock_shift_register();
+        period += 1;
+        if apu.noise.shift_register == initial {
+            break;
+        }
+    }
+    assert_eq!(period, 32767);
+
+    // Short mode repeats every 93 clocks (when started from a state inside that loop)
+    apu.write_register(MMIO_NOISE_LO, 0b1000_0000);
+    for _ in 0..100 {
+        apu.noise.clock_shift_register();
+    }
+    let initial: u16 = apu.noise.shift_register;
+    let mut period: usize = 0;
+    loop {
+        apu.noise.clock_shift_register();
+        period += 1;
+        if apu.noise.shift_register == initial {
+            break;
+        }
+    }
+    assert_eq!(period, 93);
+}
+
+#[test]
+fn test_frame_counter_4_step_irq() {
+    let mut apu: APU = APU::new();
+
+    apu.write_register(MMIO_JOY2, 0b0000_0000);
+    for _ in 0..29827 {
+        apu.tick();
+    }
+    assert!(!apu.irq_pending());
+    for _ in 0..10 {
+        apu.tick();
+    }
+    assert!(apu.irq_pending());
+
+    // Reading the status acknowledges the frame interrupt
+    assert_eq!(apu.read_status() & 0b0100_0000, 0b0100_0000);
+    assert!(!apu.irq_pending());
+
+    // Setting the inhibit flag clears it as well
+    for _ in 0..29830 {
+        apu.tick();
+    }
+    assert!(apu.irq_pending());
+    apu.write_register(MMIO_JOY2, 0b0100_0000);
+    assert!(!apu.irq_pending());
+}
+
+#[test]
+fn test_frame_counter_5_step() {
+    let mut apu: APU = APU::new();
+
+    apu.write_register(MMIO_SND_CHN, 0b0000_0001);
+    apu.write_register(MMIO_SQ1_HI, 0b0000_1000);
+    assert_eq!(apu.pulse1.length_counter.counter, 254);
+
+    // Switching to the 5-step sequence immediately clocks a half frame
+    apu.write_register(MMIO_JOY2, 0b1000_0000);
+    assert_eq!(apu.frame_counter.mode, SequenceMode::FiveStep);
+    assert_eq!(apu.pulse1.length_counter.counter, 253);
+
+    // The sequencer is reset a few cycles after the write
+    for _ in 0..(37282 * 2 + 4) {
+        apu.tick();
+    }
+    assert!(!apu.irq_pending());
+    // Two half frames per sequence
+    assert_eq!(apu.pulse1.length_counter.counter, 249);
+}
+
+#[test]
+fn test_dmc_dma_and_irq() {
+    let mut prg_rom: Vec<u8> = vec![0x00; 2 * PRG_ROM_PAGE_SIZE];
+    // Sample data at $C000
+    prg_rom[0x4000] = 0xFF;
+    prg_rom[0x4001] = 0xFF;
+    let nes: NES = setup_nes_with_rom(prg_rom);
+
+    // IRQ enabled, no loop, fastest rate, 17 byte sample at $C000
+    nes.bus_mut().write(MMIO_DMC_FREQ, 0b1000_1111);
+    nes.bus_mut().write(MMIO_DMC_RAW, 0x00);
+    nes.bus_mut().write(MMIO_DMC_START, 0x00);
+    nes.bus_mut().write(MMIO_DMC_LEN, 0x01);
+    nes.bus_mut().write(MMIO_JOY2, 0b0100_0000);
+    nes.bus_mut().write(MMIO_SND_CHN, 0b0001_0000);
+    assert_eq!(nes.apu().dmc.bytes_remaining, 17);
+
+    // The first fetch happens right away and stalls the CPU
+    nes.bus_mut().tick(1);
+    assert_eq!(nes.apu().dmc.bytes_remaining, 16);
+    assert_eq!(nes.apu().dmc.current_address, 0xC001);
+    assert_eq!(nes.bus().cpu_cycles, 1 + dmc::DMA_STALL_CYCLES);
+
+    nes.bus_mut().tick(54 * 8 * 2);
+    assert!(nes.apu().dmc.output_level > 0);
+
+    assert!(nes.bus_mut().poll_interrupts().is_none());
+    nes.bus_mut().tick(54 * 8 * 16);
+    assert_eq!(nes.apu().dmc.bytes_remaining, 0);
+    assert_eq!(nes.bus_mut().read(MMIO_SND_CHN) & 0b1001_0000, 0b1000_0000);
+    assert!(nes.bus_mut().poll_interrupts() == Some(interrupt::IRQ));
+
+    // Writing $4015 acknowledges the DMC interrupt
+    nes.bus_mut().write(MMIO_SND_CHN, 0b0000_0000);
+    assert!(nes.bus_mut().poll_interrupts().is_none());
+}
+
+#[test]
+fn test_resampler_rate() {
+    let mut apu: APU = APU::new();
+    assert!(apu.resampler.is_none());
+
+    apu.set_sample_rate(Some(44_100.0));
+    // The high-pass filters would slowly pull the DC offset of the idle triangle down
+    apu.resampler.as_mut().unwrap().filters.enabled = false;
+    // One second of CPU cycles
+    for _ in 0..1_789_773 {
+        apu.tick();
+    }
+
+    let resampler: &mut Resampler = apu.resampler.as_mut().unwrap();
+    assert!((resampler.pending_samples() as isize - 44_100).abs() <= 1);
+    // Nothing is playing, so the output should be a flat line
+    let samples: Vec<f32> = resampler.drain_samples().collect();
+    assert!(samples.iter().all(|sample: &f32| *sample == samples[0]));
+    assert_eq!(resampler.pending_samples(), 0);
+}
+
+#[test]
+fn test_mixer_lookup_tables() {
+    let mixer: Mixer = Mixer::new();
+
+    assert_eq!(mixer.mix([0, 0, 0, 0, 0]), 0.0);
+    // https://www.nesdev.org/wiki/APU_Mixer#Lookup_Table
+    assert!((mixer.mix([15, 15, 0, 0, 0]) - 95.52 / (8128.0 / 30.0 + 100.0)).abs() < 0.0001);
+    assert!((mixer.mix([0, 0, 15, 15, 127]) - 163.67 / (24329.0 / 202.0 + 100.0)).abs() < 0.0001);
+
+    // The pulse mixing is non-linear, so doubling the input doesn't double the output
+    let single: f32 = mixer.mix([15, 0, 0, 0, 0]);
+    let double: f32 = mixer.mix([15, 15, 0, 0, 0]);
+    assert!(double < single * 2.0);
+}
+
+#[test]
+fn test_mixer_mute_solo_gain() {
+    let mut apu: APU = APU::new();
+    let outputs: [u8; 5] = [15, 15, 15, 15, 64];
+    let full: f32 = apu.mixer.mix(outputs);
+
+    apu.set_channel_muted(Channel::DMC, true);
+    assert!(apu.mixer.mix(outputs) < full);
+    assert_eq!(apu.mixer.mix([0, 0, 0, 0, 64]), 0.0);
+    apu.set_channel_muted(Channel::DMC, false);
+
+    apu.set_channel_solo(Channel::Triangle, true);
+    assert!(!apu.mixer.is_audible(Channel::Pulse1));
+    assert!(apu.mixer.is_audible(Channel::Triangle));
+    assert_eq!(apu.mixer.mix(outputs), apu.mixer.mix([0, 0, 15, 0, 0]));
+    apu.set_channel_solo(Channel::Triangle, false);
+
+    apu.set_channel_gain(Channel::Pulse1, 0.5);
+    let half: f32 = apu.mixer.mix([16, 0, 0, 0, 0]);
+    apu.set_channel_gain(Channel::Pulse1, 1.0);
+    assert_eq!(half, apu.mixer.mix([8, 0, 0, 0, 0]));
+}
+
+#[test]
+fn test_filter_chain_removes_dc_offset() {
+    let mut filters: FilterChain = FilterChain::new(44_100.0);
+
+    let mut output: f32 = 1.0;
+    for _ in 0..44_100 {
+        output = filters.process(0.5);
+    }
+    assert!(output.abs() < 0.001);
+
+    filters.enabled = false;
+    assert_eq!(filters.process(0.5), 0.5);
+}
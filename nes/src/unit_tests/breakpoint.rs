@@ -0,0 +1,263 @@
+use super::*;
+use crate::{
+    breakpoint::{
+        Access, AddressSpace, Breakpoint, BreakpointHit, Condition, ConditionError, MemoryAccess,
+        Trigger,
+    },
+    cpu::{CPU, interrupt::InterruptType},
+};
+
+#[rustfmt::skip]
+const COUNTER_PROGRAM: [u8; 7] = [
+    0xE8,             // INX
+    0x86, 0x10,       // STX $10
+    0x4C, 0x00, 0x80, // JMP $8000
+    0x02,             // KIL
+];
+
+fn add(nes: &NES, breakpoint: Breakpoint) -> usize {
+    nes.breakpoints_mut().add(breakpoint)
+}
+
+/// Steps until a breakpoint is hit, giving up after `max_steps` instructions
+fn run_to_hit(nes: &mut NES, max_steps: usize) -> Option<BreakpointHit> {
+    for _ in 0..max_steps {
+        if !nes.step(|_| {}) {
+            break;
+        }
+        if let Some(hit) = nes.take_breakpoint_hit() {
+            return Some(hit);
+        }
+    }
+    None
+}
+
+#[test]
+fn test_execute_breakpoint() {
+    let mut nes: NES = setup_nes_with_rom(COUNTER_PROGRAM.to_vec());
+    let index: usize = add(&nes, Breakpoint::new(Trigger::Execute(0x8001..=0x8001)));
+
+    let hit: BreakpointHit = run_to_hit(&mut nes, 10).unwrap();
+    assert_eq!(hit.index, index);
+    assert_eq!(hit.program_counter, 0x8001);
+    assert_eq!(hit.access, None);
+    // Stops before the instruction runs
+    assert_eq!(nes.cpu.program_counter, 0x8001);
+    assert_eq!(nes.memory().cpu_vram[0x10], 0x00);
+
+    // Resuming runs past it, then stops on the next loop
+    let hit: BreakpointHit = run_to_hit(&mut nes, 10).unwrap();
+    assert_eq!(hit.program_counter, 0x8001);
+    assert_eq!(nes.memory().cpu_vram[0x10], 0x01);
+    assert_eq!(nes.cpu.index_x, 0x02);
+}
+
+#[test]
+fn test_set_drops_stale_hits() {
+    let mut nes: NES = setup_nes_with_rom(COUNTER_PROGRAM.to_vec());
+    add(&nes, Breakpoint::new(Trigger::Execute(0x8001..=0x8001)));
+    assert!(nes.step(|_| {}));
+    assert!(nes.step(|_| {}));
+    assert_eq!(nes.cpu.program_counter, 0x8001);
+
+    // The hit isn't taken before the list is replaced
+    nes.breakpoints_mut()
+        .set(vec![Breakpoint::new(Trigger::Execute(0x8001..=0x8001))]);
+    assert_eq!(nes.take_breakpoint_hit(), None);
+
+    // And the new breakpoint isn't skipped as if it was being resumed from
+    assert!(nes.step(|_| {}));
+    let hit: BreakpointHit = nes.take_breakpoint_hit().unwrap();
+    assert_eq!(hit.program_counter, 0x8001);
+    assert_eq!(nes.memory().cpu_vram[0x10], 0x00);
+}
+
+#[test]
+fn test_execute_breakpoint_condition() {
+    let mut nes: NES = setup_nes_with_rom(COUNTER_PROGRAM.to_vec());
+    add(
+        &nes,
+        Breakpoint::new(Trigger::Execute(0x8000..=0x8005))
+            .with_condition("X == 3 && PC == $8001".parse().unwrap()),
+    );
+
+    let hit: BreakpointHit = run_to_hit(&mut nes, 100).unwrap();
+    assert_eq!(hit.program_counter, 0x8001);
+    assert_eq!(nes.cpu.index_x, 0x03);
+}
+
+#[test]
+fn test_write_watchpoint() {
+    let mut nes: NES = setup_nes_with_rom(COUNTER_PROGRAM.to_vec());
+    add(
+        &nes,
+        Breakpoint::new(Trigger::Memory {
+            space: AddressSpace::Cpu,
+            access: Access::WRITE,
+            range: 0x0010..=0x0010,
+        })
+        .with_condition("VALUE >= 4".parse().unwrap()),
+    );
+
+    let hit: BreakpointHit = run_to_hit(&mut nes, 100).unwrap();
+    // Reported after the instruction that wrote
+    assert_eq!(hit.program_counter, 0x8001);
+    assert_eq!(nes.cpu.program_counter, 0x8003);
+    assert_eq!(
+        hit.access,
+        Some(MemoryAccess {
+            space: AddressSpace::Cpu,
+            access: Access::WRITE,
+            addr: 0x0010,
+            value: 0x04,
+        })
+    );
+}
+
+#[test]
+fn test_dmc_dma_skips_watchpoints() {
+    #[rustfmt::skip]
+    let mut nes: NES = setup_nes_with_rom(vec![
+        0xA9, 0x0F,       // LDA #$0F
+        0x8D, 0x10, 0x40, // STA $4010
+        0xA9, 0x00,       // LDA #$00
+        0x8D, 0x12, 0x40, // STA $4012
+        0xA9, 0x01,       // LDA #$01
+        0x8D, 0x13, 0x40, // STA $4013
+        0xA9, 0x10,       // LDA #$10
+        0x8D, 0x15, 0x40, // STA $4015
+        0x4C, 0x14, 0x80, // JMP $8014
+    ]);
+    add(
+        &nes,
+        Breakpoint::new(Trigger::Memory {
+            space: AddressSpace::Cpu,
+            access: Access::READ,
+            range: 0xC000..=0xC010,
+        }),
+    );
+
+    // The DMC reads its sample from $C000, but the CPU never does
+    assert_eq!(run_to_hit(&mut nes, 2_000), None);
+    assert!(nes.apu().dmc.bytes_remaining < 17);
+}
+
+#[test]
+fn test_ppu_watchpoint() {
+    #[rustfmt::skip]
+    let mut nes: NES = setup_nes_with_rom(vec![
+        0xA9, 0x21,       // LDA #$21
+        0x8D, 0x06, 0x20, // STA $2006
+        0xA9, 0x08,       // LDA #$08
+        0x8D, 0x06, 0x20, // STA $2006
+        0xA9, 0x5A,       // LDA #$5A
+        0x8D, 0x07, 0x20, // STA $2007
+        0x02,             // KIL
+    ]);
+    // The PPU ignores $2006 right after power-on
+    nes.ppu_mut().cycles = 9886;
+    add(
+        &nes,
+        Breakpoint::new(Trigger::Memory {
+            space: AddressSpace::Ppu,
+            access: Access::WRITE,
+            range: 0x2000..=0x23FF,
+        }),
+    );
+
+    let hit: BreakpointHit = run_to_hit(&mut nes, 10).unwrap();
+    assert_eq!(hit.program_counter, 0x800C);
+    assert_eq!(
+        hit.access,
+        Some(MemoryAccess {
+            space: AddressSpace::Ppu,
+            access: Access::WRITE,
+            addr: 0x2108,
+            value: 0x5A,
+        })
+    );
+}
+
+#[test]
+fn test_interrupt_breakpoints() {
+    // NOP
+    // BRK
+    let mut nes: NES = setup_nes_with_rom(vec![0xEA, 0x00]);
+    let nmi: usize = add(
+        &nes,
+        Breakpoint::new(Trigger::Interrupt(InterruptType::NMI)),
+    );
+    let brk: usize = add(
+        &nes,
+        Breakpoint::new(Trigger::Interrupt(InterruptType::BRK)),
+    );
+
+    nes.ppu_mut().nmi_interrupt = Some(1);
+    let hit: BreakpointHit = run_to_hit(&mut nes, 1).unwrap();
+    assert_eq!(hit.index, nmi);
+    // Stops on the first instruction of the handler, which hasn't run yet
+    assert_eq!(hit.program_counter, 0x0000);
+    assert_eq!(nes.cpu.program_counter, 0x0000);
+
+    // The handler at $0000 is a BRK, since RAM starts out zeroed
+    let hit: BreakpointHit = run_to_hit(&mut nes, 1).unwrap();
+    assert_eq!(hit.index, brk);
+    assert_eq!(hit.program_counter, 0x0000);
+}
+
+#[test]
+fn test_disabled_breakpoints_and_debugger_reads() {
+    let mut nes: NES = setup_nes_with_rom(COUNTER_PROGRAM.to_vec());
+    let index: usize = add(&nes, Breakpoint::new(Trigger::Execute(0x8001..=0x8001)));
+    nes.breakpoints_mut().set_enabled(index, false);
+    let watch: usize = add(
+        &nes,
+        Breakpoint::new(Trigger::Memory {
+            space: AddressSpace::Cpu,
+            access: Access::READ,
+            range: 0x8000..=0x8005,
+        }),
+    );
+    nes.breakpoints_mut().set_enabled(watch, false);
+    assert_eq!(run_to_hit(&mut nes, 100), None);
+
+    // Disassembling reads the watched bytes, but isn't the program reading them
+    nes.breakpoints_mut().set_enabled(watch, true);
+    let _: String = tools::trace(&nes.cpu);
+    assert_eq!(nes.take_breakpoint_hit(), None);
+    assert_eq!(
+        nes.breakpoints().list()[watch].trigger.to_string(),
+        "Cpu Read $8000-$8005"
+    );
+}
+
+#[test]
+fn test_condition_parsing() {
+    let mut cpu_nes: NES = setup_nes();
+    cpu_nes.cpu.accumulator = 0x10;
+    cpu_nes.cpu.index_x = 3;
+    cpu_nes.cpu.status.insert(crate::cpu::Flags::CARRY);
+    let cpu: &CPU = &cpu_nes.cpu;
+    let eval = |text: &str| -> bool { text.parse::<Condition>().unwrap().eval(cpu, None) };
+
+    assert!(eval("A == $10"));
+    assert!(eval("a == 0x10"));
+    assert!(eval("A == 16"));
+    assert!(eval("X <= 3 && X >= 3 && X != 4"));
+    assert!(eval("C"));
+    assert!(!eval("Z"));
+    // `&&` binds tighter than `||`
+    assert!(eval("A == 0 && X == 0 || C"));
+    assert!(!eval("A == 0 || X == 0 && C || Z"));
+
+    let condition: Condition = " A > 2 ".parse().unwrap();
+    assert_eq!(condition.to_string(), "A > 2");
+    assert_eq!(
+        "A ==".parse::<Condition>(),
+        Err(ConditionError::MissingOperand)
+    );
+    assert_eq!(
+        "Q == 1".parse::<Condition>(),
+        Err(ConditionError::UnknownOperand("Q".to_string()))
+    );
+}
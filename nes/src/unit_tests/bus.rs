@@ -1,4 +1,8 @@
 use super::*;
+use crate::{
+    breakpoint::{Access, AddressSpace, Breakpoint, Trigger},
+    input_device::{NESDeviceType, joypad::JoypadButton},
+};
 
 #[test]
 fn test_mem_read_write_to_ram() {
@@ -7,3 +11,135 @@ fn test_mem_read_write_to_ram() {
 
     assert_eq!(nes.bus_mut().read(0x0001), 0x55);
 }
+
+#[test]
+fn test_cartridge_space_goes_to_mapper() {
+    let mut prg_rom: Vec<u8> = vec![0x00; PRG_ROM_PAGE_SIZE];
+    prg_rom[0x0000] = 0x42;
+    let nes: NES = setup_nes_with_rom(prg_rom);
+
+    // NROM-256 doesn't mirror $C000-$FFFF down to $8000
+    assert_eq!(nes.bus_mut().read(0x8000), 0x42);
+    assert_eq!(nes.bus_mut().read(0xC000), 0x00);
+
+    nes.bus_mut().write(0x8000, 0x55);
+    assert_eq!(nes.bus_mut().read(0x8000), 0x42);
+}
+
+#[test]
+fn test_input_devices_on_both_ports() {
+    let mut nes: NES = setup_nes();
+    nes.connect_input_device(1, NESDeviceType::Joypad);
+    nes.connect_input_device(2, NESDeviceType::Joypad);
+    nes.device2_mut()
+        .set_button_pressed_status(Box::new(JoypadButton::BUTTON_A), true);
+
+    nes.bus_mut().write(0x4016, 1);
+    nes.bus_mut().write(0x4016, 0);
+    assert_eq!(nes.bus_mut().read(0x4016), 0);
+    assert_eq!(nes.bus_mut().read(0x4017), 1);
+}
+
+#[test]
+fn test_step_frame_without_nmi() {
+    #[rustfmt::skip]
+    let mut nes: NES = setup_nes_with_rom(vec![
+        0x4C, 0x00, 0x80, // JMP $8000
+    ]);
+    let rendered: Rc<RefCell<usize>> = Rc::new(RefCell::new(0));
+    let cb_rendered: Rc<RefCell<usize>> = rendered.clone();
+    nes.render_callback(move |_, _, _| *cb_rendered.borrow_mut() += 1);
+
+    for _ in 0..3 {
+        assert!(nes.step_frame(|_| {}));
+        assert_eq!(nes.ppu().scanline, crate::VBLANK_SCANLINE);
+    }
+    assert_eq!(*rendered.borrow(), 0);
+}
+
+#[test]
+fn test_peek_has_no_side_effects() {
+    let mut nes: NES = setup_nes();
+    nes.connect_input_device(1, NESDeviceType::Joypad);
+    nes.device1_mut()
+        .set_button_pressed_status(Box::new(JoypadButton::BUTTON_A), true);
+    nes.ppu_mut().status.set_vblank_status(true);
+    nes.ppu_mut().vram_addr.set_hi_byte(0x3F);
+    nes.ppu_mut().vram_addr.set_lo_byte(0x01);
+    nes.ppu_mut().palette_table[1] = 0x2A;
+    nes.apu_mut().frame_counter.irq_flag = true;
+
+    for _ in 0..2 {
+        assert_eq!(nes.bus().peek(0x2002) & 0x80, 0x80);
+        assert_eq!(nes.bus().peek(0x3FFA), 0x80);
+        assert_eq!(nes.bus().peek(0x2007), 0x2A);
+        assert_eq!(nes.bus().peek(0x4015) & 0x40, 0x40);
+        assert_eq!(nes.bus().peek(0x4016), 1);
+    }
+    assert_eq!(nes.ppu().vram_addr.get(), 0x3F01);
+
+    // The real reads still have their side effects
+    assert_eq!(nes.bus_mut().read(0x2002) & 0x80, 0x80);
+    assert_eq!(nes.bus().peek(0x2002) & 0x80, 0x00);
+    assert_eq!(nes.bus_mut().read(0x4015) & 0x40, 0x40);
+    assert_eq!(nes.bus().peek(0x4015) & 0x40, 0x00);
+}
+
+#[test]
+fn test_peek_doesnt_trip_breakpoints() {
+    #[rustfmt::skip]
+    let mut nes: NES = setup_nes_with_rom(vec![
+        0xEA,             // NOP
+        0x4C, 0x00, 0x80, // JMP $8000
+    ]);
+    nes.breakpoints_mut().add(Breakpoint::new(Trigger::Memory {
+        space: AddressSpace::Cpu,
+        access: Access::READ,
+        range: 0x0000..=0x7FFF,
+    }));
+
+    for addr in [0x0000, 0x2002, 0x2007, 0x4015, 0x4016, 0x6000] {
+        nes.bus().peek(addr);
+    }
+    assert!(nes.step(|_| {}));
+    assert_eq!(nes.take_breakpoint_hit(), None);
+
+    nes.bus_mut().read(0x0000);
+    assert!(nes.step(|_| {}));
+    assert!(nes.take_breakpoint_hit().is_some());
+}
+
+#[test]
+fn test_poke_writes_storage() {
+    let nes: NES = setup_nes_with_rom(vec![0xEA]);
+    assert!(nes.bus_mut().poke(0x0801, 0x55));
+    assert_eq!(nes.bus().peek(0x0001), 0x55);
+
+    // PRG-ROM can be patched, unlike with a write
+    assert!(nes.bus_mut().poke(0xC000, 0x42));
+    assert_eq!(nes.bus().peek(0xC000), 0x42);
+    assert_eq!(nes.memory().prg_rom[0x4000], 0x42);
+
+    // Registers have nothing behind them
+    assert!(!nes.bus_mut().poke(0x2000, 0x80));
+    assert_eq!(nes.ppu().ctrl.bits(), 0x00);
+    // Neither does PRG-RAM on a cartridge without it
+    assert!(!nes.bus_mut().poke(0x6000, 0x01));
+}
+
+#[test]
+fn test_ppu_peek_and_poke() {
+    let nes: NES = setup_nes();
+    nes.ppu_mut().poke(0x3F10, 0x0F);
+    assert_eq!(nes.ppu().palette_table[0x00], 0x0F);
+    assert_eq!(nes.ppu().peek(0x3F30), 0x0F);
+
+    // Horizontal mirroring, $2400 is $2000 and $3000-$3EFF mirrors $2000-$2EFF
+    nes.ppu_mut().poke(0x2400, 0x24);
+    assert_eq!(nes.ppu().peek(0x2000), 0x24);
+    assert_eq!(nes.ppu().peek(0x3000), 0x24);
+
+    nes.ppu_mut().poke(0x0010, 0x99);
+    assert_eq!(nes.ppu().peek(0x0010), 0x99);
+    assert_eq!(nes.memory().chr_mem[0x0010], 0x99);
+}
@@ -13,7 +13,7 @@ fn test_for_missing_opcodes() {
         }
     }
 
-    if missing_opcodes.len() > 0 {
+    if !missing_opcodes.is_empty() {
         panic!(
             "{} missing opcodes\n[{}]",
             missing_opcodes.len(),
@@ -30,17 +30,15 @@ fn test_for_missing_opcodes() {
 fn test_for_no_cycle_opcodes() {
     let mut no_cycle_opcodes: Vec<OpCode> = Vec::new();
     for byte in 0..256 {
-        match OPCODES.get(&(byte as u8)) {
-            Some(opcode) => {
-                if (opcode.cycles <= 0) && (opcode.instruction != Instruction::KIL) {
-                    no_cycle_opcodes.push(*opcode);
-                }
-            }
-            None => {}
+        if let Some(opcode) = OPCODES.get(&(byte as u8))
+            && opcode.cycles == 0
+            && opcode.instruction != Instruction::KIL
+        {
+            no_cycle_opcodes.push(*opcode);
         }
     }
 
-    if no_cycle_opcodes.len() > 0 {
+    if !no_cycle_opcodes.is_empty() {
         panic!(
             "{} no cycle opcodes\n[{}]",
             no_cycle_opcodes.len(),
@@ -0,0 +1,500 @@
+use super::*;
+use crate::{
+    cpu::interrupt,
+    ppu::registers::{ControlRegister, MaskRegister},
+    save_state::{SaveState, SaveStateError, StateReader, StateWriter},
+};
+
+#[test]
+fn test_nrom_battery_ram() {
+    let mut rom: ROM = test_rom(vec![]);
+    rom.uses_bat_mem = true;
+    let nes: NES = NES::new(rom);
+
+    // A battery without a declared PRG-RAM size still gets 8 KiB at $6000-$7FFF
+    assert_eq!(nes.memory().prg_ram.len(), PRG_RAM_PAGE_SIZE);
+    nes.bus_mut().write(0x6000, 0x12);
+    nes.bus_mut().write(0x7FFF, 0x34);
+
+    let save: Vec<u8> = nes.battery_ram().unwrap();
+    assert_eq!(save[0x0000], 0x12);
+    assert_eq!(save[0x1FFF], 0x34);
+
+    let mut rom: ROM = test_rom(vec![]);
+    rom.uses_bat_mem = true;
+    let mut reloaded: NES = NES::new(rom);
+    reloaded.load_battery_ram(&save);
+    assert_eq!(reloaded.bus_mut().read(0x6000), 0x12);
+    assert_eq!(reloaded.bus_mut().read(0x7FFF), 0x34);
+
+    // Only cartridges with a battery have anything worth saving
+    assert!(setup_nes().battery_ram().is_none());
+}
+
+/// 8 PRG-ROM banks and 8 CHR-ROM banks, each filled with its own bank number
+fn mmc1_rom(submapper: u8, prg_ram_size: usize) -> ROM {
+    let mut prg_rom: Vec<u8> = (0..8u8)
+        .flat_map(|bank: u8| vec![bank; PRG_ROM_PAGE_SIZE])
+        .collect();
+    prg_rom[7 * PRG_ROM_PAGE_SIZE + 0x3FFC] = 0x00;
+    prg_rom[7 * PRG_ROM_PAGE_SIZE + 0x3FFD] = 0x80;
+
+    ROM {
+        ines_ver: 2,
+        mapper: 1,
+        submapper,
+        region: ROMRegion::NTSC,
+        prg_rom,
+        prg_ram_size,
+        chr_rom: (0..8u8).flat_map(|bank: u8| vec![bank; 0x1000]).collect(),
+        chr_ram_size: 0,
+        screen_mirroring: Mirroring::Horizontal,
+        uses_bat_mem: false,
+    }
+}
+
+fn write_mmc1_register(nes: &NES, addr: u16, value: u8) {
+    for bit in 0..5 {
+        nes.bus_mut().write(addr, (value >> bit) & 0b0000_0001);
+    }
+}
+
+#[test]
+fn test_mmc1_power_up_fixes_last_bank() {
+    let nes: NES = NES::new(mmc1_rom(0, 0));
+
+    assert_eq!(nes.bus_mut().read(0x8000), 0);
+    assert_eq!(nes.bus_mut().read(0xC000), 7);
+}
+
+#[test]
+fn test_mmc1_shift_register_reset() {
+    let nes: NES = NES::new(mmc1_rom(0, 0));
+
+    // Two bits in, then a reset: the next 5 writes have to start from scratch
+    nes.bus_mut().write(0xE000, 0b0000_0001);
+    nes.bus_mut().write(0xE000, 0b0000_0001);
+    nes.bus_mut().write(0x8000, 0b1000_0000);
+    write_mmc1_register(&nes, 0xE000, 0b0000_0010);
+
+    assert_eq!(nes.bus_mut().read(0x8000), 2);
+}
+
+#[test]
+fn test_mmc1_prg_modes() {
+    let nes: NES = NES::new(mmc1_rom(0, 0));
+
+    // Mode 3: switch $8000, fix last bank at $C000
+    write_mmc1_register(&nes, 0xE000, 3);
+    assert_eq!(nes.bus_mut().read(0x8000), 3);
+    assert_eq!(nes.bus_mut().read(0xC000), 7);
+
+    // Mode 2: fix first bank at $8000, switch $C000
+    write_mmc1_register(&nes, 0x8000, 0b0000_1000);
+    assert_eq!(nes.bus_mut().read(0x8000), 0);
+    assert_eq!(nes.bus_mut().read(0xC000), 3);
+
+    // Mode 0: switch 32 KiB, ignoring the low bit
+    write_mmc1_register(&nes, 0x8000, 0b0000_0000);
+    assert_eq!(nes.bus_mut().read(0x8000), 2);
+    assert_eq!(nes.bus_mut().read(0xC000), 3);
+}
+
+#[test]
+fn test_mmc1_chr_modes() {
+    let nes: NES = NES::new(mmc1_rom(0, 0));
+
+    // 8 KiB mode ignores the low bit and CHR bank 1
+    write_mmc1_register(&nes, 0xA000, 3);
+    write_mmc1_register(&nes, 0xC000, 5);
+    assert_eq!(nes.ppu_mut().raw_read_data(0x0000), 2);
+    assert_eq!(nes.ppu_mut().raw_read_data(0x1000), 3);
+
+    // 4 KiB mode
+    write_mmc1_register(&nes, 0x8000, 0b0001_1100);
+    assert_eq!(nes.ppu_mut().raw_read_data(0x0000), 3);
+    assert_eq!(nes.ppu_mut().raw_read_data(0x1000), 5);
+}
+
+#[test]
+fn test_mmc1_mirroring() {
+    let nes: NES = NES::new(mmc1_rom(0, 0));
+
+    write_mmc1_register(&nes, 0x8000, 0b0000_1100);
+    assert_eq!(nes.mapper().mirroring(), Mirroring::SingleScreenA);
+    write_mmc1_register(&nes, 0x8000, 0b0000_1101);
+    assert_eq!(nes.mapper().mirroring(), Mirroring::SingleScreenB);
+    write_mmc1_register(&nes, 0x8000, 0b0000_1110);
+    assert_eq!(nes.mapper().mirroring(), Mirroring::Vertical);
+    write_mmc1_register(&nes, 0x8000, 0b0000_1111);
+    assert_eq!(nes.mapper().mirroring(), Mirroring::Horizontal);
+}
+
+#[test]
+fn test_mmc1_prg_ram_enable() {
+    let nes: NES = NES::new(mmc1_rom(0, 0));
+
+    nes.bus_mut().write(0x6000, 0x55);
+    assert_eq!(nes.bus_mut().read(0x6000), 0x55);
+
+    write_mmc1_register(&nes, 0xE000, 0b0001_0000);
+    nes.bus_mut().write(0x6000, 0xAA);
+    assert_eq!(nes.bus_mut().read(0x6000), 0x00);
+
+    write_mmc1_register(&nes, 0xE000, 0b0000_0000);
+    assert_eq!(nes.bus_mut().read(0x6000), 0x55);
+}
+
+#[test]
+fn test_mmc1_sorom_prg_ram_banks() {
+    let nes: NES = NES::new(mmc1_rom(2, 2 * PRG_RAM_PAGE_SIZE));
+
+    nes.bus_mut().write(0x6000, 0x11);
+    write_mmc1_register(&nes, 0xA000, 0b0000_1000);
+    assert_eq!(nes.bus_mut().read(0x6000), 0x00);
+    nes.bus_mut().write(0x6000, 0x22);
+
+    write_mmc1_register(&nes, 0xA000, 0b0000_0000);
+    assert_eq!(nes.bus_mut().read(0x6000), 0x11);
+}
+
+/// `count` ROM banks of `size` bytes, each filled with its own bank number
+fn banked_rom(count: u8, size: usize) -> Vec<u8> {
+    (0..count).flat_map(|bank: u8| vec![bank; size]).collect()
+}
+
+fn discrete_rom(mapper: u16, submapper: u8, prg_rom: Vec<u8>, chr_rom: Vec<u8>) -> ROM {
+    let mut rom: ROM = test_rom(vec![]);
+    rom.mapper = mapper;
+    rom.submapper = submapper;
+    rom.prg_rom = prg_rom;
+    rom.chr_ram_size = if chr_rom.is_empty() {
+        CHR_ROM_PAGE_SIZE
+    } else {
+        0
+    };
+    rom.chr_rom = chr_rom;
+    rom
+}
+
+#[test]
+fn test_uxrom_banks() {
+    let nes: NES = NES::new(discrete_rom(2, 0, banked_rom(8, PRG_ROM_PAGE_SIZE), vec![]));
+
+    assert_eq!(nes.bus_mut().read(0x8000), 0);
+    assert_eq!(nes.bus_mut().read(0xC000), 7);
+
+    nes.bus_mut().write(0x8000, 5);
+    assert_eq!(nes.bus_mut().read(0x8000), 5);
+    assert_eq!(nes.bus_mut().read(0xFFFF), 7);
+
+    // CHR-RAM
+    nes.ppu_mut().raw_write_to_data(0x1234, 0x66);
+    assert_eq!(nes.ppu_mut().raw_read_data(0x1234), 0x66);
+}
+
+#[test]
+fn test_uxrom_bus_conflicts() {
+    let nes: NES = NES::new(discrete_rom(2, 2, banked_rom(8, PRG_ROM_PAGE_SIZE), vec![]));
+
+    // The fixed bank is filled with 0b111, so only those bits of the value survive
+    nes.bus_mut().write(0xC000, 0b0000_1101);
+    assert_eq!(nes.bus_mut().read(0x8000), 5);
+
+    // The switchable bank currently holds 5
+    nes.bus_mut().write(0x8000, 0b0000_0110);
+    assert_eq!(nes.bus_mut().read(0x8000), 4);
+}
+
+#[test]
+fn test_cnrom_chr_banks() {
+    let nes: NES = NES::new(discrete_rom(
+        3,
+        0,
+        banked_rom(1, PRG_ROM_PAGE_SIZE),
+        banked_rom(4, CHR_ROM_PAGE_SIZE),
+    ));
+
+    // NROM-128 style mirroring of the only PRG-ROM bank
+    assert_eq!(nes.bus_mut().read(0xC000), 0);
+    assert_eq!(nes.ppu_mut().raw_read_data(0x0000), 0);
+
+    nes.bus_mut().write(0x8000, 3);
+    assert_eq!(nes.ppu_mut().raw_read_data(0x0000), 3);
+    assert_eq!(nes.ppu_mut().raw_read_data(0x1FFF), 3);
+}
+
+#[test]
+fn test_cnrom_bus_conflicts() {
+    let mut prg_rom: Vec<u8> = vec![0xFF; 2 * PRG_ROM_PAGE_SIZE];
+    prg_rom[0x0000] = 0b0000_0001;
+    let nes: NES = NES::new(discrete_rom(
+        3,
+        2,
+        prg_rom,
+        banked_rom(4, CHR_ROM_PAGE_SIZE),
+    ));
+
+    nes.bus_mut().write(0x8000, 0b0000_0011);
+    assert_eq!(nes.ppu_mut().raw_read_data(0x0000), 1);
+    nes.bus_mut().write(0x8001, 0b0000_0011);
+    assert_eq!(nes.ppu_mut().raw_read_data(0x0000), 3);
+}
+
+#[test]
+fn test_axrom_banks_and_mirroring() {
+    let nes: NES = NES::new(discrete_rom(
+        7,
+        0,
+        banked_rom(8, 2 * PRG_ROM_PAGE_SIZE),
+        vec![],
+    ));
+
+    assert_eq!(nes.bus_mut().read(0x8000), 0);
+    assert_eq!(nes.mapper().mirroring(), Mirroring::SingleScreenA);
+
+    nes.bus_mut().write(0x8000, 0b0001_0110);
+    assert_eq!(nes.bus_mut().read(0x8000), 6);
+    assert_eq!(nes.bus_mut().read(0xFFFF), 6);
+    assert_eq!(nes.mapper().mirroring(), Mirroring::SingleScreenB);
+
+    nes.ppu_mut().raw_write_to_data(0x2805, 0x66);
+    assert_eq!(nes.ppu().vram[0x0405], 0x66);
+}
+
+fn mmc3_nes(submapper: u8) -> NES {
+    let mut prg_rom: Vec<u8> = banked_rom(16, 0x2000);
+    prg_rom[15 * 0x2000 + 0x1FFC] = 0x00;
+    prg_rom[15 * 0x2000 + 0x1FFD] = 0x80;
+    NES::new(discrete_rom(4, submapper, prg_rom, banked_rom(32, 0x0400)))
+}
+
+#[test]
+fn test_mmc3_prg_banks() {
+    let nes: NES = mmc3_nes(0);
+
+    nes.bus_mut().write(0x8000, 6);
+    nes.bus_mut().write(0x8001, 3);
+    nes.bus_mut().write(0x8000, 7);
+    nes.bus_mut().write(0x8001, 4);
+    assert_eq!(nes.bus_mut().read(0x8000), 3);
+    assert_eq!(nes.bus_mut().read(0xA000), 4);
+    assert_eq!(nes.bus_mut().read(0xC000), 14);
+    assert_eq!(nes.bus_mut().read(0xE000), 15);
+
+    // PRG-ROM bank mode 1 swaps $8000 and $C000
+    nes.bus_mut().write(0x8000, 0b0100_0000);
+    assert_eq!(nes.bus_mut().read(0x8000), 14);
+    assert_eq!(nes.bus_mut().read(0xC000), 3);
+}
+
+#[test]
+fn test_mmc3_chr_banks() {
+    let nes: NES = mmc3_nes(0);
+
+    for (register, bank) in [(0, 8), (1, 11), (2, 20), (3, 21), (4, 22), (5, 23)] {
+        nes.bus_mut().write(0x8000, register);
+        nes.bus_mut().write(0x8001, bank);
+    }
+    assert_eq!(nes.ppu_mut().raw_read_data(0x0000), 8);
+    assert_eq!(nes.ppu_mut().raw_read_data(0x0400), 9);
+    assert_eq!(nes.ppu_mut().raw_read_data(0x0800), 10);
+    assert_eq!(nes.ppu_mut().raw_read_data(0x0C00), 11);
+    assert_eq!(nes.ppu_mut().raw_read_data(0x1000), 20);
+    assert_eq!(nes.ppu_mut().raw_read_data(0x1C00), 23);
+
+    // CHR A12 inversion swaps the 2 KiB and 1 KiB halves
+    nes.bus_mut().write(0x8000, 0b1000_0000);
+    assert_eq!(nes.ppu_mut().raw_read_data(0x0000), 20);
+    assert_eq!(nes.ppu_mut().raw_read_data(0x1000), 8);
+    assert_eq!(nes.ppu_mut().raw_read_data(0x1C00), 11);
+}
+
+#[test]
+fn test_mmc3_mirroring_and_prg_ram_protect() {
+    let nes: NES = mmc3_nes(0);
+
+    nes.bus_mut().write(0xA000, 1);
+    assert_eq!(nes.mapper().mirroring(), Mirroring::Horizontal);
+    nes.bus_mut().write(0xA000, 0);
+    assert_eq!(nes.mapper().mirroring(), Mirroring::Vertical);
+
+    nes.bus_mut().write(0xA001, 0b1000_0000);
+    nes.bus_mut().write(0x6000, 0x55);
+    assert_eq!(nes.bus_mut().read(0x6000), 0x55);
+
+    nes.bus_mut().write(0xA001, 0b1100_0000);
+    nes.bus_mut().write(0x6000, 0xAA);
+    assert_eq!(nes.bus_mut().read(0x6000), 0x55);
+
+    nes.bus_mut().write(0xA001, 0b0000_0000);
+    assert_eq!(nes.bus_mut().read(0x6000), 0x00);
+}
+
+#[test]
+fn test_mmc3_a12_filter() {
+    let nes: NES = mmc3_nes(0);
+    nes.bus_mut().write(0xC000, 1);
+    nes.bus_mut().write(0xC001, 0);
+    nes.bus_mut().write(0xE001, 0);
+
+    // The first rise reloads the counter with 1, the second one brings it down to 0
+    nes.mapper_mut().ppu_bus_address(0x1000, 100);
+    nes.mapper_mut().ppu_bus_address(0x0000, 110);
+    // A12 wasn't low for long enough, like during background fetches from $1000
+    nes.mapper_mut().ppu_bus_address(0x1000, 114);
+    assert!(!nes.mapper().irq_pending());
+
+    nes.mapper_mut().ppu_bus_address(0x0000, 120);
+    nes.mapper_mut().ppu_bus_address(0x1000, 140);
+    assert!(nes.mapper().irq_pending());
+    assert!(nes.bus_mut().poll_interrupts() == Some(interrupt::IRQ));
+
+    nes.bus_mut().write(0xE000, 0);
+    assert!(!nes.mapper().irq_pending());
+}
+
+#[test]
+fn test_mmc3a_irq_on_reload_to_zero() {
+    for (submapper, fires) in [(0, true), (4, false)] {
+        let nes: NES = mmc3_nes(submapper);
+        nes.bus_mut().write(0xC000, 0);
+        nes.bus_mut().write(0xE001, 0);
+
+        // Reloading an already-zero counter with 0 only fires on the newer revisions
+        nes.mapper_mut().ppu_bus_address(0x1000, 100);
+        nes.mapper_mut().ppu_bus_address(0x0000, 110);
+        nes.mapper_mut().ppu_bus_address(0x1000, 130);
+        assert_eq!(nes.mapper().irq_pending(), fires);
+    }
+}
+
+#[test]
+fn test_mmc3_scanline_irq() {
+    let nes: NES = mmc3_nes(0);
+    nes.ppu_mut().ctrl = ControlRegister::from_bits_truncate(0b0000_1000); // Sprites at $1000
+    nes.ppu_mut().mask = MaskRegister::from_bits_truncate(0b0001_1000);
+
+    nes.bus_mut().write(0xC000, 10);
+    nes.bus_mut().write(0xC001, 0);
+    nes.bus_mut().write(0xE001, 0);
+
+    // A12 rises once per scanline during the sprite fetches, the 11th rise is on scanline 10
+    nes.bus_mut().tick(1200);
+    assert_eq!(nes.ppu().scanline, 10);
+    assert!(!nes.mapper().irq_pending());
+
+    nes.bus_mut().tick(100);
+    assert_eq!(nes.ppu().scanline, 11);
+    assert!(nes.mapper().irq_pending());
+}
+
+/// Maps every nametable to the page picked by `page`, the way MMC5-style boards do
+struct PagedNametables {
+    page: usize,
+}
+
+impl SaveState for PagedNametables {
+    fn write_state(&self, state: &mut StateWriter) {
+        state.usize(self.page);
+    }
+    fn read_state(&mut self, state: &mut StateReader) -> Result<(), SaveStateError> {
+        self.page = state.usize()?;
+        Ok(())
+    }
+}
+
+// Has no memory of its own
+impl NESAccess for PagedNametables {}
+
+impl Mapper for PagedNametables {
+    fn cpu_read(&mut self, _addr: u16) -> u8 {
+        0
+    }
+    fn cpu_write(&mut self, _addr: u16, data: u8) {
+        self.page = data as usize;
+    }
+    fn ppu_read(&mut self, _addr: u16) -> u8 {
+        0
+    }
+    fn ppu_write(&mut self, _addr: u16, _data: u8) {}
+    fn chr_offset(&self, addr: u16) -> usize {
+        addr as usize
+    }
+    fn mirroring(&self) -> Mirroring {
+        Mirroring::MapperControlled
+    }
+    fn nametable_read(&mut self, addr: u16, vram: &[u8; 4096]) -> u8 {
+        self.nametable_peek(addr, vram)
+    }
+    fn nametable_peek(&self, addr: u16, vram: &[u8; 4096]) -> u8 {
+        vram[self.page * 0x0400 + (addr & 0x03FF) as usize]
+    }
+    fn nametable_write(&mut self, addr: u16, data: u8, vram: &mut [u8; 4096]) {
+        vram[self.page * 0x0400 + (addr & 0x03FF) as usize] = data;
+    }
+}
+
+#[test]
+fn test_mapper_controlled_nametables() {
+    let mapper: RcRef<BoxMapper> = Rc::new(RefCell::new(Box::new(PagedNametables { page: 1 })));
+    let mut ppu: PPU = PPU::new(
+        mapper.clone(),
+        Rc::new(RefCell::new(Breakpoints::default())),
+    );
+
+    ppu.raw_write_to_data(0x2C05, 0x66);
+    assert_eq!(ppu.vram[0x0405], 0x66);
+
+    mapper.borrow_mut().cpu_write(0x5105, 0);
+    assert_eq!(ppu.raw_read_data(0x2405), 0x00);
+    ppu.raw_write_to_data(0x2405, 0x77);
+    assert_eq!(ppu.vram[0x0005], 0x77);
+    assert_eq!(ppu.peek(0x2C05), 0x77);
+}
+
+/// Claims to control mirroring but leaves the nametable accesses to the defaults
+struct UnresolvedNametables;
+
+impl SaveState for UnresolvedNametables {
+    fn write_state(&self, _state: &mut StateWriter) {}
+    fn read_state(&mut self, _state: &mut StateReader) -> Result<(), SaveStateError> {
+        Ok(())
+    }
+}
+
+impl NESAccess for UnresolvedNametables {}
+
+impl Mapper for UnresolvedNametables {
+    fn cpu_read(&mut self, _addr: u16) -> u8 {
+        0
+    }
+    fn cpu_write(&mut self, _addr: u16, _data: u8) {}
+    fn ppu_read(&mut self, _addr: u16) -> u8 {
+        0
+    }
+    fn ppu_write(&mut self, _addr: u16, _data: u8) {}
+    fn chr_offset(&self, addr: u16) -> usize {
+        addr as usize
+    }
+    fn mirroring(&self) -> Mirroring {
+        Mirroring::MapperControlled
+    }
+}
+
+#[test]
+fn test_unresolved_mapper_controlled_nametables() {
+    assert_eq!(Mirroring::MapperControlled.vram_index(0x2C05), 0x0C05);
+
+    let mapper: RcRef<BoxMapper> = Rc::new(RefCell::new(Box::new(UnresolvedNametables)));
+    let mut ppu: PPU = PPU::new(
+        mapper.clone(),
+        Rc::new(RefCell::new(Breakpoints::default())),
+    );
+
+    // The defaults use all four nametables
+    ppu.raw_write_to_data(0x2C05, 0x66);
+    assert_eq!(ppu.vram[0x0C05], 0x66);
+    assert_eq!(ppu.peek(0x2C05), 0x66);
+    assert_eq!(ppu.peek(0x2405), 0x00);
+}
@@ -1,14 +1,25 @@
+#![allow(clippy::identity_op)]
+
+pub mod apu;
+pub mod breakpoint;
 pub mod bus;
 pub mod cpu;
 pub mod joypad;
+pub mod mapper;
+pub mod movie;
 pub mod ppu;
+pub mod rewind;
+pub mod save_state;
+pub mod symbols;
 pub mod trace;
 
 use crate::{
-    NES,
+    BoxMapper, NES, RcRef,
+    breakpoint::Breakpoints,
     cartridge::{
         CHR_ROM_PAGE_SIZE, Mirroring, PRG_RAM_PAGE_SIZE, PRG_ROM_PAGE_SIZE, ROM, ROMRegion,
     },
+    mapper::init_mapper,
     memory::Memory,
     ppu::PPU,
     prelude::*,
@@ -42,17 +53,19 @@ fn setup_nes() -> NES {
     setup_nes_with_rom(vec![])
 }
 
-fn empty_ppu(mirroring: Mirroring) -> PPU {
-    let memory: Memory = Memory {
-        cpu_vram: [0x00; 2048],
-        prg_rom: vec![],
-        prg_ram: vec![],
-        chr_mem: vec![0x00; CHR_ROM_PAGE_SIZE],
-        use_chr_ram: false,
-    };
-
-    let mut ppu: PPU = PPU::new(Rc::new(RefCell::new(memory)), mirroring);
+fn ppu_with_rom(rom: ROM) -> PPU {
+    let rom: RefCell<ROM> = RefCell::new(rom);
+    let memory: RcRef<Memory> = Rc::new(RefCell::new(Memory::new(rom.borrow())));
+    let mapper: RcRef<BoxMapper> = Rc::new(RefCell::new(init_mapper(rom.borrow(), memory)));
+
+    let mut ppu: PPU = PPU::new(mapper, Rc::new(RefCell::new(Breakpoints::default())));
     ppu.cycles = 9886;
 
     ppu
 }
+
+fn empty_ppu(mirroring: Mirroring) -> PPU {
+    let mut rom: ROM = test_rom(vec![]);
+    rom.screen_mirroring = mirroring;
+    ppu_with_rom(rom)
+}
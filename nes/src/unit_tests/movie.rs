@@ -0,0 +1,169 @@
+use super::*;
+use crate::{
+    input_device::{NESDeviceType, joypad::JoypadButton},
+    movie::{Movie, MovieCommand, MovieError, MovieFrame, MoviePlayer, set_joypads},
+};
+use std::cell::Cell;
+
+/// Reads controller 1 on every NMI and adds the pressed buttons up in $10. The PPU ignores
+/// writes right after power-on, so the main loop keeps turning NMIs on.
+fn joypad_nes() -> (NES, Rc<Cell<usize>>) {
+    #[rustfmt::skip]
+    let mut prg_rom: Vec<u8> = vec![
+        0xA9, 0x80,       // LDA #$80
+        0x8D, 0x00, 0x20, // STA $2000
+        0xA9, 0x1E,       // LDA #$1E
+        0x8D, 0x01, 0x20, // STA $2001
+        0x4C, 0x00, 0x80, // JMP $8000
+    ];
+    prg_rom.resize(0x0100, 0x00);
+    #[rustfmt::skip]
+    prg_rom.extend([
+        0xA9, 0x01,       // LDA #$01
+        0x8D, 0x16, 0x40, // STA $4016
+        0xA9, 0x00,       // LDA #$00
+        0x8D, 0x16, 0x40, // STA $4016
+        0xA2, 0x08,       // LDX #$08
+        0xAD, 0x16, 0x40, // LDA $4016
+        0x29, 0x01,       // AND #$01
+        0x18,             // CLC
+        0x65, 0x10,       // ADC $10
+        0x85, 0x10,       // STA $10
+        0xCA,             // DEX
+        0xD0, 0xF3,       // BNE $810C
+        0x40,             // RTI
+    ]);
+    prg_rom.resize(2 * PRG_ROM_PAGE_SIZE, 0x00);
+    prg_rom[0x7FFA] = 0x00;
+    prg_rom[0x7FFB] = 0x81;
+
+    let mut nes: NES = setup_nes_with_rom(prg_rom);
+    nes.connect_input_device(1, NESDeviceType::Joypad);
+
+    let frames: Rc<Cell<usize>> = Rc::new(Cell::new(0));
+    let cb_frames: Rc<Cell<usize>> = frames.clone();
+    nes.render_callback(move |_, _, _| cb_frames.set(cb_frames.get() + 1));
+    (nes, frames)
+}
+
+/// Calls `on_frame` at the start of each of the next `count` frames
+fn run_frames(
+    nes: &mut NES,
+    frames: &Rc<Cell<usize>>,
+    count: usize,
+    mut on_frame: impl FnMut(&mut NES, usize),
+) {
+    for frame in 0..count {
+        on_frame(nes, frame);
+        let start: usize = frames.get();
+        while frames.get() == start {
+            assert!(nes.step(|_| {}));
+        }
+    }
+}
+
+fn test_movie() -> Movie {
+    let mut movie: Movie = Movie::new(&test_rom(vec![]), "test.nes", None);
+    movie.joypad2 = true;
+    movie.comments.push("author nesmur".to_string());
+    movie.frames = vec![
+        MovieFrame::new([JoypadButton::empty(), JoypadButton::empty()]),
+        MovieFrame::new([
+            JoypadButton::RIGHT | JoypadButton::BUTTON_A,
+            JoypadButton::START,
+        ]),
+        MovieFrame {
+            commands: MovieCommand::SOFT_RESET,
+            joypads: [JoypadButton::all(), JoypadButton::LEFT],
+        },
+    ];
+    movie
+}
+
+#[test]
+fn test_fm2_round_trip() {
+    let movie: Movie = test_movie();
+    let text: String = movie.to_fm2();
+    assert!(text.contains("|0|R......A|....T...||\n"));
+    assert!(text.contains("|1|RLDUTSBA|.L......||\n"));
+    assert_eq!(Movie::from_fm2(&text), Ok(movie.clone()));
+
+    let mut from_state: Movie = movie;
+    from_state.start_state = Some((0..=255u8).collect());
+    assert_eq!(Movie::from_fm2(&from_state.to_fm2()), Ok(from_state));
+}
+
+#[test]
+fn test_fm2_import() {
+    let text: &str = "version 3\r\n\
+        emuVersion 20604\r\n\
+        romFilename Some Game\r\n\
+        romChecksum 0x000102030405060708090a0b0c0d0e0f\r\n\
+        guid 452DE2C3-EF43-2FA9-77AC-0677FC51543B\r\n\
+        port0 1\r\n\
+        port1 0\r\n\
+        port2 0\r\n\
+        |0|........|||\r\n\
+        |0|...U   A|||\r\n";
+    let movie: Movie = Movie::from_fm2(text).unwrap();
+
+    assert_eq!(movie.rom_filename, "Some Game");
+    assert_eq!(movie.rom_checksum, std::array::from_fn(|i: usize| i as u8));
+    assert!(!movie.joypad2);
+    assert_eq!(
+        movie.frames[1].joypads,
+        [
+            JoypadButton::UP | JoypadButton::BUTTON_A,
+            JoypadButton::empty()
+        ]
+    );
+}
+
+#[test]
+fn test_fm2_rejects_unsupported() {
+    assert_eq!(
+        Movie::from_fm2("version 2\n"),
+        Err(MovieError::UnsupportedVersion("2".to_string()))
+    );
+    assert_eq!(
+        Movie::from_fm2("port1 2\n"),
+        Err(MovieError::UnsupportedDevice("Zapper"))
+    );
+    assert_eq!(
+        Movie::from_fm2("savestate base64:AAAA\n"),
+        Err(MovieError::FCEUXSavestate)
+    );
+    assert_eq!(
+        Movie::from_fm2("port0 1\n|0|...|||\n"),
+        Err(MovieError::InvalidFrame(2))
+    );
+}
+
+#[test]
+fn test_movie_playback_is_deterministic() {
+    let inputs: [JoypadButton; 4] = [
+        JoypadButton::empty(),
+        JoypadButton::BUTTON_A,
+        JoypadButton::UP | JoypadButton::START,
+        JoypadButton::all(),
+    ];
+
+    let (mut nes, frames) = joypad_nes();
+    let power_on: Vec<u8> = nes.save_state();
+    let mut movie: Movie = Movie::new(&nes.rom(), "joypad.nes", None);
+    run_frames(&mut nes, &frames, 60, |nes: &mut NES, frame: usize| {
+        let joypads: [JoypadButton; 2] = [inputs[frame * 7 % 4], JoypadButton::empty()];
+        movie.frames.push(MovieFrame::new(joypads));
+        set_joypads(nes, joypads);
+    });
+    let recorded: Vec<u8> = nes.save_state();
+    assert_ne!(nes.bus_mut().read(0x0010), 0);
+
+    let movie: Movie = Movie::from_fm2(&movie.to_fm2()).unwrap();
+    let mut player: MoviePlayer = MoviePlayer::start(&mut nes, movie, &power_on).unwrap();
+    run_frames(&mut nes, &frames, 60, |nes: &mut NES, _| {
+        player.next_frame(nes)
+    });
+    assert!(player.is_finished());
+    assert!(nes.save_state() == recorded);
+}
@@ -21,7 +21,7 @@ fn test_step() {
     ppu.step();
     assert_eq!(ppu.dot, 0);
     assert_eq!(ppu.scanline, 0);
-    assert_eq!(ppu.odd_frame, true);
+    assert!(ppu.odd_frame);
 }
 
 #[test]
@@ -133,6 +133,43 @@ fn test_vram_vertical_mirror() {
     assert_eq!(ppu.read_data(), 0x77); // Read from B
 }
 
+// Single-Screen B: https://www.nesdev.org/wiki/Mirroring#Single-Screen
+//   [0x2000 b ] [0x2400 b ]
+//   [0x2800 b ] [0x2C00 b ]
+#[test]
+fn test_vram_single_screen_mirror() {
+    let mut ppu: PPU = empty_ppu(Mirroring::SingleScreenB);
+
+    ppu.write_to_ppu_addr(0x20);
+    ppu.write_to_ppu_addr(0x05);
+    ppu.write_to_data(0x66); // Write to b
+    ppu.write_to_ppu_addr(0x2C);
+    ppu.write_to_ppu_addr(0x05);
+    ppu.read_data(); // Load into buffer
+
+    assert_eq!(ppu.read_data(), 0x66); // Read from b
+    assert_eq!(ppu.vram[0x0405], 0x66);
+}
+
+// Four-Screen: https://www.nesdev.org/wiki/Mirroring#4-Screen
+//   [0x2000 A ] [0x2400 B ]
+//   [0x2800 C ] [0x2C00 D ]
+#[test]
+fn test_vram_four_screen() {
+    let mut ppu: PPU = empty_ppu(Mirroring::FourScreen);
+
+    for (i, hi) in [0x20, 0x24, 0x28, 0x2C].into_iter().enumerate() {
+        ppu.write_to_ppu_addr(hi);
+        ppu.write_to_ppu_addr(0x05);
+        ppu.write_to_data(i as u8);
+    }
+
+    assert_eq!(ppu.vram[0x0005], 0);
+    assert_eq!(ppu.vram[0x0405], 1);
+    assert_eq!(ppu.vram[0x0805], 2);
+    assert_eq!(ppu.vram[0x0C05], 3);
+}
+
 #[test]
 fn test_read_status_resets_latch() {
     let mut ppu: PPU = empty_ppu(Mirroring::Horizontal);
@@ -5,18 +5,11 @@ use crate::{
 };
 
 fn build_ppu(mirroring: Mirroring) -> PPU {
-    let memory: Memory = Memory {
-        cpu_vram: [0x00; 2048],
-        prg_rom: vec![0x00; PRG_ROM_PAGE_SIZE * 2],
-        prg_ram: vec![0x00; PRG_RAM_PAGE_SIZE],
-        chr_mem: vec![0x00; CHR_ROM_PAGE_SIZE],
-        use_chr_ram: true,
-    };
-
-    let mut ppu: PPU = PPU::new(Rc::new(RefCell::new(memory)), mirroring);
-    ppu.cycles = 9886;
-
-    ppu
+    let mut rom: ROM = test_rom(vec![]);
+    rom.screen_mirroring = mirroring;
+    rom.chr_rom = vec![];
+    rom.chr_ram_size = CHR_ROM_PAGE_SIZE;
+    ppu_with_rom(rom)
 }
 
 fn quick_setup() -> (PPU, Renderer) {
@@ -50,12 +43,12 @@ fn test_sprite_overflow() {
     }
     renderer.eval_sprites(&mut ppu);
     assert_eq!(renderer.secondary_oam.len(), 8);
-    assert_eq!(ppu.status.is_sprite_overflow(), false);
+    assert!(!ppu.status.is_sprite_overflow());
 
     ppu.oam_data[8 * 4] = 0x0A;
     renderer.eval_sprites(&mut ppu);
     assert_eq!(renderer.secondary_oam.len(), 8);
-    assert_eq!(ppu.status.is_sprite_overflow(), true);
+    assert!(ppu.status.is_sprite_overflow());
 }
 
 #[test]
@@ -220,7 +213,7 @@ fn test_render_pixel_transparent_sprite_front() {
         (0x00, false, false)
     );
     assert_eq!(renderer.render_pixel(0, 0, &mut ppu), Some(0x03));
-    assert_eq!(ppu.status.is_sprite_0_hit(), false);
+    assert!(!ppu.status.is_sprite_0_hit());
 }
 
 #[test]
@@ -242,7 +235,7 @@ fn test_render_pixel_opaque_sprite_front() {
         (0x11, false, true)
     );
     assert_eq!(renderer.render_pixel(0, 0, &mut ppu), Some(0x11));
-    assert_eq!(ppu.status.is_sprite_0_hit(), true);
+    assert!(ppu.status.is_sprite_0_hit());
 }
 
 #[test]
@@ -264,5 +257,5 @@ fn test_render_pixel_opaque_sprite_behind() {
         (0b0001_0001, true, true)
     );
     assert_eq!(renderer.render_pixel(0, 0, &mut ppu), Some(0x03));
-    assert_eq!(ppu.status.is_sprite_0_hit(), true);
+    assert!(ppu.status.is_sprite_0_hit());
 }
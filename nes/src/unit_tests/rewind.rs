@@ -0,0 +1,143 @@
+use super::*;
+use crate::{
+    ppu::renderer::RGB,
+    rewind::{RewindBuffer, decode_delta, encode_delta},
+};
+
+#[test]
+fn test_delta_round_trip() {
+    let newer: Vec<u8> = (0..1000u32).map(|i: u32| i as u8).collect();
+    let mut older: Vec<u8> = newer.clone();
+    older[0] = 0xFF;
+    older[10] ^= 0x01;
+    older[12] ^= 0x01;
+    older[999] = 0x00;
+
+    let delta: Vec<u8> = encode_delta(&newer, &older);
+    assert!(delta.len() < 32);
+    assert_eq!(decode_delta(&newer, &delta), older);
+
+    assert_eq!(decode_delta(&newer, &encode_delta(&newer, &newer)), newer);
+}
+
+#[test]
+fn test_delta_different_lengths() {
+    let newer: Vec<u8> = vec![1, 2, 3, 4, 5, 6];
+    let shorter: Vec<u8> = vec![1, 2, 3];
+    let longer: Vec<u8> = vec![1, 2, 3, 4, 5, 6, 0, 0, 7, 8];
+
+    assert_eq!(
+        decode_delta(&newer, &encode_delta(&newer, &shorter)),
+        shorter
+    );
+    assert_eq!(decode_delta(&newer, &encode_delta(&newer, &longer)), longer);
+    assert_eq!(decode_delta(&newer, &encode_delta(&newer, &[])), vec![]);
+}
+
+#[test]
+fn test_delta_long_runs() {
+    let newer: Vec<u8> = vec![0x00; 200_000];
+    let mut older: Vec<u8> = vec![0xAA; 200_000];
+    older[100_000] = 0x00;
+
+    assert_eq!(decode_delta(&newer, &encode_delta(&newer, &older)), older);
+    assert_eq!(decode_delta(&older, &encode_delta(&older, &newer)), newer);
+}
+
+#[test]
+fn test_rewind_buffer_pops_newest_first() {
+    let mut rewind: RewindBuffer = RewindBuffer::new(usize::MAX);
+    for i in 0..10u8 {
+        rewind.push(vec![i; 100]);
+    }
+    assert_eq!(rewind.len(), 10);
+
+    for i in (0..10u8).rev() {
+        assert_eq!(rewind.pop(), Some(vec![i; 100]));
+    }
+    assert!(rewind.is_empty());
+    assert_eq!(rewind.size(), 0);
+    assert_eq!(rewind.pop(), None);
+}
+
+#[test]
+fn test_rewind_buffer_drops_oldest() {
+    let mut rewind: RewindBuffer = RewindBuffer::new(1000);
+    for i in 0..100u8 {
+        let mut state: Vec<u8> = vec![0x00; 500];
+        state[i as usize] = i;
+        rewind.push(state);
+        assert!(rewind.size() <= 1000);
+    }
+    assert!(rewind.len() > 2);
+    assert!(rewind.len() < 100);
+
+    let mut expected: u8 = 99;
+    while let Some(state) = rewind.pop() {
+        assert_eq!(state[expected as usize], expected);
+        expected -= 1;
+    }
+}
+
+#[test]
+fn test_rewind_save_states() {
+    let mut nes: NES = setup_nes();
+    let mut rewind: RewindBuffer = RewindBuffer::new(usize::MAX);
+
+    let mut states: Vec<Vec<u8>> = Vec::new();
+    for _ in 0..5 {
+        for _ in 0..2_000 {
+            nes.step(|_| {});
+        }
+        states.push(nes.save_state());
+        rewind.push(nes.save_state());
+    }
+
+    while let Some(state) = rewind.pop() {
+        assert!(state == states.pop().unwrap());
+        assert_eq!(nes.load_state(&state), Ok(()));
+    }
+}
+
+#[test]
+fn test_rewind_shows_rewound_frame() {
+    // Changes the backdrop color every vblank, so each frame is a different color
+    #[rustfmt::skip]
+    let mut nes: NES = setup_nes_with_rom(vec![
+        0xA9, 0x08,       // LDA #$08
+        0x8D, 0x01, 0x20, // STA $2001
+        0x2C, 0x02, 0x20, // BIT $2002
+        0x10, 0xFB,       // BPL $8005
+        0xE8,             // INX
+        0xA9, 0x3F,       // LDA #$3F
+        0x8D, 0x06, 0x20, // STA $2006
+        0xA9, 0x00,       // LDA #$00
+        0x8D, 0x06, 0x20, // STA $2006
+        0x8A,             // TXA
+        0x8D, 0x07, 0x20, // STA $2007
+        0x4C, 0x05, 0x80, // JMP $8005
+    ]);
+    let backdrop = |nes: &NES| -> (u8, u8, u8) {
+        let pixel: RGB = nes.renderer().pixels[0];
+        (pixel.0, pixel.1, pixel.2)
+    };
+    let mut rewind: RewindBuffer = RewindBuffer::new(usize::MAX);
+
+    let mut frames: Vec<(u8, u8, u8)> = Vec::new();
+    for _ in 0..6 {
+        assert!(nes.step_frame(|_| {}));
+        rewind.push(nes.save_state());
+        frames.push(backdrop(&nes));
+    }
+    assert!(
+        frames
+            .windows(2)
+            .skip(1)
+            .all(|pair: &[(u8, u8, u8)]| pair[0] != pair[1])
+    );
+
+    while let Some(state) = rewind.pop() {
+        assert_eq!(nes.load_state(&state), Ok(()));
+        assert_eq!(backdrop(&nes), frames.pop().unwrap());
+    }
+}
@@ -0,0 +1,134 @@
+use super::*;
+use crate::{ppu::renderer::RGB, save_state::SaveStateError};
+
+/// Turns on rendering and NMIs, then keeps writing to RAM and VRAM, so every component has
+/// some state that changes
+fn busy_nes() -> NES {
+    #[rustfmt::skip]
+    let mut prg_rom: Vec<u8> = vec![
+        0xA9, 0x1E,       // LDA #$1E
+        0x8D, 0x01, 0x20, // STA $2001
+        0xA9, 0x80,       // LDA #$80
+        0x8D, 0x00, 0x20, // STA $2000
+        0xA2, 0x00,       // LDX #$00
+        0xE8,             // INX
+        0x86, 0x10,       // STX $10
+        0x8A,             // TXA
+        0x8D, 0x07, 0x20, // STA $2007
+        0x4C, 0x0C, 0x80, // JMP $800C
+    ];
+    prg_rom.resize(2 * PRG_ROM_PAGE_SIZE, 0x00);
+    prg_rom[0x0100] = 0x40; // RTI
+    prg_rom[0x7FFA] = 0x00;
+    prg_rom[0x7FFB] = 0x81;
+
+    let mut nes: NES = setup_nes_with_rom(prg_rom);
+    nes.connect_input_device(1, crate::input_device::NESDeviceType::Joypad);
+    nes
+}
+
+fn run_steps(nes: &mut NES, steps: usize) {
+    for _ in 0..steps {
+        assert!(nes.step(|_| {}));
+    }
+}
+
+#[test]
+fn test_save_state_resumes_identically() {
+    let mut nes: NES = busy_nes();
+    run_steps(&mut nes, 20_000);
+    let state: Vec<u8> = nes.save_state();
+
+    run_steps(&mut nes, 30_000);
+    let expected: Vec<u8> = nes.save_state();
+    let expected_pc: u16 = nes.cpu.program_counter;
+    let expected_cycles: usize = nes.ppu().cycles;
+
+    assert_eq!(nes.load_state(&state), Ok(()));
+    assert_eq!(nes.save_state(), state);
+
+    run_steps(&mut nes, 30_000);
+    assert_eq!(nes.cpu.program_counter, expected_pc);
+    assert_eq!(nes.ppu().cycles, expected_cycles);
+    assert!(nes.save_state() == expected);
+}
+
+#[test]
+fn test_load_state_restores_frame_buffer() {
+    let mut nes: NES = busy_nes();
+    run_steps(&mut nes, 20_000);
+    let state: Vec<u8> = nes.save_state();
+    let rgb = |pixels: &[RGB]| -> Vec<(u8, u8, u8)> {
+        pixels
+            .iter()
+            .map(|pixel: &RGB| (pixel.0, pixel.1, pixel.2))
+            .collect()
+    };
+    let expected: Vec<(u8, u8, u8)> = rgb(&nes.renderer().pixels);
+
+    nes.renderer_mut().pixels.fill(RGB(0x12, 0x34, 0x56));
+    assert_eq!(nes.load_state(&state), Ok(()));
+    assert!(rgb(&nes.renderer().pixels) == expected);
+}
+
+#[test]
+fn test_load_state_rejects_other_rom() {
+    let state: Vec<u8> = busy_nes().save_state();
+
+    let mut nes: NES = setup_nes();
+    let before: Vec<u8> = nes.save_state();
+    assert_eq!(nes.load_state(&state), Err(SaveStateError::ROMMismatch));
+    assert!(nes.save_state() == before);
+}
+
+#[test]
+fn test_load_state_rejects_bad_data() {
+    let mut nes: NES = busy_nes();
+    let state: Vec<u8> = nes.save_state();
+    run_steps(&mut nes, 5_000);
+    let before: Vec<u8> = nes.save_state();
+
+    assert_eq!(
+        nes.load_state(b"NOTASTATE"),
+        Err(SaveStateError::InvalidMagic)
+    );
+    assert_eq!(
+        nes.load_state(&state[..state.len() - 1]),
+        Err(SaveStateError::UnexpectedEnd)
+    );
+
+    // The joypad chunk is last and NROM's mapper chunk is empty, so this drops both of them after
+    // everything else was already loaded
+    let truncated: &[u8] = &state[..state.len() - (8 + 2) - 8];
+    assert_eq!(
+        nes.load_state(truncated),
+        Err(SaveStateError::MissingChunk(*b"MAPR"))
+    );
+    assert!(nes.save_state() == before);
+}
+
+fn mmc3_rom() -> ROM {
+    let mut prg_rom: Vec<u8> = (0..16u8).flat_map(|bank: u8| vec![bank; 0x2000]).collect();
+    prg_rom[15 * 0x2000 + 0x1FFC] = 0x00;
+    prg_rom[15 * 0x2000 + 0x1FFD] = 0x80;
+
+    let mut rom: ROM = test_rom(vec![]);
+    rom.mapper = 4;
+    rom.prg_rom = prg_rom;
+    rom
+}
+
+#[test]
+fn test_save_state_mapper_registers() {
+    let nes: NES = NES::new(mmc3_rom());
+    nes.bus_mut().write(0x8000, 6);
+    nes.bus_mut().write(0x8001, 3);
+    nes.bus_mut().write(0xA001, 0b1000_0000);
+    nes.bus_mut().write(0x6000, 0x42);
+    let state: Vec<u8> = nes.save_state();
+
+    let mut reloaded: NES = NES::new(mmc3_rom());
+    assert_eq!(reloaded.load_state(&state), Ok(()));
+    assert_eq!(reloaded.bus_mut().read(0x8000), 3);
+    assert_eq!(reloaded.bus_mut().read(0x6000), 0x42);
+}
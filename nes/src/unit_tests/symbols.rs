@@ -0,0 +1,156 @@
+use super::*;
+use crate::symbols::{SymbolError, Symbols};
+use std::path::PathBuf;
+
+const EXAMPLE_TOML: &str = include_str!("../../../docs/future_dev/symbols_example.toml");
+
+#[test]
+fn test_toml_symbols() {
+    let symbols: Symbols = Symbols::from_toml(EXAMPLE_TOML).unwrap();
+    // AccuracyCoin is 32 KiB of NROM, so everything is in bank 0
+    assert_eq!(symbols.label(Some(0), 0xD925), Some("TEST_BFlag"));
+    assert_eq!(symbols.label(Some(0), 0xA038), Some("TEST_Fail"));
+    // Another bank mapped at the same address
+    assert_eq!(symbols.label(Some(1), 0xD925), None);
+    assert_eq!(symbols.label(None, 0xD925), None);
+    assert_eq!(
+        symbols.constant(Some(0), 0xD936),
+        Some("LOW(TEST_BFlag_BRK)")
+    );
+    assert_eq!(symbols.len(), 9);
+
+    assert!(matches!(
+        Symbols::from_toml("[bank.0.label]\nZZZZ = \"Nope\""),
+        Err(SymbolError::InvalidValue(key)) if key == "bank.0.label.ZZZZ"
+    ));
+    assert!(matches!(
+        Symbols::from_toml("bank = ["),
+        Err(SymbolError::Toml(_))
+    ));
+}
+
+#[test]
+fn test_nl_symbols() {
+    let ram: Symbols = Symbols::from_nl(
+        "$0010#counter#Frames since reset\n\n$0300/20#buffer#\n",
+        None,
+        0x4000,
+    )
+    .unwrap();
+    // RAM isn't banked
+    assert_eq!(ram.label(None, 0x0010), Some("counter"));
+    assert_eq!(ram.label(Some(3), 0x0010), Some("counter"));
+    assert_eq!(ram.comment(None, 0x0010), Some("Frames since reset"));
+    assert_eq!(ram.label(None, 0x0300), Some("buffer"));
+    assert_eq!(ram.comment(None, 0x0300), None);
+
+    let rom: Symbols = Symbols::from_nl("$C000#Reset#", Some(1), 0x4000).unwrap();
+    assert_eq!(rom.label(Some(1), 0xC000), Some("Reset"));
+    assert_eq!(rom.label(Some(0), 0xC000), None);
+
+    // FCEUX's 16 KiB pages are converted to the mapper's banks
+    let rom: Symbols = Symbols::from_nl("$C000#Reset#", Some(3), 0x8000).unwrap();
+    assert_eq!(rom.label(Some(1), 0xC000), Some("Reset"));
+    let rom: Symbols = Symbols::from_nl("$E000#Reset#", Some(3), 0x2000).unwrap();
+    assert_eq!(rom.label(Some(7), 0xE000), Some("Reset"));
+
+    assert!(matches!(
+        Symbols::from_nl("$0010#counter#\nC000#Reset#", None, 0x4000),
+        Err(SymbolError::InvalidLine(2))
+    ));
+}
+
+#[test]
+fn test_dbg_symbols() {
+    let dbg: &str = "version\tmajor=2,minor=0
+seg\tid=0,name=\"ZEROPAGE\",start=0x000000,size=0x0010,addrsize=zeropage,type=rw
+seg\tid=1,name=\"CODE\",start=0x008000,size=0x4000,addrsize=absolute,type=ro,oname=\"test.nes\",ooffs=16
+seg\tid=2,name=\"TESTS\",start=0x00C000,size=0x4000,addrsize=absolute,type=ro,oname=\"test.nes\",ooffs=16400
+sym\tid=0,name=\"counter\",addrsize=zeropage,scope=0,def=0,ref=3,val=0x10,seg=0,type=lab
+sym\tid=1,name=\"TEST_Fail\",addrsize=absolute,scope=0,def=1,val=0xA038,seg=1,type=lab
+sym\tid=2,name=\"TEST_BFlag\",addrsize=absolute,scope=0,def=2,val=0xD925,seg=2,type=lab
+sym\tid=3,name=\"SIZE\",addrsize=zeropage,scope=0,def=3,val=0x4,type=equ
+";
+    let symbols: Symbols = Symbols::from_dbg(dbg, 0x4000).unwrap();
+    assert_eq!(symbols.label(None, 0x0010), Some("counter"));
+    assert_eq!(symbols.label(Some(0), 0xA038), Some("TEST_Fail"));
+    assert_eq!(symbols.label(Some(1), 0xD925), Some("TEST_BFlag"));
+    assert_eq!(symbols.label(Some(0), 0xD925), None);
+    // Equates aren't addresses
+    assert_eq!(symbols.label(None, 0x0004), None);
+    assert_eq!(symbols.len(), 3);
+
+    // Both segments are in the same 32 KiB bank
+    let symbols: Symbols = Symbols::from_dbg(dbg, 0x8000).unwrap();
+    assert_eq!(symbols.label(Some(0), 0xA038), Some("TEST_Fail"));
+    assert_eq!(symbols.label(Some(0), 0xD925), Some("TEST_BFlag"));
+}
+
+#[test]
+fn test_load_symbols_for_rom() {
+    let dir: PathBuf = std::env::temp_dir().join(format!("nesmur_symbols_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("game.toml"),
+        "multifile = true\nbanks_covered = [1]\n[bank.0.label]\n8000 = \"Start\"",
+    )
+    .unwrap();
+    std::fs::write(dir.join("game.1.toml"), "[bank.1.label]\nC000 = \"Tests\"").unwrap();
+    std::fs::write(dir.join("game.nes.ram.nl"), "$0010#counter#").unwrap();
+    std::fs::write(dir.join("game.nes.1.nl"), "$C010#Loop#").unwrap();
+    std::fs::write(dir.join("other.nes.0.nl"), "$8000#Other#").unwrap();
+
+    let symbols: Result<Symbols, SymbolError> =
+        Symbols::load_for_rom(&dir.join("game.nes"), 0x4000);
+    std::fs::remove_dir_all(&dir).unwrap();
+    let symbols: Symbols = symbols.unwrap();
+
+    assert_eq!(symbols.label(Some(0), 0x8000), Some("Start"));
+    assert_eq!(symbols.label(Some(1), 0xC000), Some("Tests"));
+    assert_eq!(symbols.label(None, 0x0010), Some("counter"));
+    assert_eq!(symbols.label(Some(1), 0xC010), Some("Loop"));
+    assert_eq!(symbols.len(), 4);
+}
+
+#[test]
+fn test_disassemble_with_symbols() {
+    #[rustfmt::skip]
+    let mut prg_rom: Vec<u8> = vec![
+        0x20, 0x25, 0xD9, // JSR $D925
+        0xA9, 0x41,       // LDA #$41
+        0xB5, 0x10,       // LDA $10,X
+        0xD0, 0xF7,       // BNE $8000
+    ];
+    prg_rom.resize(0x5925, 0x00);
+    prg_rom.extend([0x60]); // RTS
+    let nes: NES = setup_nes_with_rom(prg_rom);
+
+    let mut symbols: Symbols = Symbols::from_toml(EXAMPLE_TOML).unwrap();
+    symbols.add_label(Some(0), 0x8000, "Reset");
+    symbols.add_comment(Some(0), 0x8000, "Runs the test");
+    symbols.add_constant(Some(0), 0x8003, "LOW(Letters)");
+    symbols.add_label(None, 0x0010, "counter");
+    nes.bus_mut().symbols = symbols;
+
+    let mut addr: u16 = 0x8000;
+    let mut lines: Vec<String> = vec![];
+    for _ in 0..4 {
+        let disassembly: tools::Disassembly = tools::disassemble(&nes.cpu, addr);
+        lines.push(disassembly.to_string());
+        addr = disassembly.next_addr();
+    }
+    assert_eq!(
+        lines,
+        [
+            "8000  20 25 D9  JSR TEST_BFlag",
+            "8003  A9 41     LDA #LOW(Letters)",
+            "8005  B5 10     LDA counter,X",
+            "8007  D0 F7     BNE Reset",
+        ]
+    );
+
+    let first: tools::Disassembly = tools::disassemble(&nes.cpu, 0x8000);
+    assert_eq!(first.label.as_deref(), Some("Reset"));
+    assert_eq!(first.comment.as_deref(), Some("Runs the test"));
+    assert!(tools::trace(&nes.cpu).starts_with("8000  20 25 D9  JSR TEST_BFlag "));
+}
@@ -71,3 +71,44 @@ fn test_format_mem_access() {
         result[0]
     );
 }
+
+#[test]
+fn test_disassemble() {
+    let nes: NES = setup_nes();
+
+    let mut bus: RefMut<'_, Bus> = nes.bus_mut();
+    // LDA $0200,X
+    // BNE $0062
+    // ASL A
+    // LAX ($10),Y
+    // JMP ($1234)
+    for (i, byte) in [
+        0xBD, 0x00, 0x02, 0xD0, 0xF9, 0x0A, 0xB3, 0x10, 0x6C, 0x34, 0x12,
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        bus.write(0x0064 + i as u16, byte);
+    }
+    drop(bus);
+
+    let mut addr: u16 = 0x0064;
+    let mut lines: Vec<String> = vec![];
+    for _ in 0..5 {
+        let disassembly: tools::Disassembly = tools::disassemble(&nes.cpu, addr);
+        lines.push(disassembly.to_string());
+        addr = disassembly.next_addr();
+    }
+
+    assert_eq!(
+        lines,
+        [
+            "0064  BD 00 02  LDA $0200,X",
+            "0067  D0 F9     BNE $0062",
+            "0069  0A        ASL A",
+            "006A  B3 10    *LAX ($10),Y",
+            "006C  6C 34 12  JMP ($1234)",
+        ]
+    );
+    assert_eq!(addr, 0x006F);
+}
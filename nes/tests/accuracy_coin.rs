@@ -0,0 +1,207 @@
+mod common;
+
+use nes::{
+    NES,
+    input_device::{NESDeviceType, joypad::JoypadButton},
+    movie,
+    tools::NESAccess,
+};
+use std::path::Path;
+
+const ROM_PATH: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../third_party/roms/AccuracyCoin.nes"
+);
+const RESULTS_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../AccuracyCoin-Results.txt");
+
+// https://github.com/100thCoin/AccuracyCoin
+// The result table's address and encoding below still need checking against
+// `AccuracyCoin.asm`. If they're wrong, results never leave NOT_RUN, or decode as unknown
+// bytes, and the test fails instead of passing.
+/// One result byte per test, in the same order as the menu and `AccuracyCoin-Results.txt`
+const RESULTS_ADDR: u16 = 0x0400;
+const RESULT_NOT_RUN: u8 = 0x00;
+const RESULT_PASS: u8 = 0x01;
+/// The error code is kept in the upper 6 bits
+const RESULT_FAIL: u8 = 0x02;
+
+/// Frames to wait for the menu to come up before pressing anything
+const BOOT_FRAMES: usize = 60;
+const PRESS_FRAMES: usize = 2;
+/// Running every test takes a bit under a minute on hardware
+const MAX_FRAMES: usize = 60 * 60 * 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TestResult {
+    NotRun,
+    Pass,
+    Fail(u8),
+}
+
+impl TestResult {
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            RESULT_NOT_RUN => Some(TestResult::NotRun),
+            RESULT_PASS => Some(TestResult::Pass),
+            _ if byte & 0b11 == RESULT_FAIL => Some(TestResult::Fail(byte >> 2)),
+            _ => None,
+        }
+    }
+
+    fn parse(text: &str) -> Self {
+        match text.split_whitespace().collect::<Vec<&str>>().as_slice() {
+            ["PASS"] => TestResult::Pass,
+            ["FAIL", code] => TestResult::Fail(code.parse().unwrap_or(0)),
+            _ => TestResult::NotRun,
+        }
+    }
+
+    fn to_text(self) -> String {
+        match self {
+            TestResult::NotRun => "_".to_string(),
+            TestResult::Pass => "PASS".to_string(),
+            TestResult::Fail(code) => format!("FAIL {}", code),
+        }
+    }
+}
+
+/// Line index, name and recorded result of every entry in the results file
+fn parse_results(text: &str) -> Vec<(usize, String, TestResult)> {
+    text.lines()
+        .enumerate()
+        .filter_map(|(index, line): (usize, &str)| {
+            let (name, result): (&str, &str) = line.split_once(':')?;
+            Some((index, name.trim().to_string(), TestResult::parse(result)))
+        })
+        .collect()
+}
+
+/// Holds `buttons` on controller 1 for a few frames, then lets go
+fn press(nes: &mut NES, buttons: JoypadButton) {
+    movie::set_joypads(nes, [buttons, JoypadButton::empty()]);
+    for _ in 0..PRESS_FRAMES {
+        assert!(nes.step_frame(|_| {}));
+    }
+    movie::set_joypads(nes, [JoypadButton::empty(); 2]);
+}
+
+fn read_results(nes: &NES, count: usize) -> Vec<u8> {
+    (0..count)
+        .map(|i: usize| nes.bus().peek(RESULTS_ADDR + i as u16))
+        .collect()
+}
+
+/// Compares every result against `AccuracyCoin-Results.txt` and fails if a test that passed
+/// before doesn't anymore. Run with `NESMUR_BLESS=1` to write the new results to the file.
+#[test]
+#[ignore = "needs third_party/roms/AccuracyCoin.nes, run with `--ignored`"]
+fn accuracy_coin() {
+    assert!(
+        Path::new(ROM_PATH).exists(),
+        "{} wasn't found, AccuracyCoin can be downloaded from \
+         https://github.com/100thCoin/AccuracyCoin",
+        ROM_PATH
+    );
+    let bless: bool = std::env::var_os("NESMUR_BLESS").is_some();
+
+    let previous_text: String = std::fs::read_to_string(RESULTS_PATH).unwrap();
+    let previous: Vec<(usize, String, TestResult)> = parse_results(&previous_text);
+    assert!(
+        bless
+            || previous
+                .iter()
+                .any(|(_, _, result): &(usize, String, TestResult)| *result != TestResult::NotRun),
+        "{} has no recorded results to compare against, run with `NESMUR_BLESS=1` once to \
+         record them",
+        RESULTS_PATH
+    );
+
+    let mut nes: NES = common::setup_nes_from_path(Path::new(ROM_PATH));
+    nes.connect_input_device(1, NESDeviceType::Joypad);
+    for _ in 0..BOOT_FRAMES {
+        assert!(nes.step_frame(|_| {}));
+    }
+    // Start on the main menu is expected to run every test, which the NOT_RUN check below
+    // catches if it doesn't
+    press(&mut nes, JoypadButton::START);
+
+    let mut frames: usize = 0;
+    while frames < MAX_FRAMES && read_results(&nes, previous.len()).contains(&RESULT_NOT_RUN) {
+        assert!(nes.step_frame(|_| {}), "The CPU stopped");
+        frames += 1;
+    }
+
+    let results: Vec<TestResult> = read_results(&nes, previous.len())
+        .into_iter()
+        .enumerate()
+        .map(|(i, byte): (usize, u8)| {
+            TestResult::from_byte(byte).unwrap_or_else(|| {
+                panic!(
+                    "Unknown result {:#04X} for `{}` at ${:04X}, does `RESULTS_ADDR` match this \
+                     version of AccuracyCoin?",
+                    byte,
+                    previous[i].1,
+                    RESULTS_ADDR + i as u16
+                )
+            })
+        })
+        .collect();
+    let not_run: Vec<&str> = previous
+        .iter()
+        .zip(results.iter())
+        .filter_map(
+            |((_, name, _), result): (&(usize, String, TestResult), &TestResult)| {
+                (*result == TestResult::NotRun).then_some(name.as_str())
+            },
+        )
+        .collect();
+    assert!(
+        not_run.is_empty(),
+        "{} tests still hadn't run after {} frames:\n{}",
+        not_run.len(),
+        frames,
+        not_run.join("\n")
+    );
+
+    let mut lines: Vec<String> = previous_text.lines().map(str::to_string).collect();
+    let mut regressions: Vec<&str> = Vec::new();
+    let mut changed: usize = 0;
+    for ((line, name, before), after) in previous.iter().zip(results.iter()) {
+        let (label, _): (&str, &str) = lines[*line].split_once(':').unwrap();
+        lines[*line] = format!("{}:   {}", label, after.to_text());
+        if before != after {
+            changed += 1;
+        }
+        if *before == TestResult::Pass && *after != TestResult::Pass {
+            regressions.push(name);
+        }
+    }
+    if bless {
+        let mut text: String = lines.join("\n");
+        if previous_text.ends_with('\n') {
+            text.push('\n');
+        }
+        std::fs::write(RESULTS_PATH, text).unwrap();
+    } else if changed > 0 {
+        println!(
+            "{} results differ from {}, run with `NESMUR_BLESS=1` to update it",
+            changed, RESULTS_PATH
+        );
+    }
+
+    let passed: usize = results
+        .iter()
+        .filter(|result: &&TestResult| **result == TestResult::Pass)
+        .count();
+    println!(
+        "AccuracyCoin: {}/{} tests passed after {} frames",
+        passed,
+        results.len(),
+        frames
+    );
+    assert!(
+        regressions.is_empty(),
+        "Previously passing tests regressed:\n{}",
+        regressions.join("\n")
+    );
+}
@@ -1,11 +1,18 @@
 #[allow(unused_imports)]
 use nes::{NES, cartridge::ROM};
+use std::path::Path;
 
+#[allow(dead_code)]
 pub fn setup_nes(rom_path: &str) -> NES {
     let path: String = format!(
         concat!(env!("CARGO_MANIFEST_DIR"), "/tests/roms/{}"),
         rom_path
     );
+    setup_nes_from_path(Path::new(&path))
+}
+
+#[allow(dead_code)]
+pub fn setup_nes_from_path(path: &Path) -> NES {
     let rom_bytes: Vec<u8> = std::fs::read(path).unwrap();
     let rom: ROM = ROM::new(&rom_bytes).unwrap();
     NES::new(rom)
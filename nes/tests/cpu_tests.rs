@@ -8,10 +8,9 @@ use std::{
 
 #[test]
 fn nestest() {
-    const GOOD_NESTEST_LOG: &'static str =
+    const GOOD_NESTEST_LOG: &str =
         concat!(env!("CARGO_MANIFEST_DIR"), "/tests/logs/good-nestest.log");
-    const NESTEST_LOG: &'static str =
-        concat!(env!("CARGO_MANIFEST_DIR"), "/tests/logs/nestest.log");
+    const NESTEST_LOG: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/logs/nestest.log");
 
     let mut debug_log: String = String::new();
     let mut nes: NES = common::setup_nes("nestest.nes");
@@ -35,10 +34,9 @@ fn nestest() {
     // Hardcoded hash is the hash of the current best cpu log
     if log_hash != good_hash && log_hash != "a9bc0c53220971c7d3ab15b228121d38" {
         let mut log_file: BufWriter<File> = BufWriter::new(File::create(NESTEST_LOG).unwrap());
-        write!(&mut log_file, "{}", &debug_log).unwrap();
+        write!(&mut log_file, "{}", debug_log).unwrap();
         log_file.flush().unwrap();
-        assert!(
-            false,
+        panic!(
             "The generated CPU log does not match the known good reference log!\nGenerated Hash: {}\nKnown Good Hash: {}",
             log_hash, good_hash
         );
@@ -0,0 +1,284 @@
+mod common;
+
+use nes::{NES, SCREEN_HEIGHT, SCREEN_WIDTH, ppu::renderer::RGB, tools::NESAccess};
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+};
+
+const MANIFEST: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/rom_tests.txt");
+const WORKSPACE_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/..");
+const GOLDEN_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden");
+const OUTPUT_DIR: &str = concat!(env!("CARGO_TARGET_TMPDIR"), "/rom_tests");
+
+// https://github.com/christopherpow/nes-test-roms/blob/master/instr_test-v5/readme.txt
+const STATUS_ADDR: u16 = 0x6000;
+const STATUS_SIGNATURE: [u8; 3] = [0xDE, 0xB0, 0x61];
+const STATUS_RUNNING: u8 = 0x80;
+const STATUS_NEEDS_RESET: u8 = 0x81;
+/// Reset has to be held for at least 100 ms
+const RESET_DELAY_FRAMES: usize = 6;
+
+enum Check {
+    Status { max_frames: usize },
+    Frame { frames: usize, md5: String },
+}
+
+struct RomTest {
+    line: usize,
+    path: String,
+    check: Check,
+    xfail: bool,
+}
+
+enum Outcome {
+    Pass,
+    Fail(String),
+    Skip(String),
+}
+
+struct TestResult {
+    outcome: Outcome,
+    /// The last frame, for blessing and diffs
+    pixels: Vec<u8>,
+}
+
+fn parse_manifest(text: &str) -> Vec<RomTest> {
+    let mut tests: Vec<RomTest> = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line: &str = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let frames: usize = fields
+            .get(2)
+            .and_then(|frames: &&str| frames.parse().ok())
+            .unwrap_or_else(|| panic!("Missing frame count on line {}", index + 1));
+        let (check, flags): (Check, &[&str]) = match fields[1] {
+            "status" => (Check::Status { max_frames: frames }, &fields[3..]),
+            "frame" => (
+                Check::Frame {
+                    frames,
+                    md5: fields[3].to_string(),
+                },
+                &fields[4..],
+            ),
+            check => panic!("Unknown check `{}` on line {}", check, index + 1),
+        };
+        tests.push(RomTest {
+            line: index,
+            path: fields[0].to_string(),
+            check,
+            xfail: flags.contains(&"xfail"),
+        });
+    }
+    tests
+}
+
+fn run_test(test: &RomTest) -> TestResult {
+    let path: PathBuf = Path::new(WORKSPACE_DIR).join(&test.path);
+    if !path.exists() {
+        return TestResult {
+            outcome: Outcome::Skip("ROM not found".to_string()),
+            pixels: Vec::new(),
+        };
+    }
+
+    let mut nes: NES = common::setup_nes_from_path(&path);
+    let outcome: Outcome = match &test.check {
+        Check::Status { max_frames } => run_status(&mut nes, *max_frames),
+        Check::Frame { frames, md5 } => match (0..*frames).all(|_| nes.step_frame(|_| {})) {
+            true if frame_md5(&nes) == *md5 => Outcome::Pass,
+            true => Outcome::Fail(format!("frame hash is {}", frame_md5(&nes))),
+            false => Outcome::Fail("CPU stopped".to_string()),
+        },
+    };
+    TestResult {
+        outcome,
+        pixels: frame_bytes(&nes),
+    }
+}
+
+fn run_status(nes: &mut NES, max_frames: usize) -> Outcome {
+    let mut reset_frame: Option<usize> = None;
+    for frame in 0..max_frames {
+        if !nes.step_frame(|_| {}) {
+            return Outcome::Fail("CPU stopped".to_string());
+        }
+
+        let signature: [u8; 3] =
+            std::array::from_fn(|i: usize| nes.bus().peek(STATUS_ADDR + 1 + i as u16));
+        if signature != STATUS_SIGNATURE {
+            continue;
+        }
+        let status: u8 = nes.bus().peek(STATUS_ADDR);
+        match status {
+            STATUS_RUNNING => {}
+            STATUS_NEEDS_RESET => match reset_frame {
+                None => reset_frame = Some(frame + RESET_DELAY_FRAMES),
+                Some(reset_at) if frame >= reset_at => {
+                    nes.reset();
+                    reset_frame = None;
+                }
+                Some(_) => {}
+            },
+            0x00 => return Outcome::Pass,
+            code => {
+                return Outcome::Fail(format!("code {}: {}", code, status_text(nes)));
+            }
+        }
+    }
+    Outcome::Fail(format!("timed out after {} frames", max_frames))
+}
+
+/// Null-terminated text the ROM writes after the signature
+fn status_text(nes: &NES) -> String {
+    let mut text: Vec<u8> = Vec::new();
+    let mut addr: u16 = STATUS_ADDR + 4;
+    while addr < 0x8000 {
+        let byte: u8 = nes.bus().peek(addr);
+        if byte == 0x00 {
+            break;
+        }
+        text.push(byte);
+        addr += 1;
+    }
+    String::from_utf8_lossy(&text)
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+fn frame_bytes(nes: &NES) -> Vec<u8> {
+    nes.renderer()
+        .pixels
+        .iter()
+        .flat_map(|color: &RGB| [color.0, color.1, color.2])
+        .collect()
+}
+
+fn frame_md5(nes: &NES) -> String {
+    pixels_md5(&frame_bytes(nes))
+}
+
+fn pixels_md5(pixels: &[u8]) -> String {
+    format!("{:x}", md5::compute(pixels))
+}
+
+/// Unique per ROM, since many suites reuse file names like `01-basics.nes`
+fn image_name(test: &RomTest) -> String {
+    test.path.trim_end_matches(".nes").replace(['/', '\\'], "_")
+}
+
+fn write_png(path: &Path, pixels: &[u8]) {
+    let writer: BufWriter<File> = BufWriter::new(File::create(path).unwrap());
+    let mut encoder: png::Encoder<BufWriter<File>> =
+        png::Encoder::new(writer, SCREEN_WIDTH as u32, SCREEN_HEIGHT as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer: png::Writer<BufWriter<File>>| writer.write_image_data(pixels))
+        .unwrap();
+}
+
+fn read_png(path: &Path) -> Option<Vec<u8>> {
+    let reader: BufReader<File> = BufReader::new(File::open(path).ok()?);
+    let mut reader: png::Reader<BufReader<File>> = png::Decoder::new(reader).read_info().ok()?;
+    let mut pixels: Vec<u8> = vec![0x00; reader.output_buffer_size()?];
+    let info: png::OutputInfo = reader.next_frame(&mut pixels).ok()?;
+    pixels.truncate(info.buffer_size());
+    Some(pixels)
+}
+
+/// Dims matching pixels and marks differing ones red
+fn diff_pixels(golden: &[u8], actual: &[u8]) -> Vec<u8> {
+    golden
+        .chunks(3)
+        .zip(actual.chunks(3))
+        .flat_map(|(golden, actual): (&[u8], &[u8])| match golden == actual {
+            true => [golden[0] / 4, golden[1] / 4, golden[2] / 4],
+            false => [0xFF, 0x00, 0x00],
+        })
+        .collect()
+}
+
+#[test]
+fn rom_tests() {
+    let mut manifest: String = std::fs::read_to_string(MANIFEST).unwrap();
+    let tests: Vec<RomTest> = parse_manifest(&manifest);
+    let bless: bool = std::env::var_os("NESMUR_BLESS").is_some();
+    std::fs::create_dir_all(OUTPUT_DIR).unwrap();
+
+    // Every test gets its own machine, so they can run side by side
+    let results: Vec<TestResult> = std::thread::scope(|scope| {
+        let handles: Vec<std::thread::ScopedJoinHandle<TestResult>> = tests
+            .iter()
+            .map(|test: &RomTest| scope.spawn(move || run_test(test)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect()
+    });
+
+    let mut lines: Vec<String> = manifest.lines().map(str::to_string).collect();
+    let mut failures: usize = 0;
+    println!("{:<52} {:<7} {:<6} DETAIL", "ROM", "CHECK", "RESULT");
+    for (test, result) in tests.iter().zip(results.iter()) {
+        let name: String = image_name(test);
+        let (mut status, mut detail): (&str, String) = match &result.outcome {
+            Outcome::Pass if test.xfail => ("XPASS", "remove `xfail`".to_string()),
+            Outcome::Pass => ("PASS", String::new()),
+            Outcome::Fail(detail) if test.xfail => ("XFAIL", detail.clone()),
+            Outcome::Fail(detail) => ("FAIL", detail.clone()),
+            Outcome::Skip(detail) => ("SKIP", detail.clone()),
+        };
+
+        if let Check::Frame { md5, .. } = &test.check
+            && !matches!(result.outcome, Outcome::Skip(_))
+        {
+            let golden_path: PathBuf = Path::new(GOLDEN_DIR).join(format!("{}.png", name));
+            if bless {
+                std::fs::create_dir_all(GOLDEN_DIR).unwrap();
+                write_png(&golden_path, &result.pixels);
+                let actual: String = pixels_md5(&result.pixels);
+                let line: &mut String = &mut lines[test.line];
+                let hash_at: usize = line.rfind(md5.as_str()).unwrap();
+                line.replace_range(hash_at..hash_at + md5.len(), &actual);
+                (status, detail) = ("BLESS", actual);
+            } else if matches!(result.outcome, Outcome::Fail(_))
+                && let Some(golden) = read_png(&golden_path)
+                && golden.len() == result.pixels.len()
+            {
+                let diff_path: PathBuf = Path::new(OUTPUT_DIR).join(format!("{}.diff.png", name));
+                write_png(&diff_path, &diff_pixels(&golden, &result.pixels));
+            }
+        }
+        if matches!(status, "FAIL" | "XPASS") {
+            failures += 1;
+            let actual_path: PathBuf = Path::new(OUTPUT_DIR).join(format!("{}.png", name));
+            write_png(&actual_path, &result.pixels);
+        }
+
+        let check: &str = match test.check {
+            Check::Status { .. } => "status",
+            Check::Frame { .. } => "frame",
+        };
+        println!("{:<52} {:<7} {:<6} {}", test.path, check, status, detail);
+    }
+
+    if bless {
+        manifest = lines.join("\n") + "\n";
+        std::fs::write(MANIFEST, manifest).unwrap();
+    }
+    assert!(
+        failures == 0,
+        "{} test ROM(s) failed, their last frames and diffs were saved to {}",
+        failures,
+        OUTPUT_DIR
+    );
+}
@@ -1,7 +1,9 @@
 use crate::{
     INITIAL_SIZE_HEIGHT, INITIAL_SIZE_WIDTH, PERSISTENT_DATA_PATH,
+    audio::{AudioBackendType, AudioOutput},
     events::{AppEvent, AppEventQueue, ResetTarget},
     input::{ControllerConfig, InputManager, InputMapping},
+    nes_manager::{FastForwardConfig, RewindConfig},
     prelude::*,
 };
 use eframe::{CreationContext, Storage};
@@ -9,6 +11,7 @@ use egui::{Color32, ColorImage, TextureOptions};
 use serde::{Deserialize, Serialize};
 use std::{
     collections::HashMap,
+    path::PathBuf,
     time::{Duration, Instant},
 };
 use uuid::Uuid;
@@ -21,6 +24,10 @@ pub struct AppConfig {
     pub keyboard_input_mapping: (InputMapping, InputMapping),
     pub controller_input_mapping: HashMap<Uuid, ControllerConfig>,
     pub selected_controllers: (Option<Uuid>, Option<Uuid>),
+    #[serde(default)]
+    pub rewind: RewindConfig,
+    #[serde(default)]
+    pub fast_forward: FastForwardConfig,
 }
 
 impl Default for AppConfig {
@@ -30,6 +37,8 @@ impl Default for AppConfig {
             keyboard_input_mapping: (InputMapping::default_keyboard(), InputMapping::default()),
             controller_input_mapping: HashMap::new(),
             selected_controllers: (None, None),
+            rewind: RewindConfig::default(),
+            fast_forward: FastForwardConfig::default(),
         }
     }
 }
@@ -39,13 +48,20 @@ pub struct App {
     // States
     pub show_controller_config: bool,
     pub is_paused: bool,
+    pub is_rewinding: bool,
+    pub is_fast_forwarding: bool,
     pub show_reset_app_data: bool,
     pub do_reset_app_data: Option<bool>,
+    pub cpu_debugger: crate::debugger::cpu::CpuDebugger,
+    pub memory_editor: crate::debugger::memory::MemoryEditor,
+    pub nametable_viewer: crate::debugger::nametable::NametableViewer,
+    pub tile_viewer: crate::debugger::tiles::TileViewer,
     #[cfg(debug_assertions)]
     pub debug: crate::debug::DebugOptions,
 
     // Data
     pub input_manager: InputManager,
+    pub audio: AudioOutput,
     pub nes_manager: crate::nes_manager::NESManager,
     pub nes_state: crate::NESState,
     events: AppEventQueue,
@@ -60,7 +76,11 @@ pub struct App {
 }
 
 impl App {
-    pub fn new(cc: &CreationContext) -> Self {
+    pub fn new(
+        cc: &CreationContext,
+        audio_backend: AudioBackendType,
+        saves_dir: Option<PathBuf>,
+    ) -> Self {
         debug!("Initializing app...");
 
         egui_extras::install_image_loaders(&cc.egui_ctx);
@@ -71,10 +91,17 @@ impl App {
             ColorImage::new([256, 240], vec![Color32::BLACK; 256 * 240]),
             TextureOptions::NEAREST,
         );
-        let nes_manager: crate::nes_manager::NESManager =
-            crate::nes_manager::NESManager::new(screen_texture);
+        let audio: AudioOutput = AudioOutput::new(audio_backend);
+        let mut nes_manager: crate::nes_manager::NESManager = crate::nes_manager::NESManager::new(
+            screen_texture,
+            audio.buffer.clone(),
+            audio.sample_rate(),
+            saves_dir,
+        );
 
         let config: AppConfig = Self::read_config(cc.storage);
+        nes_manager.rewind_config = config.rewind;
+        nes_manager.fast_forward_config = config.fast_forward;
         let input_manager: InputManager = InputManager::new(&config);
 
         debug!("Finished initializing app");
@@ -82,13 +109,20 @@ impl App {
             // States
             show_controller_config: false,
             is_paused: false,
+            is_rewinding: false,
+            is_fast_forwarding: false,
             show_reset_app_data: false,
             do_reset_app_data: None,
+            cpu_debugger: crate::debugger::cpu::CpuDebugger::default(),
+            memory_editor: crate::debugger::memory::MemoryEditor::default(),
+            nametable_viewer: crate::debugger::nametable::NametableViewer::default(),
+            tile_viewer: crate::debugger::tiles::TileViewer::default(),
             #[cfg(debug_assertions)]
             debug: crate::debug::DebugOptions::new(),
 
             // Data
             input_manager,
+            audio,
             // screen_texture,
             nes_manager,
             nes_state: crate::NESState::Stopped,
@@ -126,14 +160,26 @@ impl App {
             ColorImage::new([256, 240], vec![Color32::BLACK; 256 * 240]),
             TextureOptions::NEAREST,
         );
-        let nes_manager: crate::nes_manager::NESManager =
-            crate::nes_manager::NESManager::new(screen_texture);
+        let mut nes_manager: crate::nes_manager::NESManager = crate::nes_manager::NESManager::new(
+            screen_texture,
+            self.audio.buffer.clone(),
+            self.audio.sample_rate(),
+            self.nes_manager.saves_dir.clone(),
+        );
 
         let config: AppConfig = AppConfig::default();
+        nes_manager.rewind_config = config.rewind;
+        nes_manager.fast_forward_config = config.fast_forward;
         let input_manager: InputManager = InputManager::new(&config);
 
         self.show_controller_config = false;
         self.is_paused = false;
+        self.is_rewinding = false;
+        self.is_fast_forwarding = false;
+        self.cpu_debugger = crate::debugger::cpu::CpuDebugger::default();
+        self.memory_editor = crate::debugger::memory::MemoryEditor::default();
+        self.nametable_viewer = crate::debugger::nametable::NametableViewer::default();
+        self.tile_viewer = crate::debugger::tiles::TileViewer::default();
         #[cfg(debug_assertions)]
         {
             self.debug = crate::debug::DebugOptions::new();
@@ -184,10 +230,12 @@ impl App {
         match storage {
             Some(storage) => {
                 let state: AppConfig = AppConfig {
+                    volume: self.volume,
                     keyboard_input_mapping: self.input_manager.keyboard_input_mapping,
                     controller_input_mapping: self.input_manager.controller_input_mapping.clone(),
                     selected_controllers: self.input_manager.selected_controllers,
-                    ..Default::default()
+                    rewind: self.nes_manager.rewind_config,
+                    fast_forward: self.nes_manager.fast_forward_config,
                 };
                 match serde_json::to_string(&state) {
                     Ok(config) => {
@@ -222,6 +270,39 @@ impl App {
         self.avg_framerate = 1000.0 / self.avg_frametime;
     }
 
+    fn handle_state_hotkeys(&mut self) {
+        let Some(save_slots) = self.nes_manager.save_slots.as_mut() else {
+            return;
+        };
+
+        if self
+            .input_manager
+            .hotkey_pressed(|mapping: &InputMapping| mapping.next_slot)
+        {
+            save_slots.select_next();
+        }
+        if self
+            .input_manager
+            .hotkey_pressed(|mapping: &InputMapping| mapping.previous_slot)
+        {
+            save_slots.select_previous();
+        }
+
+        let slot: usize = save_slots.selected;
+        if self
+            .input_manager
+            .hotkey_pressed(|mapping: &InputMapping| mapping.save_state)
+        {
+            self.new_event(AppEvent::NES(crate::NESEvent::SaveState(slot)));
+        }
+        if self
+            .input_manager
+            .hotkey_pressed(|mapping: &InputMapping| mapping.load_state)
+        {
+            self.new_event(AppEvent::NES(crate::NESEvent::LoadState(slot)));
+        }
+    }
+
     pub fn new_event(&mut self, event: AppEvent) {
         self.events.push(event);
     }
@@ -286,6 +367,8 @@ impl App {
                     self.nes_manager.stop_nes();
                     self.nes_state = crate::NESState::Stopped;
                     self.is_paused = false;
+                    self.is_rewinding = false;
+                    self.is_fast_forwarding = false;
                 }
                 NES(crate::NESEvent::Pause) => {
                     if self.nes_state != crate::NESState::Running {
@@ -299,7 +382,10 @@ impl App {
                     self.nes_state = crate::NESState::Paused;
                 }
                 NES(crate::NESEvent::Resume) => {
-                    if self.nes_state != crate::NESState::Paused {
+                    if !matches!(
+                        self.nes_state,
+                        crate::NESState::Paused | crate::NESState::Stepping
+                    ) {
                         error!(
                             "Tried to resume nes when it a non-paused state: {:?}",
                             self.nes_state
@@ -309,6 +395,64 @@ impl App {
                     self.nes_manager.resume();
                     self.nes_state = crate::NESState::Running;
                 }
+                NES(crate::NESEvent::Step(kind)) => {
+                    if self.nes_state != crate::NESState::Paused {
+                        error!(
+                            "Tried to step nes when it a non-paused state: {:?}",
+                            self.nes_state
+                        );
+                        return;
+                    }
+                    self.nes_manager.step(kind);
+                    self.nes_state = crate::NESState::Stepping;
+                }
+                NES(crate::NESEvent::SaveState(slot)) => {
+                    if self.nes_state == crate::NESState::Stopped {
+                        error!("Tried to save state when nes is stopped");
+                        return;
+                    }
+                    self.nes_manager.save_state(slot);
+                }
+                NES(crate::NESEvent::LoadState(slot)) => {
+                    if self.nes_state == crate::NESState::Stopped {
+                        error!("Tried to load state when nes is stopped");
+                        return;
+                    }
+                    self.nes_manager.load_state(slot);
+                }
+                NES(crate::NESEvent::Rewind(rewinding)) => {
+                    if self.nes_state == crate::NESState::Stopped {
+                        return;
+                    }
+                    self.nes_manager.set_rewinding(rewinding);
+                }
+                NES(crate::NESEvent::FastForward(fast_forward)) => {
+                    if self.nes_state == crate::NESState::Stopped {
+                        return;
+                    }
+                    self.nes_manager.set_fast_forward(fast_forward);
+                }
+                NES(crate::NESEvent::RecordMovie(path, from_power_on)) => {
+                    if self.nes_state == crate::NESState::Stopped {
+                        error!("Tried to record a movie when nes is stopped");
+                        return;
+                    }
+                    self.nes_manager.record_movie(path, from_power_on);
+                }
+                NES(crate::NESEvent::PlayMovie(path)) => {
+                    if self.nes_state == crate::NESState::Stopped {
+                        error!("Tried to play a movie when nes is stopped");
+                        return;
+                    }
+                    self.nes_manager.play_movie(&path);
+                }
+                NES(crate::NESEvent::StopMovie) => self.nes_manager.stop_movie(),
+                NES(crate::NESEvent::SetBreakpoints(list)) => {
+                    self.nes_manager.set_breakpoints(list)
+                }
+                NES(crate::NESEvent::PokeMemory(region, offset, value)) => {
+                    self.nes_manager.poke_memory(region, offset, value)
+                }
 
                 e => warn!("Unhandled app event: {:?}", e),
             }
@@ -323,6 +467,26 @@ impl eframe::App for App {
 
         self.handle_events(ctx, frame);
         self.nes_manager.handle_nes_messages();
+        if self.nes_state == crate::NESState::Stepping && !self.nes_manager.stepping {
+            self.nes_state = crate::NESState::Paused;
+        }
+        if let Some(hit) = self.nes_manager.take_breakpoint_hit() {
+            self.is_paused = true;
+            self.nes_state = crate::NESState::Paused;
+            self.cpu_debugger.open = true;
+            self.cpu_debugger.breakpoints.last_hit = Some(hit);
+        }
+        if let Some(snapshot) = self.nes_manager.take_memory_snapshot() {
+            self.memory_editor
+                .update(snapshot, ctx.input(|i: &egui::InputState| i.time));
+        }
+        if let Some(snapshot) = self.nes_manager.take_nametable_snapshot() {
+            self.nametable_viewer.update(ctx, snapshot);
+        }
+        if let Some(snapshot) = self.nes_manager.take_tile_snapshot() {
+            self.tile_viewer.update(ctx, snapshot);
+        }
+        self.audio.set_volume(self.volume);
 
         #[cfg(debug_assertions)]
         self.debug.update(ctx);
@@ -339,6 +503,31 @@ impl eframe::App for App {
                 false => self.new_event(AppEvent::NES(crate::NESEvent::Resume)),
             };
         }
+        self.handle_state_hotkeys();
+
+        let rewinding: bool = self
+            .input_manager
+            .hotkey_held(|mapping: &InputMapping| mapping.rewind);
+        if rewinding != self.is_rewinding {
+            self.is_rewinding = rewinding;
+            self.new_event(AppEvent::NES(crate::NESEvent::Rewind(rewinding)));
+        }
+
+        let fast_forward: bool = match self.nes_manager.fast_forward_config.toggle {
+            true => {
+                self.is_fast_forwarding
+                    ^ self
+                        .input_manager
+                        .hotkey_pressed(|mapping: &InputMapping| mapping.fast_forward)
+            }
+            false => self
+                .input_manager
+                .hotkey_held(|mapping: &InputMapping| mapping.fast_forward),
+        };
+        if fast_forward != self.is_fast_forwarding {
+            self.is_fast_forwarding = fast_forward;
+            self.new_event(AppEvent::NES(crate::NESEvent::FastForward(fast_forward)));
+        }
 
         self.draw_ui(ctx);
     }
@@ -0,0 +1,87 @@
+use super::{AudioBackend, SharedAudioBuffer};
+use crate::prelude::*;
+use anyhow::anyhow;
+use cpal::{
+    Device, FromSample, Host, OutputCallbackInfo, SampleFormat, SizedSample, Stream, StreamConfig,
+    SupportedStreamConfig,
+    traits::{DeviceTrait, HostTrait, StreamTrait},
+};
+
+/// Plays the buffer through the host's default output device
+pub struct CpalBackend {
+    sample_rate: u32,
+    // The stream stops playing once dropped
+    _stream: Stream,
+}
+
+impl CpalBackend {
+    pub fn new(buffer: SharedAudioBuffer) -> anyhow::Result<Self> {
+        let host: Host = cpal::default_host();
+        let device: Device = host
+            .default_output_device()
+            .ok_or_else(|| anyhow!("No default output device available"))?;
+        let supported_config: SupportedStreamConfig = device.default_output_config()?;
+        let sample_format: SampleFormat = supported_config.sample_format();
+        let config: StreamConfig = supported_config.into();
+        let sample_rate: u32 = config.sample_rate.0;
+
+        // The buffer holds f32 samples, which get converted to whatever the device plays
+        let stream: Stream = match sample_format {
+            SampleFormat::I8 => build_stream::<i8>(&device, &config, buffer)?,
+            SampleFormat::I16 => build_stream::<i16>(&device, &config, buffer)?,
+            SampleFormat::I32 => build_stream::<i32>(&device, &config, buffer)?,
+            SampleFormat::U8 => build_stream::<u8>(&device, &config, buffer)?,
+            SampleFormat::U16 => build_stream::<u16>(&device, &config, buffer)?,
+            SampleFormat::U32 => build_stream::<u32>(&device, &config, buffer)?,
+            SampleFormat::F32 => build_stream::<f32>(&device, &config, buffer)?,
+            SampleFormat::F64 => build_stream::<f64>(&device, &config, buffer)?,
+            format => {
+                return Err(anyhow!(
+                    "Default output device uses unsupported {:?} samples",
+                    format
+                ));
+            }
+        };
+        stream.play()?;
+        debug!("Playing {:?} samples at {} Hz", sample_format, sample_rate);
+
+        Ok(CpalBackend {
+            sample_rate,
+            _stream: stream,
+        })
+    }
+}
+
+fn build_stream<T>(
+    device: &Device,
+    config: &StreamConfig,
+    buffer: SharedAudioBuffer,
+) -> anyhow::Result<Stream>
+where
+    T: SizedSample + FromSample<f32>,
+{
+    let channels: usize = config.channels as usize;
+    let stream: Stream = device.build_output_stream(
+        config,
+        move |data: &mut [T], _: &OutputCallbackInfo| {
+            // The NES is mono, so every channel gets the same sample
+            for frame in data.chunks_mut(channels) {
+                let sample: T = T::from_sample(buffer.pop().unwrap_or(0.0));
+                frame.fill(sample);
+            }
+        },
+        |err: cpal::StreamError| error!("Audio stream error: {}", err),
+        None,
+    )?;
+    Ok(stream)
+}
+
+impl AudioBackend for CpalBackend {
+    fn name(&self) -> &'static str {
+        "cpal"
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+}
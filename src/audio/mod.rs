@@ -0,0 +1,143 @@
+//! Audio output
+//!
+//! The "nes" thread pushes resampled APU output into an [`AudioBuffer`], which is drained by
+//! whichever [`AudioBackend`] is active.
+
+#[cfg(feature = "cpal")]
+pub mod cpal_backend;
+pub mod null;
+pub mod wav;
+
+use crate::prelude::*;
+use crossbeam::queue::ArrayQueue;
+use std::{
+    path::PathBuf,
+    sync::{
+        Arc,
+        atomic::{AtomicU32, Ordering},
+    },
+};
+
+pub const DEFAULT_SAMPLE_RATE: u32 = 44_100;
+/// Roughly 1/4th of a second at 44.1 kHz
+const BUFFER_CAPACITY: usize = 11_025;
+
+pub type SharedAudioBuffer = Arc<AudioBuffer>;
+
+/// Lock-free ring buffer of mono samples, shared between the "nes" thread and the audio backend
+pub struct AudioBuffer {
+    queue: ArrayQueue<f32>,
+    /// `f32` stored as its bits
+    volume: AtomicU32,
+}
+
+impl AudioBuffer {
+    pub fn new(capacity: usize) -> Self {
+        AudioBuffer {
+            queue: ArrayQueue::new(capacity),
+            volume: AtomicU32::new(1.0f32.to_bits()),
+        }
+    }
+
+    /// Overwrites the oldest sample if the backend isn't keeping up
+    pub fn push(&self, sample: f32) {
+        self.queue.force_push(sample);
+    }
+
+    /// Pops the next sample with the volume applied
+    pub fn pop(&self) -> Option<f32> {
+        self.queue
+            .pop()
+            .map(|sample: f32| -> f32 { sample * self.volume() })
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    pub fn clear(&self) {
+        while self.queue.pop().is_some() {}
+    }
+
+    pub fn volume(&self) -> f32 {
+        f32::from_bits(self.volume.load(Ordering::Relaxed))
+    }
+
+    pub fn set_volume(&self, volume: f64) {
+        self.volume
+            .store((volume.clamp(0.0, 1.0) as f32).to_bits(), Ordering::Relaxed);
+    }
+}
+
+pub trait AudioBackend {
+    fn name(&self) -> &'static str;
+    fn sample_rate(&self) -> u32;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AudioBackendType {
+    /// Discards all audio
+    Null,
+    /// Records all audio to a WAV file
+    Wav(PathBuf),
+    /// Plays audio through the default output device
+    #[cfg(feature = "cpal")]
+    Cpal,
+}
+
+impl Default for AudioBackendType {
+    fn default() -> Self {
+        #[cfg(feature = "cpal")]
+        return AudioBackendType::Cpal;
+        #[cfg(not(feature = "cpal"))]
+        return AudioBackendType::Null;
+    }
+}
+
+/// Owns the active backend along with the buffer feeding it
+pub struct AudioOutput {
+    pub buffer: SharedAudioBuffer,
+    backend: Box<dyn AudioBackend>,
+}
+
+impl AudioOutput {
+    pub fn new(backend_type: AudioBackendType) -> Self {
+        let buffer: SharedAudioBuffer = Arc::new(AudioBuffer::new(BUFFER_CAPACITY));
+
+        let backend: anyhow::Result<Box<dyn AudioBackend>> = match &backend_type {
+            AudioBackendType::Null => Ok(Box::new(null::NullBackend::new())),
+            AudioBackendType::Wav(path) => wav::WavBackend::new(path, buffer.clone())
+                .map(|backend: wav::WavBackend| -> Box<dyn AudioBackend> { Box::new(backend) }),
+            #[cfg(feature = "cpal")]
+            AudioBackendType::Cpal => cpal_backend::CpalBackend::new(buffer.clone()).map(
+                |backend: cpal_backend::CpalBackend| -> Box<dyn AudioBackend> { Box::new(backend) },
+            ),
+        };
+        let backend: Box<dyn AudioBackend> = backend.unwrap_or_else(|err| {
+            error!(
+                "Failed to initialize the {:?} audio backend, falling back to no audio: {}",
+                backend_type, err
+            );
+            Box::new(null::NullBackend::new())
+        });
+        info!(
+            "Using the '{}' audio backend at {} Hz",
+            backend.name(),
+            backend.sample_rate()
+        );
+
+        AudioOutput { buffer, backend }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.backend.sample_rate()
+    }
+
+    pub fn set_volume(&self, volume: f64) {
+        self.buffer.set_volume(volume);
+    }
+}
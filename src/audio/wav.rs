@@ -0,0 +1,85 @@
+use super::{AudioBackend, DEFAULT_SAMPLE_RATE, SharedAudioBuffer};
+use crate::{new_named_thread, prelude::*};
+use hound::{SampleFormat, WavSpec, WavWriter};
+use std::{
+    fs::File,
+    io::BufWriter,
+    path::Path,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+
+const DRAIN_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Drains the buffer on its own thread and writes every sample to a 32-bit float mono WAV file
+pub struct WavBackend {
+    running: Arc<AtomicBool>,
+    thread_handle: Option<JoinHandle<()>>,
+}
+
+impl WavBackend {
+    pub fn new(path: &Path, buffer: SharedAudioBuffer) -> anyhow::Result<Self> {
+        let spec: WavSpec = WavSpec {
+            channels: 1,
+            sample_rate: DEFAULT_SAMPLE_RATE,
+            bits_per_sample: 32,
+            sample_format: SampleFormat::Float,
+        };
+        let mut writer: WavWriter<BufWriter<File>> = WavWriter::create(path, spec)?;
+        info!("Recording audio to {:?}", path);
+
+        let running: Arc<AtomicBool> = Arc::new(AtomicBool::new(true));
+        let thread_running: Arc<AtomicBool> = running.clone();
+        let thread_handle: JoinHandle<()> = new_named_thread("audio-wav", move || {
+            loop {
+                // Check before draining, so the last samples are still written when stopping
+                let running: bool = thread_running.load(Ordering::Relaxed);
+                while let Some(sample) = buffer.pop() {
+                    if let Err(err) = writer.write_sample(sample) {
+                        error!("Failed to write audio sample to WAV file: {}", err);
+                        return;
+                    }
+                }
+                if !running {
+                    break;
+                }
+                std::thread::sleep(DRAIN_INTERVAL);
+            }
+
+            if let Err(err) = writer.finalize() {
+                error!("Failed to finalize WAV file: {}", err);
+            }
+            trace!("Terminating thread...");
+        })?;
+
+        Ok(WavBackend {
+            running,
+            thread_handle: Some(thread_handle),
+        })
+    }
+}
+
+impl AudioBackend for WavBackend {
+    fn name(&self) -> &'static str {
+        "wav"
+    }
+
+    fn sample_rate(&self) -> u32 {
+        DEFAULT_SAMPLE_RATE
+    }
+}
+
+impl Drop for WavBackend {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(thread_handle) = self.thread_handle.take()
+            && thread_handle.join().is_err()
+        {
+            error!("The 'audio-wav' thread panicked");
+        }
+    }
+}
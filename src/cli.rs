@@ -2,9 +2,9 @@
 //!
 //! It defines the structure and functions necessary to interpret user input.
 
-use crate::ENV_VARS;
-use clap::{CommandFactory, Parser};
-use std::sync::LazyLock;
+use crate::{ENV_VARS, audio::AudioBackendType, headless::HeadlessArgs};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use std::{path::PathBuf, sync::LazyLock};
 
 static LONG_VERSION: LazyLock<String> = LazyLock::new(|| -> String {
     format!(
@@ -55,9 +55,48 @@ pub struct Cli {
         value_parser = parse_debug_info
     )]
     pub debug_info: bool,
+
+    /// Audio backend to use [default: cpal, or null when built without it]
+    #[arg(long, required = false, value_enum)]
+    pub audio: Option<AudioBackendArg>,
+
+    /// File the `wav` audio backend records to
+    #[arg(long, required = false, default_value = "nesmur.wav")]
+    pub wav_path: PathBuf,
+
+    /// Directory battery saves are kept in [default: next to the ROM]
+    #[arg(long, required = false)]
+    pub saves_dir: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Runs a ROM without opening a window
+    Headless(HeadlessArgs),
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioBackendArg {
+    Null,
+    Wav,
+    #[cfg(feature = "cpal")]
+    Cpal,
 }
 
 impl Cli {
+    pub fn audio_backend(&self) -> AudioBackendType {
+        match self.audio {
+            None => AudioBackendType::default(),
+            Some(AudioBackendArg::Null) => AudioBackendType::Null,
+            Some(AudioBackendArg::Wav) => AudioBackendType::Wav(self.wav_path.clone()),
+            #[cfg(feature = "cpal")]
+            Some(AudioBackendArg::Cpal) => AudioBackendType::Cpal,
+        }
+    }
+
     /// Prints the short version message
     pub fn _print_version() {
         println!("{}", Self::command().render_version())
@@ -0,0 +1,234 @@
+//! Breakpoint list for the CPU debugger
+//!
+//! The list itself lives in the `NESManager`, every edit sends the whole list to the NES again.
+
+use egui::{Color32, RichText, Ui};
+use nes::{
+    breakpoint::{
+        Access, AddressSpace, Breakpoint, BreakpointHit, Condition, ConditionError, Trigger,
+    },
+    cpu::interrupt::InterruptType,
+};
+use std::ops::RangeInclusive;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TriggerKind {
+    Execute,
+    Read,
+    Write,
+    ReadWrite,
+    Interrupt(InterruptType),
+}
+
+impl TriggerKind {
+    #[rustfmt::skip]
+    const ALL: [(TriggerKind, &str); 7] = [
+        (TriggerKind::Execute, "Execute"), (TriggerKind::Read, "Read"), (TriggerKind::Write, "Write"),
+        (TriggerKind::ReadWrite, "Read/Write"), (TriggerKind::Interrupt(InterruptType::NMI), "NMI"),
+        (TriggerKind::Interrupt(InterruptType::IRQ), "IRQ"), (TriggerKind::Interrupt(InterruptType::BRK), "BRK"),
+    ];
+
+    fn label(&self) -> &'static str {
+        TriggerKind::ALL
+            .iter()
+            .find(|(kind, _): &&(TriggerKind, &str)| kind == self)
+            .map_or("", |(_, label): &(TriggerKind, &str)| label)
+    }
+}
+
+pub struct BreakpointEditor {
+    /// Shown until the next hit
+    pub last_hit: Option<BreakpointHit>,
+    kind: TriggerKind,
+    space: AddressSpace,
+    range: String,
+    condition: String,
+    error: Option<String>,
+}
+
+impl Default for BreakpointEditor {
+    fn default() -> Self {
+        BreakpointEditor {
+            last_hit: None,
+            kind: TriggerKind::Execute,
+            space: AddressSpace::Cpu,
+            range: String::new(),
+            condition: String::new(),
+            error: None,
+        }
+    }
+}
+
+/// Hex addresses with an optional `$`, either `8000` or `$8000-$80FF`
+fn parse_range(text: &str) -> Result<RangeInclusive<u16>, String> {
+    let parse = |addr: &str| -> Result<u16, String> {
+        let addr: &str = addr.trim();
+        u16::from_str_radix(addr.strip_prefix('$').unwrap_or(addr), 16)
+            .map_err(|_| format!("'{}' isn't a hex address", addr))
+    };
+
+    match text.split_once('-') {
+        Some((start, end)) => {
+            let (start, end): (u16, u16) = (parse(start)?, parse(end)?);
+            match start <= end {
+                true => Ok(start..=end),
+                false => Err("The range ends before it starts".to_string()),
+            }
+        }
+        None => parse(text).map(|addr: u16| addr..=addr),
+    }
+}
+
+/// Adds an execute breakpoint at `addr`, or removes the one that is already there
+pub fn toggle_execute(list: &[Breakpoint], addr: u16) -> Vec<Breakpoint> {
+    let trigger: Trigger = Trigger::Execute(addr..=addr);
+    let mut list: Vec<Breakpoint> = list.to_vec();
+    match list
+        .iter()
+        .position(|breakpoint: &Breakpoint| breakpoint.trigger == trigger)
+    {
+        Some(index) => {
+            list.remove(index);
+        }
+        None => list.push(Breakpoint::new(trigger)),
+    }
+    list
+}
+
+pub fn has_execute(list: &[Breakpoint], addr: u16) -> bool {
+    list.iter().any(|breakpoint: &Breakpoint| {
+        breakpoint.enabled
+            && matches!(&breakpoint.trigger, Trigger::Execute(range) if range.contains(&addr))
+    })
+}
+
+impl BreakpointEditor {
+    /// Returns the new list if it was edited
+    pub fn show(&mut self, ui: &mut Ui, list: &[Breakpoint]) -> Option<Vec<Breakpoint>> {
+        let mut edited: Option<Vec<Breakpoint>> = None;
+
+        if let Some(hit) = &self.last_hit {
+            let mut text: String = format!("Hit at ${:04X}", hit.program_counter);
+            if let Some(access) = &hit.access {
+                text += &format!(
+                    ", {:?} ${:04X} = ${:02X}",
+                    access.access, access.addr, access.value
+                );
+            }
+            ui.label(RichText::new(text).color(ui.visuals().warn_fg_color));
+        }
+
+        egui::ScrollArea::vertical()
+            .id_salt("breakpoint_list")
+            .max_height(120.0)
+            .show(ui, |ui: &mut Ui| {
+                for (index, breakpoint) in list.iter().enumerate() {
+                    ui.horizontal(|ui: &mut Ui| {
+                        let mut enabled: bool = breakpoint.enabled;
+                        let mut text: String = breakpoint.trigger.to_string();
+                        if let Some(condition) = &breakpoint.condition {
+                            text += &format!(" if {}", condition);
+                        }
+                        let mut text: RichText = RichText::new(text).monospace();
+                        if self.last_hit.as_ref().map(|hit: &BreakpointHit| hit.index)
+                            == Some(index)
+                        {
+                            text = text.strong();
+                        }
+
+                        if ui.checkbox(&mut enabled, text).changed() {
+                            let mut list: Vec<Breakpoint> = list.to_vec();
+                            list[index].enabled = enabled;
+                            edited = Some(list);
+                        }
+                        if ui.small_button("🗑").on_hover_text("Remove").clicked() {
+                            let mut list: Vec<Breakpoint> = list.to_vec();
+                            list.remove(index);
+                            self.last_hit = None;
+                            edited = Some(list);
+                        }
+                    });
+                }
+                if list.is_empty() {
+                    ui.weak("No breakpoints, double click a line to add one");
+                }
+            });
+        ui.separator();
+
+        ui.horizontal(|ui: &mut Ui| {
+            egui::ComboBox::from_id_salt("breakpoint_kind")
+                .selected_text(self.kind.label())
+                .show_ui(ui, |ui: &mut Ui| {
+                    for (kind, label) in TriggerKind::ALL {
+                        ui.selectable_value(&mut self.kind, kind, label);
+                    }
+                });
+            if matches!(
+                self.kind,
+                TriggerKind::Read | TriggerKind::Write | TriggerKind::ReadWrite
+            ) {
+                egui::ComboBox::from_id_salt("breakpoint_space")
+                    .selected_text(format!("{:?}", self.space))
+                    .show_ui(ui, |ui: &mut Ui| {
+                        ui.selectable_value(&mut self.space, AddressSpace::Cpu, "Cpu");
+                        ui.selectable_value(&mut self.space, AddressSpace::Ppu, "Ppu");
+                    });
+            }
+            if !matches!(self.kind, TriggerKind::Interrupt(_)) {
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.range)
+                        .hint_text("$8000-$80FF")
+                        .desired_width(90.0),
+                );
+            }
+            ui.add(
+                egui::TextEdit::singleline(&mut self.condition)
+                    .hint_text("A == $10 && C")
+                    .desired_width(110.0),
+            );
+            if ui.button("Add").clicked() {
+                match self.breakpoint() {
+                    Ok(breakpoint) => {
+                        let mut list: Vec<Breakpoint> = list.to_vec();
+                        list.push(breakpoint);
+                        edited = Some(list);
+                        self.error = None;
+                    }
+                    Err(err) => self.error = Some(err),
+                }
+            }
+        });
+        if let Some(err) = &self.error {
+            ui.colored_label(Color32::RED, err);
+        }
+
+        edited
+    }
+
+    fn breakpoint(&self) -> Result<Breakpoint, String> {
+        let memory = |access: Access| -> Result<Trigger, String> {
+            Ok(Trigger::Memory {
+                space: self.space,
+                access,
+                range: parse_range(&self.range)?,
+            })
+        };
+        let trigger: Trigger = match self.kind {
+            TriggerKind::Execute => Trigger::Execute(parse_range(&self.range)?),
+            TriggerKind::Read => memory(Access::READ)?,
+            TriggerKind::Write => memory(Access::WRITE)?,
+            TriggerKind::ReadWrite => memory(Access::READ | Access::WRITE)?,
+            TriggerKind::Interrupt(itype) => Trigger::Interrupt(itype),
+        };
+
+        let mut breakpoint: Breakpoint = Breakpoint::new(trigger);
+        if !self.condition.trim().is_empty() {
+            let condition: Condition = self
+                .condition
+                .parse()
+                .map_err(|err: ConditionError| format!("Condition: {}", err))?;
+            breakpoint = breakpoint.with_condition(condition);
+        }
+        Ok(breakpoint)
+    }
+}
@@ -0,0 +1,359 @@
+//! CPU debugger with a disassembly around PC, the registers and the stack
+//!
+//! The NES thread sends a [`CpuSnapshot`] every frame while the window is open, and again
+//! whenever it pauses or finishes a step.
+
+use super::{
+    StepKind,
+    breakpoints::{self, BreakpointEditor},
+};
+use crate::{NESEvent, NESState, app::App, events::AppEvent};
+use egui::{
+    Color32, RichText, TextFormat, TextStyle, Ui, ViewportBuilder, ViewportId, text::LayoutJob,
+};
+use nes::{
+    NES,
+    breakpoint::Breakpoint,
+    cpu::Flags,
+    tools::{self, Disassembly, NESAccess},
+};
+use std::collections::VecDeque;
+
+/// Executed instructions shown above the program counter
+pub const HISTORY_LEN: usize = 8;
+/// Instructions shown from the program counter onwards
+const LOOKAHEAD: usize = 24;
+const STACK_PAGE: usize = 0x0100;
+
+#[rustfmt::skip]
+const FLAGS: [(Flags, &str); 8] = [
+    (Flags::NEGATIVE, "N"), (Flags::OVERFLOW, "V"), (Flags::UNUSED, "-"), (Flags::BREAK, "B"),
+    (Flags::DECIMAL_MODE, "D"), (Flags::INTERRUPT_DISABLE, "I"), (Flags::ZERO, "Z"), (Flags::CARRY, "C"),
+];
+
+#[derive(Debug, Clone)]
+pub struct CpuSnapshot {
+    pub accumulator: u8,
+    pub index_x: u8,
+    pub index_y: u8,
+    pub stack_pointer: u8,
+    pub program_counter: u16,
+    pub status: Flags,
+    pub cycles: usize,
+    pub scanline: usize,
+    pub dot: usize,
+    pub frame: usize,
+    /// Everything above the stack pointer, the most recently pushed byte first
+    pub stack: Vec<u8>,
+    /// The last instructions that ran, oldest first
+    pub history: Vec<Disassembly>,
+    /// Starts at the program counter
+    pub upcoming: Vec<Disassembly>,
+}
+
+impl CpuSnapshot {
+    /// `history` holds the addresses of the last instructions that ran
+    pub fn capture(nes: &NES, frame: usize, history: &VecDeque<u16>) -> Self {
+        let mut upcoming: Vec<Disassembly> = Vec::with_capacity(LOOKAHEAD);
+        let mut addr: u16 = nes.cpu.program_counter;
+        for _ in 0..LOOKAHEAD {
+            let disassembly: Disassembly = tools::disassemble(&nes.cpu, addr);
+            addr = disassembly.next_addr();
+            upcoming.push(disassembly);
+        }
+
+        let stack_top: usize = STACK_PAGE + nes.cpu.stack_pointer as usize + 1;
+        CpuSnapshot {
+            accumulator: nes.cpu.accumulator,
+            index_x: nes.cpu.index_x,
+            index_y: nes.cpu.index_y,
+            stack_pointer: nes.cpu.stack_pointer,
+            program_counter: nes.cpu.program_counter,
+            status: nes.cpu.status.clone(),
+            cycles: nes.bus().cpu_cycles,
+            scanline: nes.ppu().scanline,
+            dot: nes.ppu().dot,
+            frame,
+            stack: nes.memory().cpu_vram[stack_top.min(0x0200)..0x0200].to_vec(),
+            history: history
+                .iter()
+                .map(|addr: &u16| tools::disassemble(&nes.cpu, *addr))
+                .collect(),
+            upcoming,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct CpuDebugger {
+    pub open: bool,
+    /// Selected line, where "Run to cursor" runs to
+    cursor: Option<u16>,
+    /// Only scroll to PC when it moved, so the list can be scrolled by hand while paused
+    last_pc: Option<u16>,
+    pub breakpoints: BreakpointEditor,
+}
+
+/// Immediate operands and addresses are colored like in `docs/future_dev/cpu_debugger_example.html`
+fn operand_color(ui: &Ui, operand: &str) -> Color32 {
+    let dark_mode: bool = ui.visuals().dark_mode;
+    match operand.chars().next() {
+        Some('#') if dark_mode => Color32::LIGHT_RED,
+        Some('#') => Color32::DARK_RED,
+        Some('$' | '(') if dark_mode => Color32::from_rgb(0xF1, 0xC2, 0x32),
+        Some('$' | '(') => Color32::from_rgb(0xA0, 0x70, 0x00),
+        _ => ui.visuals().text_color(),
+    }
+}
+
+fn disassembly_line(
+    ui: &Ui,
+    line: &Disassembly,
+    at_pc: bool,
+    faded: bool,
+    breakpoint: bool,
+) -> LayoutJob {
+    let format: TextFormat = TextFormat::simple(
+        TextStyle::Monospace.resolve(ui.style()),
+        ui.visuals().text_color(),
+    );
+    let weak: Color32 = ui.visuals().weak_text_color();
+    let color = |color: Color32| -> TextFormat {
+        TextFormat {
+            color: if faded { weak } else { color },
+            ..format.clone()
+        }
+    };
+
+    let mut job: LayoutJob = LayoutJob::default();
+    job.append(
+        if breakpoint { "●" } else { " " },
+        0.0,
+        TextFormat {
+            color: Color32::RED,
+            ..format.clone()
+        },
+    );
+    job.append(if at_pc { "> " } else { "  " }, 0.0, format.clone());
+    job.append(
+        &format!("{:04X}  {:8} ", line.addr, line.hex()),
+        0.0,
+        color(weak),
+    );
+    job.append(
+        &format!("{}{} ", line.undoc_marker(), line.opcode.mnemonic),
+        0.0,
+        color(ui.visuals().strong_text_color()),
+    );
+    job.append(&line.operand, 0.0, color(operand_color(ui, &line.operand)));
+    if let Some(comment) = &line.comment {
+        job.append(&format!("  ; {}", comment), 0.0, color(weak));
+    }
+    job
+}
+
+impl App {
+    pub fn cpu_debugger(&mut self, ctx: &egui::Context) {
+        ctx.show_viewport_immediate(
+            ViewportId::from_hash_of("cpu_debugger"),
+            ViewportBuilder::default()
+                .with_inner_size([560.0, 640.0])
+                .with_title("CPU Debugger"),
+            |ctx: &egui::Context, _class: egui::ViewportClass| {
+                let mut event: Option<NESEvent> = None;
+
+                egui::TopBottomPanel::top("cpu_debugger_controls").show(ctx, |ui: &mut Ui| {
+                    ui.horizontal(|ui: &mut Ui| {
+                        let run_label: &str = match self.is_paused {
+                            true => "Run",
+                            false => "Pause",
+                        };
+                        if ui
+                            .add_enabled(
+                                self.nes_state != NESState::Stopped,
+                                egui::Button::new(run_label),
+                            )
+                            .clicked()
+                        {
+                            self.is_paused = !self.is_paused;
+                            event = Some(match self.is_paused {
+                                true => NESEvent::Pause,
+                                false => NESEvent::Resume,
+                            });
+                        }
+                        ui.separator();
+
+                        ui.add_enabled_ui(self.nes_state == NESState::Paused, |ui: &mut Ui| {
+                            for (label, hover, kind) in [
+                                ("Step", "Run one instruction", StepKind::Instructions(1)),
+                                ("Scanline", "Run to the next scanline", StepKind::Scanline),
+                                ("Frame", "Run to the next vblank", StepKind::Frame),
+                            ] {
+                                if ui.button(label).on_hover_text(hover).clicked() {
+                                    event = Some(NESEvent::Step(kind));
+                                }
+                            }
+                            if let Some(addr) = self.cpu_debugger.cursor
+                                && ui
+                                    .button("Run to cursor")
+                                    .on_hover_text(format!("Run until PC is ${:04X}", addr))
+                                    .clicked()
+                            {
+                                event = Some(NESEvent::Step(StepKind::RunTo(addr)));
+                            }
+                        });
+
+                        ui.with_layout(
+                            egui::Layout::right_to_left(egui::Align::Center),
+                            |ui: &mut Ui| {
+                                ui.label(format!("{:?}", self.nes_state));
+                            },
+                        );
+                    });
+                });
+
+                egui::TopBottomPanel::bottom("cpu_debugger_breakpoints")
+                    .resizable(true)
+                    .show(ctx, |ui: &mut Ui| {
+                        ui.strong("Breakpoints");
+                        if let Some(list) = self
+                            .cpu_debugger
+                            .breakpoints
+                            .show(ui, &self.nes_manager.breakpoints)
+                        {
+                            event = Some(NESEvent::SetBreakpoints(list));
+                        }
+                    });
+
+                let snapshot: Option<&CpuSnapshot> = self.nes_manager.cpu_snapshot.as_ref();
+                let breakpoint_list: &[Breakpoint] = &self.nes_manager.breakpoints;
+                egui::SidePanel::right("cpu_debugger_state")
+                    .resizable(false)
+                    .show(ctx, |ui: &mut Ui| match snapshot {
+                        Some(snapshot) => cpu_state(ui, snapshot),
+                        None => {
+                            ui.label("No CPU data yet");
+                        }
+                    });
+
+                egui::CentralPanel::default().show(ctx, |ui: &mut Ui| {
+                    let Some(snapshot) = snapshot else {
+                        ui.label("Start a ROM to see its code");
+                        return;
+                    };
+                    let debugger: &mut CpuDebugger = &mut self.cpu_debugger;
+                    let pc_moved: bool = debugger.last_pc != Some(snapshot.program_counter);
+                    debugger.last_pc = Some(snapshot.program_counter);
+
+                    egui::ScrollArea::both()
+                        .auto_shrink(false)
+                        .show(ui, |ui: &mut Ui| {
+                            let lines = snapshot
+                                .history
+                                .iter()
+                                .map(|line: &Disassembly| (line, true))
+                                .chain(
+                                    snapshot
+                                        .upcoming
+                                        .iter()
+                                        .map(|line: &Disassembly| (line, false)),
+                                );
+                            for (index, (line, faded)) in lines.enumerate() {
+                                let at_pc: bool = index == snapshot.history.len();
+                                if let Some(label) = &line.label {
+                                    ui.monospace(RichText::new(format!("{}:", label)).strong());
+                                }
+                                let response: egui::Response = ui
+                                    .selectable_label(
+                                        debugger.cursor == Some(line.addr),
+                                        disassembly_line(
+                                            ui,
+                                            line,
+                                            at_pc,
+                                            faded,
+                                            breakpoints::has_execute(breakpoint_list, line.addr),
+                                        ),
+                                    )
+                                    .on_hover_text("Double click to toggle a breakpoint");
+                                if response.double_clicked() {
+                                    event = Some(NESEvent::SetBreakpoints(
+                                        breakpoints::toggle_execute(breakpoint_list, line.addr),
+                                    ));
+                                } else if response.clicked() {
+                                    debugger.cursor = match debugger.cursor == Some(line.addr) {
+                                        true => None,
+                                        false => Some(line.addr),
+                                    };
+                                }
+                                if at_pc && pc_moved {
+                                    response.scroll_to_me(Some(egui::Align::Center));
+                                }
+                            }
+                        });
+                });
+
+                if let Some(event) = event {
+                    self.new_event(AppEvent::NES(event));
+                }
+                if ctx.input(|i: &egui::InputState| i.viewport().close_requested()) {
+                    self.cpu_debugger.open = false;
+                }
+            },
+        );
+    }
+}
+
+fn cpu_state(ui: &mut Ui, snapshot: &CpuSnapshot) {
+    egui::Grid::new("cpu_registers").show(ui, |ui: &mut Ui| {
+        for (name, value) in [
+            ("PC", format!("${:04X}", snapshot.program_counter)),
+            ("A", format!("${:02X}", snapshot.accumulator)),
+            ("X", format!("${:02X}", snapshot.index_x)),
+            ("Y", format!("${:02X}", snapshot.index_y)),
+            ("SP", format!("${:02X}", snapshot.stack_pointer)),
+            ("P", format!("${:02X}", snapshot.status.bits())),
+        ] {
+            ui.label(name);
+            ui.monospace(value);
+            ui.end_row();
+        }
+
+        ui.label("");
+        ui.horizontal(|ui: &mut Ui| {
+            ui.spacing_mut().item_spacing.x = 2.0;
+            for (flag, name) in FLAGS {
+                let text: RichText = RichText::new(name).monospace();
+                match snapshot.status.contains(flag) {
+                    true => ui.label(text.strong()),
+                    false => ui.label(text.weak()),
+                };
+            }
+        });
+        ui.end_row();
+    });
+    ui.separator();
+
+    egui::Grid::new("cpu_timing").show(ui, |ui: &mut Ui| {
+        for (name, value) in [
+            ("Cycle", snapshot.cycles),
+            ("Scanline", snapshot.scanline),
+            ("Dot", snapshot.dot),
+            ("Frame", snapshot.frame),
+        ] {
+            ui.label(name);
+            ui.monospace(value.to_string());
+            ui.end_row();
+        }
+    });
+    ui.separator();
+
+    ui.label("Stack");
+    egui::ScrollArea::vertical()
+        .id_salt("cpu_stack")
+        .show(ui, |ui: &mut Ui| {
+            let top: usize = STACK_PAGE + snapshot.stack_pointer as usize + 1;
+            for (offset, value) in snapshot.stack.iter().enumerate() {
+                ui.monospace(format!("${:04X}  {:02X}", top + offset, value));
+            }
+        });
+}
@@ -0,0 +1,454 @@
+//! Hex editor for every kind of memory in the NES
+//!
+//! Works like the CPU debugger: the NES thread sends a [`MemorySnapshot`] of the shown region
+//! every frame while the window is open, and again whenever it pauses or finishes a step.
+//! Edits go back to the NES thread as pokes.
+
+use crate::{NESState, app::App, events::AppEvent, prelude::*};
+use egui::{
+    Color32, Key, RichText, Sense, TextStyle, Ui, ViewportBuilder, ViewportId, text::LayoutJob,
+};
+use nes::{NES, tools::NESAccess};
+
+const BYTES_PER_ROW: usize = 16;
+/// How long changed bytes stay highlighted
+const HIGHLIGHT_SECS: f64 = 1.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryRegion {
+    /// `$0000-$FFFF` as the CPU sees it
+    CpuBus,
+    InternalRam,
+    PrgRom,
+    PrgRam,
+    Chr,
+    /// The PPU's nametable VRAM
+    Vram,
+    Oam,
+    Palette,
+}
+
+impl MemoryRegion {
+    #[rustfmt::skip]
+    const ALL: [(MemoryRegion, &str); 8] = [
+        (MemoryRegion::CpuBus, "CPU Bus"), (MemoryRegion::InternalRam, "Internal RAM"),
+        (MemoryRegion::PrgRom, "PRG-ROM"), (MemoryRegion::PrgRam, "PRG-RAM"), (MemoryRegion::Chr, "CHR"),
+        (MemoryRegion::Vram, "PPU VRAM"), (MemoryRegion::Oam, "OAM"), (MemoryRegion::Palette, "Palette RAM"),
+    ];
+
+    pub fn name(&self) -> &'static str {
+        MemoryRegion::ALL
+            .iter()
+            .find(|(region, _): &&(MemoryRegion, &str)| region == self)
+            .map_or("", |(_, name): &(MemoryRegion, &str)| name)
+    }
+
+    /// The address of the first byte, offsets into storage start at 0
+    pub fn base(&self) -> usize {
+        match self {
+            MemoryRegion::Palette => 0x3F00,
+            _ => 0x0000,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MemorySnapshot {
+    pub region: MemoryRegion,
+    pub data: Vec<u8>,
+}
+
+impl MemorySnapshot {
+    pub fn capture(nes: &NES, region: MemoryRegion) -> Self {
+        let data: Vec<u8> = match region {
+            MemoryRegion::CpuBus => (0..=u16::MAX)
+                .map(|addr: u16| nes.bus().peek(addr))
+                .collect(),
+            MemoryRegion::InternalRam => nes.memory().cpu_vram.to_vec(),
+            MemoryRegion::PrgRom => nes.memory().prg_rom.clone(),
+            MemoryRegion::PrgRam => nes.memory().prg_ram.clone(),
+            MemoryRegion::Chr => nes.memory().chr_mem.clone(),
+            MemoryRegion::Vram => nes.ppu().vram.to_vec(),
+            MemoryRegion::Oam => nes.ppu().oam_data.to_vec(),
+            MemoryRegion::Palette => nes.ppu().palette_table.to_vec(),
+        };
+        MemorySnapshot { region, data }
+    }
+}
+
+/// Writes straight to storage, so registers never see the write. `offset` counts from the start
+/// of the region, pokes to registers on the CPU bus are dropped.
+pub fn poke(nes: &NES, region: MemoryRegion, offset: usize, value: u8) {
+    let set = |bytes: &mut [u8], index: usize| -> bool {
+        match bytes.get_mut(index) {
+            Some(byte) => {
+                *byte = value;
+                true
+            }
+            None => false,
+        }
+    };
+
+    let written: bool = match region {
+        MemoryRegion::CpuBus => nes.bus_mut().poke(offset as u16, value),
+        MemoryRegion::InternalRam => set(&mut nes.memory_mut().cpu_vram, offset),
+        MemoryRegion::PrgRom => set(&mut nes.memory_mut().prg_rom, offset),
+        MemoryRegion::PrgRam => set(&mut nes.memory_mut().prg_ram, offset),
+        MemoryRegion::Chr => set(&mut nes.memory_mut().chr_mem, offset),
+        MemoryRegion::Vram => set(&mut nes.ppu_mut().vram, offset),
+        MemoryRegion::Oam => set(&mut nes.ppu_mut().oam_data, offset),
+        MemoryRegion::Palette => set(&mut nes.ppu_mut().palette_table, offset),
+    };
+    if !written {
+        warn!(
+            "Can't write to {} ${:04X}",
+            region.name(),
+            region.base() + offset
+        );
+    }
+}
+
+/// Hex bytes with `??` as a wildcard, e.g. `A9 ?? 8D`
+fn parse_pattern(text: &str) -> Result<Vec<Option<u8>>, String> {
+    let pattern: Vec<Option<u8>> = text
+        .split_whitespace()
+        .map(|byte: &str| match byte {
+            "?" | "??" => Ok(None),
+            _ => u8::from_str_radix(byte.strip_prefix('$').unwrap_or(byte), 16)
+                .map(Some)
+                .map_err(|_| format!("'{}' isn't a hex byte", byte)),
+        })
+        .collect::<Result<_, String>>()?;
+    match pattern.is_empty() {
+        true => Err("Nothing to search for".to_string()),
+        false => Ok(pattern),
+    }
+}
+
+/// The first match after `start`, wrapping around at the end
+fn find_pattern(data: &[u8], pattern: &[Option<u8>], start: usize) -> Option<usize> {
+    let matches = |offset: &usize| -> bool {
+        data[*offset..]
+            .iter()
+            .zip(pattern)
+            .all(|(byte, wanted): (&u8, &Option<u8>)| {
+                wanted.is_none_or(|wanted: u8| *byte == wanted)
+            })
+    };
+    let last: usize = data.len().checked_sub(pattern.len())?;
+    (start..=last).chain(0..start.min(last + 1)).find(matches)
+}
+
+pub struct MemoryEditor {
+    pub open: bool,
+    pub region: MemoryRegion,
+    data: Vec<u8>,
+    /// When each byte last changed, in `egui` time
+    changed_at: Vec<f64>,
+    cursor: Option<usize>,
+    /// The byte being typed over, and what has been typed so far
+    editing: Option<(usize, String)>,
+    goto: String,
+    search: String,
+    message: Option<String>,
+    scroll_to: Option<usize>,
+}
+
+impl Default for MemoryEditor {
+    fn default() -> Self {
+        MemoryEditor {
+            open: false,
+            region: MemoryRegion::CpuBus,
+            data: Vec::new(),
+            changed_at: Vec::new(),
+            cursor: None,
+            editing: None,
+            goto: String::new(),
+            search: String::new(),
+            message: None,
+            scroll_to: None,
+        }
+    }
+}
+
+impl MemoryEditor {
+    /// Takes in a new snapshot, marking the bytes that changed since the last one
+    pub fn update(&mut self, snapshot: MemorySnapshot, now: f64) {
+        if snapshot.region != self.region {
+            return;
+        }
+        match snapshot.data.len() == self.data.len() {
+            true => {
+                for (index, (old, new)) in self.data.iter().zip(&snapshot.data).enumerate() {
+                    if old != new {
+                        self.changed_at[index] = now;
+                    }
+                }
+            }
+            false => self.changed_at = vec![f64::NEG_INFINITY; snapshot.data.len()],
+        }
+        self.data = snapshot.data;
+    }
+
+    fn set_region(&mut self, region: MemoryRegion) {
+        if region != self.region {
+            *self = MemoryEditor {
+                open: self.open,
+                region,
+                search: std::mem::take(&mut self.search),
+                ..MemoryEditor::default()
+            };
+        }
+    }
+
+    fn go_to(&mut self, offset: usize) {
+        self.cursor = Some(offset);
+        self.scroll_to = Some(offset);
+        self.editing = None;
+    }
+
+    fn controls(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui: &mut Ui| {
+            let mut region: MemoryRegion = self.region;
+            egui::ComboBox::from_id_salt("memory_region")
+                .selected_text(region.name())
+                .show_ui(ui, |ui: &mut Ui| {
+                    for (option, name) in MemoryRegion::ALL {
+                        ui.selectable_value(&mut region, option, name);
+                    }
+                });
+            self.set_region(region);
+            ui.weak(format!("{} bytes", self.data.len()));
+            ui.separator();
+
+            let goto: egui::Response = ui.add(
+                egui::TextEdit::singleline(&mut self.goto)
+                    .hint_text("Go to $0000")
+                    .desired_width(80.0),
+            );
+            if goto.lost_focus() && ui.input(|i: &egui::InputState| i.key_pressed(Key::Enter)) {
+                let text: &str = self.goto.trim();
+                let addr: Option<usize> =
+                    usize::from_str_radix(text.strip_prefix('$').unwrap_or(text), 16).ok();
+                match addr.and_then(|addr: usize| addr.checked_sub(self.region.base())) {
+                    Some(offset) if offset < self.data.len() => {
+                        self.go_to(offset);
+                        self.message = None;
+                    }
+                    _ => self.message = Some(format!("'{}' isn't in {}", text, self.region.name())),
+                }
+            }
+        });
+
+        ui.horizontal(|ui: &mut Ui| {
+            let search: egui::Response = ui.add(
+                egui::TextEdit::singleline(&mut self.search)
+                    .hint_text("A9 ?? 8D")
+                    .desired_width(160.0),
+            );
+            let enter: bool =
+                search.lost_focus() && ui.input(|i: &egui::InputState| i.key_pressed(Key::Enter));
+            if ui.button("Find next").clicked() || enter {
+                match parse_pattern(&self.search) {
+                    Ok(pattern) => {
+                        let start: usize = self.cursor.map_or(0, |cursor: usize| cursor + 1);
+                        match find_pattern(&self.data, &pattern, start) {
+                            Some(offset) => {
+                                self.go_to(offset);
+                                self.message = None;
+                            }
+                            None => self.message = Some("Not found".to_string()),
+                        }
+                    }
+                    Err(err) => self.message = Some(err),
+                }
+            }
+            if let Some(message) = &self.message {
+                ui.colored_label(Color32::RED, message);
+            }
+        });
+    }
+
+    /// Returns the edit to poke into the NES: the offset and the new value
+    fn show(&mut self, ui: &mut Ui) -> Option<(usize, u8)> {
+        let mut poke: Option<(usize, u8)> = None;
+        let now: f64 = ui.input(|i: &egui::InputState| i.time);
+        let row_height: f32 = ui.text_style_height(&TextStyle::Monospace);
+        let highlight: Color32 = ui.visuals().selection.bg_fill;
+        let weak: Color32 = ui.visuals().weak_text_color();
+        let base: usize = self.region.base();
+
+        let mut scroll_area: egui::ScrollArea = egui::ScrollArea::vertical().auto_shrink(false);
+        if let Some(offset) = self.scroll_to.take() {
+            let row: usize = offset / BYTES_PER_ROW;
+            let spacing: f32 = ui.spacing().item_spacing.y;
+            scroll_area = scroll_area
+                .vertical_scroll_offset((row.saturating_sub(4)) as f32 * (row_height + spacing));
+        }
+
+        let rows: usize = self.data.len().div_ceil(BYTES_PER_ROW);
+        scroll_area.show_rows(
+            ui,
+            row_height,
+            rows,
+            |ui: &mut Ui, rows: std::ops::Range<usize>| {
+                for row in rows {
+                    ui.horizontal(|ui: &mut Ui| {
+                        ui.spacing_mut().item_spacing.x = 4.0;
+                        ui.label(
+                            RichText::new(format!("{:04X}:", base + row * BYTES_PER_ROW))
+                                .monospace()
+                                .color(weak),
+                        );
+
+                        let start: usize = row * BYTES_PER_ROW;
+                        let end: usize = (start + BYTES_PER_ROW).min(self.data.len());
+                        for offset in start..end {
+                            if offset == start + BYTES_PER_ROW / 2 {
+                                ui.add_space(4.0);
+                            }
+                            if let Some(edit) = self.edit_byte(ui, offset) {
+                                poke = Some(edit);
+                                continue;
+                            }
+
+                            let mut text: RichText =
+                                RichText::new(format!("{:02X}", self.data[offset])).monospace();
+                            let age: f64 = now - self.changed_at[offset];
+                            if age < HIGHLIGHT_SECS {
+                                let fade: f32 = 1.0 - (age / HIGHLIGHT_SECS) as f32;
+                                text = text.background_color(
+                                    Color32::from_rgb(0xD0, 0x60, 0x20).gamma_multiply(fade),
+                                );
+                            }
+                            if self.cursor == Some(offset) {
+                                text = text.background_color(highlight);
+                            }
+
+                            let response: egui::Response = ui
+                                .add(egui::Label::new(text).sense(Sense::click()))
+                                .on_hover_text(format!("${:04X}", base + offset));
+                            if response.clicked() {
+                                self.cursor = Some(offset);
+                                self.editing = Some((offset, String::new()));
+                            }
+                        }
+                        ui.add_space(8.0);
+
+                        let mut ascii: LayoutJob = LayoutJob::default();
+                        for offset in start..end {
+                            let char: char = match self.data[offset] {
+                                byte @ 0x21..0x7E => byte as char,
+                                _ => '.',
+                            };
+                            let color: Color32 = match self.cursor == Some(offset) {
+                                true => ui.visuals().strong_text_color(),
+                                false => weak,
+                            };
+                            ascii.append(
+                                &char.to_string(),
+                                0.0,
+                                egui::TextFormat::simple(
+                                    TextStyle::Monospace.resolve(ui.style()),
+                                    color,
+                                ),
+                            );
+                        }
+                        ui.label(ascii);
+                    });
+                }
+            },
+        );
+
+        if let Some(cursor) = self.cursor
+            && let Some(value) = self.data.get(cursor)
+        {
+            ui.separator();
+            ui.monospace(format!(
+                "{} ${:04X} = ${:02X} ({})",
+                self.region.name(),
+                base + cursor,
+                value,
+                value
+            ));
+        }
+        poke
+    }
+
+    /// Shows the text box for the byte being edited, returns the new value once two hex digits
+    /// were typed. Editing then moves on to the next byte.
+    fn edit_byte(&mut self, ui: &mut Ui, offset: usize) -> Option<(usize, u8)> {
+        let (editing, text) = self.editing.as_mut()?;
+        if *editing != offset {
+            return None;
+        }
+
+        let response: egui::Response = ui.add(
+            egui::TextEdit::singleline(text)
+                .id_salt("memory_edit")
+                .font(TextStyle::Monospace)
+                .char_limit(2)
+                .desired_width(ui.text_style_height(&TextStyle::Monospace))
+                .margin(0.0),
+        );
+        response.request_focus();
+        if ui.input(|i: &egui::InputState| i.key_pressed(Key::Escape)) {
+            self.editing = None;
+            return None;
+        }
+
+        let value: Option<u8> = (text.len() == 2)
+            .then(|| u8::from_str_radix(text, 16).ok())
+            .flatten();
+        match value {
+            Some(value) => {
+                self.data[offset] = value;
+                let next: usize = offset + 1;
+                self.editing = (next < self.data.len()).then(|| (next, String::new()));
+                self.cursor = Some(next.min(self.data.len() - 1));
+                Some((offset, value))
+            }
+            None => {
+                if text.len() == 2 {
+                    text.clear();
+                }
+                None
+            }
+        }
+    }
+}
+
+impl App {
+    pub fn memory_editor(&mut self, ctx: &egui::Context) {
+        ctx.show_viewport_immediate(
+            ViewportId::from_hash_of("memory_editor"),
+            ViewportBuilder::default()
+                .with_inner_size([600.0, 520.0])
+                .with_title("Memory Editor"),
+            |ctx: &egui::Context, _class: egui::ViewportClass| {
+                let mut poke: Option<(usize, u8)> = None;
+
+                egui::TopBottomPanel::top("memory_editor_controls")
+                    .show(ctx, |ui: &mut Ui| self.memory_editor.controls(ui));
+                egui::CentralPanel::default().show(ctx, |ui: &mut Ui| {
+                    if self.nes_state == NESState::Stopped {
+                        ui.label("Start a ROM to see its memory");
+                        return;
+                    }
+                    poke = self.memory_editor.show(ui);
+                });
+
+                if let Some((offset, value)) = poke {
+                    self.new_event(AppEvent::NES(crate::NESEvent::PokeMemory(
+                        self.memory_editor.region,
+                        offset,
+                        value,
+                    )));
+                }
+                if ctx.input(|i: &egui::InputState| i.viewport().close_requested()) {
+                    self.memory_editor.open = false;
+                }
+            },
+        );
+    }
+}
@@ -0,0 +1,80 @@
+//! Windows for looking inside the running NES
+
+pub mod breakpoints;
+pub mod cpu;
+pub mod memory;
+pub mod nametable;
+pub mod tiles;
+
+use egui::Color32;
+use nes::{
+    NES, VBLANK_SCANLINE,
+    ppu::{palette, renderer::RGB},
+    tools::NESAccess,
+};
+
+/// The 2-bit color of every pixel in a 16 byte tile, row by row
+// https://www.nesdev.org/wiki/PPU_pattern_tables
+pub fn decode_tile(tile: &[u8]) -> [[u8; 8]; 8] {
+    std::array::from_fn(|y: usize| {
+        let (lo, hi): (u8, u8) = (tile[y], tile[y + 8]);
+        std::array::from_fn(|x: usize| ((hi >> (7 - x)) & 1) << 1 | ((lo >> (7 - x)) & 1))
+    })
+}
+
+/// How the NTSC PPU shows a palette RAM entry
+pub fn nes_color(entry: u8) -> Color32 {
+    let RGB(r, g, b) = palette::NTSC[(entry & 0x3F) as usize];
+    Color32::from_rgb(r, g, b)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepKind {
+    Instructions(usize),
+    Scanline,
+    /// Runs until the next vblank starts
+    Frame,
+    /// Runs until the CPU is about to run the instruction at this address
+    RunTo(u16),
+}
+
+/// Keeps a paused NES running until a step request is done
+#[derive(Debug)]
+pub struct Stepper {
+    kind: StepKind,
+    steps_left: usize,
+    scanline: usize,
+    in_vblank: bool,
+}
+
+impl Stepper {
+    pub fn new(kind: StepKind, nes: &NES) -> Self {
+        let scanline: usize = nes.ppu().scanline;
+        Stepper {
+            kind,
+            steps_left: match kind {
+                StepKind::Instructions(steps) => steps,
+                _ => 0,
+            },
+            scanline,
+            in_vblank: scanline >= VBLANK_SCANLINE,
+        }
+    }
+
+    /// Called after every instruction, returns whether the step is done
+    pub fn finished(&mut self, nes: &NES) -> bool {
+        match self.kind {
+            StepKind::Instructions(_) => {
+                self.steps_left = self.steps_left.saturating_sub(1);
+                self.steps_left == 0
+            }
+            StepKind::Scanline => nes.ppu().scanline != self.scanline,
+            StepKind::Frame => {
+                let was_in_vblank: bool = self.in_vblank;
+                self.in_vblank = nes.ppu().scanline >= VBLANK_SCANLINE;
+                self.in_vblank && !was_in_vblank
+            }
+            StepKind::RunTo(addr) => nes.cpu.program_counter == addr,
+        }
+    }
+}
@@ -0,0 +1,266 @@
+//! Nametable viewer showing all four nametables and where the screen is scrolled to
+//!
+//! The NES thread sends a [`NametableSnapshot`] every frame while the window is open, taken as
+//! the PPU reaches the chosen scanline, so mid-frame scroll splits can be looked at one by one.
+
+use super::{decode_tile, nes_color};
+use crate::{NESState, app::App};
+use egui::{
+    Color32, ColorImage, Pos2, Rect, Sense, Stroke, StrokeKind, TextureHandle, TextureOptions, Ui,
+    Vec2, ViewportBuilder, ViewportId,
+};
+use nes::{
+    NES, SCREEN_HEIGHT, SCREEN_WIDTH, VBLANK_SCANLINE,
+    ppu::{PPU, registers::AddrRegister},
+    tools::NESAccess,
+};
+use std::cell::Ref;
+
+/// The pre-render scanline is the last one
+const LAST_SCANLINE: usize = 261;
+const WIDTH: usize = 2 * SCREEN_WIDTH;
+const HEIGHT: usize = 2 * SCREEN_HEIGHT;
+
+#[derive(Debug, Clone)]
+pub struct NametableSnapshot {
+    pub scanline: usize,
+    /// `$2000-$2FFF` as the PPU sees it, with mirroring applied
+    pub nametables: Vec<u8>,
+    /// The background pattern table picked in PPUCTRL
+    pub pattern_table: Vec<u8>,
+    pub pattern_table_addr: u16,
+    pub palette: [u8; 32],
+    /// Top-left corner of the screen in the 512x480 picture of all four nametables
+    pub scroll: (usize, usize),
+}
+
+/// Where the screen starts in the picture of all four nametables, with the horizontal and
+/// vertical scroll read from copies of `v` or `t`
+// https://www.nesdev.org/wiki/PPU_scrolling
+fn scroll_position(horizontal: AddrRegister, vertical: AddrRegister, fine_x: u8) -> (usize, usize) {
+    let x: usize = (horizontal.get_nametable() & 1) as usize * SCREEN_WIDTH
+        + horizontal.get_coarse_x() as usize * 8
+        + fine_x as usize;
+    let y: usize = (vertical.get_nametable() >> 1) as usize * SCREEN_HEIGHT
+        + vertical.get_coarse_y() as usize * 8
+        + vertical.get_fine_y() as usize;
+    (x, y % HEIGHT)
+}
+
+impl NametableSnapshot {
+    pub fn capture(nes: &NES) -> Self {
+        let ppu: Ref<PPU> = nes.ppu();
+        let pattern_table_addr: u16 = ppu.ctrl.background_pattern_addr();
+
+        // While a visible line is drawn, `v` holds the line's vertical scroll and `t` the
+        // horizontal scroll it was reloaded with. Otherwise `t` is what the next frame starts at.
+        let scroll: (usize, usize) = match ppu.mask.rendering() && ppu.scanline < SCREEN_HEIGHT {
+            true => {
+                let (x, y): (usize, usize) =
+                    scroll_position(ppu.temp_addr, ppu.vram_addr, ppu.scroll_fine_x);
+                (x, (y + HEIGHT - ppu.scanline) % HEIGHT)
+            }
+            false => scroll_position(ppu.temp_addr, ppu.temp_addr, ppu.scroll_fine_x),
+        };
+
+        NametableSnapshot {
+            scanline: ppu.scanline,
+            nametables: (0x2000..0x3000).map(|addr: u16| ppu.peek(addr)).collect(),
+            pattern_table: (0..0x1000)
+                .map(|offset: u16| ppu.peek(pattern_table_addr + offset))
+                .collect(),
+            pattern_table_addr,
+            palette: ppu.palette_table,
+            scroll,
+        }
+    }
+
+    /// Tile under the pixel at `x`, `y` in the picture of all four nametables
+    pub fn tile_at(&self, x: usize, y: usize) -> TileInfo {
+        let nametable: usize = (y / SCREEN_HEIGHT) * 2 + x / SCREEN_WIDTH;
+        let column: usize = (x % SCREEN_WIDTH) / 8;
+        let row: usize = (y % SCREEN_HEIGHT) / 8;
+        let offset: usize = nametable * 0x0400 + row * 32 + column;
+        // https://www.nesdev.org/wiki/PPU_attribute_tables
+        let attribute_offset: usize = nametable * 0x0400 + 0x03C0 + (row / 4) * 8 + column / 4;
+        let attribute: u8 = self.nametables[attribute_offset];
+        let shift: usize = ((row % 4) / 2) * 4 + ((column % 4) / 2) * 2;
+
+        TileInfo {
+            addr: 0x2000 + offset as u16,
+            tile: self.nametables[offset],
+            attribute_addr: 0x2000 + attribute_offset as u16,
+            attribute,
+            palette: (attribute >> shift) & 0b11,
+            column,
+            row,
+        }
+    }
+
+    pub fn image(&self) -> ColorImage {
+        let mut pixels: Vec<Color32> = vec![Color32::BLACK; WIDTH * HEIGHT];
+        for y in (0..HEIGHT).step_by(8) {
+            for x in (0..WIDTH).step_by(8) {
+                let info: TileInfo = self.tile_at(x, y);
+                let tile: [[u8; 8]; 8] =
+                    decode_tile(&self.pattern_table[info.tile as usize * 16..][..16]);
+                for (fine_y, row) in tile.iter().enumerate() {
+                    for (fine_x, color) in row.iter().enumerate() {
+                        // Color 0 of every palette is the backdrop at $3F00
+                        let index: usize = match color {
+                            0 => 0,
+                            _ => (info.palette * 4 + color) as usize,
+                        };
+                        pixels[(y + fine_y) * WIDTH + x + fine_x] = nes_color(self.palette[index]);
+                    }
+                }
+            }
+        }
+        ColorImage::new([WIDTH, HEIGHT], pixels)
+    }
+}
+
+pub struct TileInfo {
+    pub addr: u16,
+    pub tile: u8,
+    pub attribute_addr: u16,
+    pub attribute: u8,
+    /// Which of the four background palettes the attribute picks for the tile
+    pub palette: u8,
+    pub column: usize,
+    pub row: usize,
+}
+
+pub struct NametableViewer {
+    pub open: bool,
+    /// The scanline snapshots are taken at
+    pub scanline: usize,
+    show_scroll: bool,
+    snapshot: Option<NametableSnapshot>,
+    texture: Option<TextureHandle>,
+}
+
+impl Default for NametableViewer {
+    fn default() -> Self {
+        NametableViewer {
+            open: false,
+            scanline: VBLANK_SCANLINE,
+            show_scroll: true,
+            snapshot: None,
+            texture: None,
+        }
+    }
+}
+
+impl NametableViewer {
+    pub fn update(&mut self, ctx: &egui::Context, snapshot: NametableSnapshot) {
+        let image: ColorImage = snapshot.image();
+        match self.texture.as_mut() {
+            Some(texture) => texture.set(image, TextureOptions::NEAREST),
+            None => {
+                self.texture = Some(ctx.load_texture("nametables", image, TextureOptions::NEAREST))
+            }
+        }
+        self.snapshot = Some(snapshot);
+    }
+
+    fn controls(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui: &mut Ui| {
+            ui.label("Capture at scanline");
+            ui.add(egui::DragValue::new(&mut self.scanline).range(0..=LAST_SCANLINE));
+            if ui.button("Start of vblank").clicked() {
+                self.scanline = VBLANK_SCANLINE;
+            }
+            ui.separator();
+            ui.checkbox(&mut self.show_scroll, "Show scroll");
+        });
+    }
+
+    fn show(&mut self, ui: &mut Ui) {
+        let (Some(snapshot), Some(texture)) = (&self.snapshot, &self.texture) else {
+            ui.label("Waiting for the NES...");
+            return;
+        };
+
+        // Leaves room for the line below the picture
+        let height: f32 = ui.available_height() - 2.0 * ui.spacing().interact_size.y;
+        let scale: f32 = (ui.available_width() / WIDTH as f32)
+            .min(height / HEIGHT as f32)
+            .max(0.5);
+        let size: Vec2 = Vec2::new(WIDTH as f32, HEIGHT as f32) * scale;
+        let response: egui::Response = ui.add(
+            egui::Image::from_texture(egui::load::SizedTexture::new(texture.id(), size))
+                .sense(Sense::hover()),
+        );
+        let rect: Rect = response.rect;
+
+        if self.show_scroll {
+            // The screen wraps around to the other side of the picture
+            let painter: egui::Painter = ui.painter_at(rect);
+            let (x, y): (usize, usize) = snapshot.scroll;
+            for dx in [0.0, -(WIDTH as f32)] {
+                for dy in [0.0, -(HEIGHT as f32)] {
+                    let min: Pos2 = rect.min + Vec2::new(x as f32 + dx, y as f32 + dy) * scale;
+                    let screen: Rect = Rect::from_min_size(
+                        min,
+                        Vec2::new(SCREEN_WIDTH as f32, SCREEN_HEIGHT as f32) * scale,
+                    );
+                    painter.rect_stroke(
+                        screen,
+                        0.0,
+                        Stroke::new(2.0_f32, Color32::from_rgb(0xF1, 0xC2, 0x32)),
+                        StrokeKind::Inside,
+                    );
+                }
+            }
+        }
+
+        if let Some(pos) = response.hover_pos() {
+            let x: usize = (((pos.x - rect.min.x) / scale) as usize).min(WIDTH - 1);
+            let y: usize = (((pos.y - rect.min.y) / scale) as usize).min(HEIGHT - 1);
+            let info: TileInfo = snapshot.tile_at(x, y);
+            response.on_hover_text_at_pointer(format!(
+                "Tile ${:02X} at ({}, {})\nPPU address ${:04X}\nAttribute ${:02X} at ${:04X}, palette {}\nPattern ${:04X}",
+                info.tile,
+                info.column,
+                info.row,
+                info.addr,
+                info.attribute,
+                info.attribute_addr,
+                info.palette,
+                snapshot.pattern_table_addr + info.tile as u16 * 16,
+            ));
+        }
+
+        ui.label(format!(
+            "Captured at scanline {}, scroll X {} Y {}, background tiles at ${:04X}",
+            snapshot.scanline, snapshot.scroll.0, snapshot.scroll.1, snapshot.pattern_table_addr
+        ));
+    }
+}
+
+impl App {
+    pub fn nametable_viewer(&mut self, ctx: &egui::Context) {
+        ctx.show_viewport_immediate(
+            ViewportId::from_hash_of("nametable_viewer"),
+            ViewportBuilder::default()
+                .with_inner_size([560.0, 600.0])
+                .with_title("Nametable Viewer"),
+            |ctx: &egui::Context, _class: egui::ViewportClass| {
+                egui::TopBottomPanel::top("nametable_viewer_controls")
+                    .show(ctx, |ui: &mut Ui| self.nametable_viewer.controls(ui));
+                egui::CentralPanel::default().show(ctx, |ui: &mut Ui| {
+                    if self.nes_state == NESState::Stopped {
+                        ui.label("Start a ROM to see its nametables");
+                        return;
+                    }
+                    self.nametable_viewer.show(ui);
+                });
+
+                if ctx.input(|i: &egui::InputState| i.viewport().close_requested()) {
+                    self.nametable_viewer.open = false;
+                }
+            },
+        );
+    }
+}
@@ -0,0 +1,283 @@
+//! Tile viewer with both pattern tables and the palette RAM
+//!
+//! The NES thread sends a [`TileSnapshot`] every frame while the window is open, and again
+//! whenever it pauses or finishes a step. Both can be saved as PNGs.
+
+use super::{decode_tile, nes_color};
+use crate::{NESState, app::App, prelude::*};
+use egui::{
+    Color32, ColorImage, RichText, Sense, Stroke, TextureHandle, TextureOptions, Ui, Vec2,
+    ViewportBuilder, ViewportId,
+};
+use nes::{NES, ppu::PPU, tools::NESAccess};
+use std::{
+    cell::Ref,
+    path::{Path, PathBuf},
+};
+
+/// A pattern table is 16x16 tiles
+const TABLE_SIZE: usize = 128;
+/// Size of every palette entry in exported palettes
+const SWATCH_SIZE: usize = 16;
+
+#[rustfmt::skip]
+const PALETTES: [&str; 8] = [
+    "Background 0", "Background 1", "Background 2", "Background 3",
+    "Sprite 0", "Sprite 1", "Sprite 2", "Sprite 3",
+];
+
+#[derive(Debug, Clone)]
+pub struct TileSnapshot {
+    /// `$0000-$1FFF` as the PPU sees it, with CHR banking applied
+    pub chr: Vec<u8>,
+    /// `$3F00-$3F1F` as the PPU sees it, with `$3F10/$3F14/$3F18/$3F1C` mirrored
+    pub palette: [u8; 32],
+}
+
+impl TileSnapshot {
+    pub fn capture(nes: &NES) -> Self {
+        let ppu: Ref<PPU> = nes.ppu();
+        TileSnapshot {
+            chr: (0x0000..0x2000).map(|addr: u16| ppu.peek(addr)).collect(),
+            palette: std::array::from_fn(|index: usize| ppu.peek(0x3F00 + index as u16)),
+        }
+    }
+
+    /// Both pattern tables side by side, colored with one of the eight palettes
+    pub fn pattern_tables(&self, palette: usize) -> ColorImage {
+        let width: usize = 2 * TABLE_SIZE;
+        let mut pixels: Vec<Color32> = vec![Color32::BLACK; width * TABLE_SIZE];
+        for (index, tile) in self.chr.chunks_exact(16).enumerate() {
+            let table: usize = index / 256;
+            let x: usize = table * TABLE_SIZE + (index % 16) * 8;
+            let y: usize = (index % 256 / 16) * 8;
+            for (fine_y, row) in decode_tile(tile).iter().enumerate() {
+                for (fine_x, color) in row.iter().enumerate() {
+                    // Color 0 of every palette is the backdrop at $3F00
+                    let entry: u8 = match color {
+                        0 => self.palette[0],
+                        _ => self.palette[palette * 4 + *color as usize],
+                    };
+                    pixels[(y + fine_y) * width + x + fine_x] = nes_color(entry);
+                }
+            }
+        }
+        ColorImage::new([width, TABLE_SIZE], pixels)
+    }
+
+    /// The palette RAM as two rows of 16 swatches, background palettes on top
+    pub fn palette_image(&self) -> ColorImage {
+        let width: usize = 16 * SWATCH_SIZE;
+        let pixels: Vec<Color32> = (0..width * 2 * SWATCH_SIZE)
+            .map(|pixel: usize| {
+                let (x, y): (usize, usize) = (pixel % width, pixel / width);
+                nes_color(self.palette[(y / SWATCH_SIZE) * 16 + x / SWATCH_SIZE])
+            })
+            .collect();
+        ColorImage::new([width, 2 * SWATCH_SIZE], pixels)
+    }
+}
+
+fn save_png(picture: &ColorImage, path: &Path) -> image::ImageResult<()> {
+    image::save_buffer(
+        path,
+        picture.as_raw(),
+        picture.size[0] as u32,
+        picture.size[1] as u32,
+        image::ExtendedColorType::Rgba8,
+    )
+}
+
+/// Black or white, whichever is easier to read on `color`
+fn text_color(color: Color32) -> Color32 {
+    let luma: u32 =
+        (color.r() as u32 * 299 + color.g() as u32 * 587 + color.b() as u32 * 114) / 1000;
+    match luma > 128 {
+        true => Color32::BLACK,
+        false => Color32::WHITE,
+    }
+}
+
+#[derive(Default)]
+pub struct TileViewer {
+    pub open: bool,
+    /// The palette tiles are colored with, sprite palettes start at 4
+    palette: usize,
+    snapshot: Option<TileSnapshot>,
+    texture: Option<TextureHandle>,
+    message: Option<String>,
+}
+
+impl TileViewer {
+    pub fn update(&mut self, ctx: &egui::Context, snapshot: TileSnapshot) {
+        self.snapshot = Some(snapshot);
+        self.refresh(ctx);
+    }
+
+    fn refresh(&mut self, ctx: &egui::Context) {
+        let Some(snapshot) = &self.snapshot else {
+            return;
+        };
+        let image: ColorImage = snapshot.pattern_tables(self.palette);
+        match self.texture.as_mut() {
+            Some(texture) => texture.set(image, TextureOptions::NEAREST),
+            None => self.texture = Some(ctx.load_texture("tiles", image, TextureOptions::NEAREST)),
+        }
+    }
+
+    fn export(&mut self, picture: &ColorImage, what: &str) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("PNG image", &["png"])
+            .save_file()
+        else {
+            return;
+        };
+        let path: PathBuf = path.with_extension("png");
+        match save_png(picture, &path) {
+            Ok(_) => {
+                info!("Saved {} to {:?}", what, path);
+                self.message = None;
+            }
+            Err(err) => {
+                error!("Failed to save {} to {:?}: {}", what, path, err);
+                self.message = Some(format!("Failed to save {}: {}", what, err));
+            }
+        }
+    }
+
+    fn controls(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui: &mut Ui| {
+            let mut palette: usize = self.palette;
+            egui::ComboBox::from_id_salt("tile_palette")
+                .selected_text(PALETTES[palette])
+                .show_ui(ui, |ui: &mut Ui| {
+                    for (index, name) in PALETTES.iter().enumerate() {
+                        ui.selectable_value(&mut palette, index, *name);
+                    }
+                });
+            if palette != self.palette {
+                self.palette = palette;
+                self.refresh(ui.ctx());
+            }
+            ui.separator();
+
+            ui.add_enabled_ui(self.snapshot.is_some(), |ui: &mut Ui| {
+                if ui.button("Export tiles...").clicked()
+                    && let Some(snapshot) = &self.snapshot
+                {
+                    let picture: ColorImage = snapshot.pattern_tables(self.palette);
+                    self.export(&picture, "tiles");
+                }
+                if ui.button("Export palettes...").clicked()
+                    && let Some(snapshot) = &self.snapshot
+                {
+                    let picture: ColorImage = snapshot.palette_image();
+                    self.export(&picture, "palettes");
+                }
+            });
+            if let Some(message) = &self.message {
+                ui.colored_label(Color32::RED, message);
+            }
+        });
+    }
+
+    fn show(&mut self, ui: &mut Ui) {
+        let (Some(snapshot), Some(texture)) = (&self.snapshot, &self.texture) else {
+            ui.label("Waiting for the NES...");
+            return;
+        };
+
+        let scale: f32 = (ui.available_width() / (2 * TABLE_SIZE) as f32)
+            .floor()
+            .max(1.0);
+        let response: egui::Response = ui.add(
+            egui::Image::from_texture(egui::load::SizedTexture::new(
+                texture.id(),
+                Vec2::new((2 * TABLE_SIZE) as f32, TABLE_SIZE as f32) * scale,
+            ))
+            .sense(Sense::hover()),
+        );
+        if let Some(pos) = response.hover_pos() {
+            let x: usize =
+                (((pos.x - response.rect.min.x) / scale) as usize).min(2 * TABLE_SIZE - 1);
+            let y: usize = (((pos.y - response.rect.min.y) / scale) as usize).min(TABLE_SIZE - 1);
+            let table: usize = x / TABLE_SIZE;
+            let tile: usize = (y / 8) * 16 + (x % TABLE_SIZE) / 8;
+            response.on_hover_text_at_pointer(format!(
+                "Tile ${:02X}\nPPU address ${:04X}",
+                tile,
+                table * 0x1000 + tile * 16
+            ));
+        }
+        ui.separator();
+
+        let mut selected: Option<usize> = None;
+        egui::Grid::new("palette_ram")
+            .spacing(Vec2::splat(2.0))
+            .show(ui, |ui: &mut Ui| {
+                for (index, entry) in snapshot.palette.iter().enumerate() {
+                    let color: Color32 = nes_color(*entry);
+                    let stroke: Stroke = match index / 4 == self.palette {
+                        true => Stroke::new(2.0_f32, ui.visuals().strong_text_color()),
+                        false => Stroke::NONE,
+                    };
+                    let swatch: egui::Response = ui
+                        .add(
+                            egui::Button::new(
+                                RichText::new(format!("{:02X}", entry))
+                                    .monospace()
+                                    .color(text_color(color)),
+                            )
+                            .fill(color)
+                            .stroke(stroke)
+                            .min_size(Vec2::splat(28.0)),
+                        )
+                        .on_hover_text(format!(
+                            "${:04X}: ${:02X} ({})",
+                            0x3F00 + index,
+                            entry,
+                            PALETTES[index / 4]
+                        ));
+                    if swatch.clicked() {
+                        selected = Some(index / 4);
+                    }
+                    if index % 16 == 15 {
+                        ui.end_row();
+                    }
+                }
+            });
+
+        if let Some(palette) = selected
+            && palette != self.palette
+        {
+            self.palette = palette;
+            self.refresh(ui.ctx());
+        }
+    }
+}
+
+impl App {
+    pub fn tile_viewer(&mut self, ctx: &egui::Context) {
+        ctx.show_viewport_immediate(
+            ViewportId::from_hash_of("tile_viewer"),
+            ViewportBuilder::default()
+                .with_inner_size([540.0, 420.0])
+                .with_title("Tile Viewer"),
+            |ctx: &egui::Context, _class: egui::ViewportClass| {
+                egui::TopBottomPanel::top("tile_viewer_controls")
+                    .show(ctx, |ui: &mut Ui| self.tile_viewer.controls(ui));
+                egui::CentralPanel::default().show(ctx, |ui: &mut Ui| {
+                    if self.nes_state == NESState::Stopped {
+                        ui.label("Start a ROM to see its tiles");
+                        return;
+                    }
+                    self.tile_viewer.show(ui);
+                });
+
+                if ctx.input(|i: &egui::InputState| i.viewport().close_requested()) {
+                    self.tile_viewer.open = false;
+                }
+            },
+        );
+    }
+}
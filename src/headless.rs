@@ -0,0 +1,312 @@
+//! Runs a ROM without a window or GPU
+//!
+//! Used for scripted runs in CI and on machines without a display:
+//! ```text
+//! nesmur headless game.nes --frames 600 --screenshot out.png --audio out.wav
+//! nesmur headless test.nes --frames 3600 --until '$6000=00' --dump-ram ram.bin
+//! nesmur headless game.nes --movie run.fm2 --trace trace.log
+//! ```
+//! Exits with code 1 if `--until` was given but no condition was met before the frame limit.
+
+use crate::{audio::DEFAULT_SAMPLE_RATE, prelude::*};
+use anyhow::{Context, bail};
+use clap::Args;
+use hound::{SampleFormat, WavSpec, WavWriter};
+use nes::{
+    NES, SCREEN_HEIGHT, SCREEN_WIDTH,
+    cartridge::ROM,
+    cpu::CPU,
+    input_device::NESDeviceType,
+    movie::{Movie, MoviePlayer},
+    ppu::renderer::RGB,
+    symbols::Symbols,
+    tools::{self, NESAccess},
+};
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+#[derive(Args, Debug)]
+pub struct HeadlessArgs {
+    /// ROM to run
+    pub rom: PathBuf,
+
+    /// Stop after this many frames [default: the length of the movie]
+    #[arg(short, long, required_unless_present = "movie")]
+    pub frames: Option<usize>,
+
+    /// Stop early once `pc=ADDR` is reached or `ADDR=VALUE` / `ADDR!=VALUE` holds at the end
+    /// of a frame (hex, may be given more than once)
+    #[arg(long, value_name = "CONDITION")]
+    pub until: Vec<StopCondition>,
+
+    /// Save state to start from
+    #[arg(long, conflicts_with = "movie")]
+    pub state: Option<PathBuf>,
+
+    /// FM2 movie to feed as input
+    #[arg(long)]
+    pub movie: Option<PathBuf>,
+
+    /// Save the last frame as a PNG
+    #[arg(long)]
+    pub screenshot: Option<PathBuf>,
+
+    /// Also save every Nth frame, numbered after the `--screenshot` file name
+    #[arg(long, value_name = "N", requires = "screenshot")]
+    pub screenshot_every: Option<usize>,
+
+    /// Record the audio to a 32-bit float mono WAV file
+    #[arg(long)]
+    pub audio: Option<PathBuf>,
+
+    /// Write the 2 KiB of internal RAM, followed by the cartridge's PRG-RAM
+    #[arg(long)]
+    pub dump_ram: Option<PathBuf>,
+
+    /// Log every executed instruction
+    #[arg(long)]
+    pub trace: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopCondition {
+    ProgramCounter(u16),
+    Equal(u16, u8),
+    NotEqual(u16, u8),
+}
+
+impl StopCondition {
+    fn check_pc(&self, cpu: &CPU) -> bool {
+        matches!(self, StopCondition::ProgramCounter(addr) if cpu.program_counter == *addr)
+    }
+
+    fn check_memory(&self, nes: &NES) -> bool {
+        match self {
+            StopCondition::ProgramCounter(_) => false,
+            StopCondition::Equal(addr, value) => nes.bus().peek(*addr) == *value,
+            StopCondition::NotEqual(addr, value) => nes.bus().peek(*addr) != *value,
+        }
+    }
+}
+
+impl FromStr for StopCondition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        fn parse_hex<T: TryFrom<u32>>(s: &str) -> Result<T, String> {
+            let digits: &str = s.trim().trim_start_matches('$').trim_start_matches("0x");
+            u32::from_str_radix(digits, 16)
+                .ok()
+                .and_then(|value: u32| T::try_from(value).ok())
+                .ok_or_else(|| format!("invalid hex value `{}`", s))
+        }
+
+        if let Some(addr) = s.strip_prefix("pc=") {
+            Ok(StopCondition::ProgramCounter(parse_hex(addr)?))
+        } else if let Some((addr, value)) = s.split_once("!=") {
+            Ok(StopCondition::NotEqual(parse_hex(addr)?, parse_hex(value)?))
+        } else if let Some((addr, value)) = s.split_once('=') {
+            Ok(StopCondition::Equal(parse_hex(addr)?, parse_hex(value)?))
+        } else {
+            Err("expected `pc=ADDR`, `ADDR=VALUE` or `ADDR!=VALUE`".to_string())
+        }
+    }
+}
+
+/// Runs the ROM, returns whether it stopped because of an `--until` condition
+pub fn run(args: &HeadlessArgs) -> anyhow::Result<bool> {
+    let rom_bytes: Vec<u8> =
+        std::fs::read(&args.rom).with_context(|| format!("Failed to read {:?}", args.rom))?;
+    let rom: ROM = match ROM::new(&rom_bytes) {
+        Ok(rom) => rom,
+        Err(err) => bail!("Failed to load {:?}: {}", args.rom, err),
+    };
+    let movie: Option<Movie> = match args.movie.as_ref() {
+        Some(path) => {
+            let text: String = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {:?}", path))?;
+            Some(Movie::from_fm2(&text).with_context(|| format!("Failed to parse {:?}", path))?)
+        }
+        None => None,
+    };
+
+    let mut nes: NES = NES::new(rom);
+    nes.connect_input_device(1, NESDeviceType::Joypad);
+    // Movies say whether a second joypad was plugged in while recording
+    if movie.as_ref().is_none_or(|movie: &Movie| movie.joypad2) {
+        nes.connect_input_device(2, NESDeviceType::Joypad);
+    }
+
+    if let Some(path) = args.state.as_ref() {
+        let state: Vec<u8> =
+            std::fs::read(path).with_context(|| format!("Failed to read {:?}", path))?;
+        nes.load_state(&state)
+            .with_context(|| format!("Failed to load state {:?}", path))?;
+    }
+
+    let mut player: Option<MoviePlayer> = None;
+    if let Some(movie) = movie {
+        info!("Playing {} frame movie", movie.frames.len());
+        let power_on: Vec<u8> = nes.save_state();
+        player = Some(
+            MoviePlayer::start(&mut nes, movie, &power_on)
+                .context("Failed to load the movie's start state")?,
+        );
+    }
+    let max_frames: usize = match (args.frames, player.as_ref()) {
+        (Some(frames), _) => frames,
+        (None, Some(player)) => player.movie.frames.len(),
+        (None, None) => unreachable!("clap requires `--frames` without `--movie`"),
+    };
+
+    let mut wav_writer: Option<WavWriter<BufWriter<File>>> = None;
+    if let Some(path) = args.audio.as_ref() {
+        let spec: WavSpec = WavSpec {
+            channels: 1,
+            sample_rate: DEFAULT_SAMPLE_RATE,
+            bits_per_sample: 32,
+            sample_format: SampleFormat::Float,
+        };
+        wav_writer = Some(
+            WavWriter::create(path, spec)
+                .with_context(|| format!("Failed to create {:?}", path))?,
+        );
+        nes.apu_mut()
+            .set_sample_rate(Some(DEFAULT_SAMPLE_RATE as f64));
+    }
+    let mut trace_writer: Option<BufWriter<File>> = match args.trace.as_ref() {
+        Some(path) => Some(BufWriter::new(
+            File::create(path).with_context(|| format!("Failed to create {:?}", path))?,
+        )),
+        None => None,
+    };
+    if trace_writer.is_some() {
+        let bank_size: usize = nes.mapper().prg_bank_size();
+        nes.bus_mut().symbols = Symbols::load_for_rom(&args.rom, bank_size)
+            .with_context(|| format!("Failed to load the symbols for {:?}", args.rom))?;
+    }
+
+    let mut frame_count: usize = 0;
+    let mut condition_met: bool = false;
+    let mut trace_result: std::io::Result<()> = Ok(());
+    while frame_count < max_frames {
+        if let Some(player) = player.as_mut() {
+            player.next_frame(&mut nes);
+        }
+
+        let mut at_pc: bool = false;
+        let running: bool = nes.step_frame(|cpu: &mut CPU| {
+            if let Some(writer) = trace_writer.as_mut()
+                && trace_result.is_ok()
+            {
+                trace_result = writeln!(writer, "{}", tools::trace(cpu));
+            }
+            at_pc = args
+                .until
+                .iter()
+                .any(|condition: &StopCondition| condition.check_pc(cpu));
+            if at_pc {
+                cpu.running = false;
+            }
+        });
+        if at_pc {
+            condition_met = true;
+            break;
+        }
+        if !running {
+            warn!("The CPU stopped on frame {}", frame_count);
+            break;
+        }
+        frame_count += 1;
+
+        if let Some(writer) = wav_writer.as_mut() {
+            write_samples(&mut nes, writer)?;
+        }
+        if let (Some(path), Some(every)) = (args.screenshot.as_ref(), args.screenshot_every)
+            && frame_count.is_multiple_of(every.max(1))
+        {
+            save_screenshot(&nes, &numbered_path(path, frame_count))?;
+        }
+        if args
+            .until
+            .iter()
+            .any(|condition: &StopCondition| condition.check_memory(&nes))
+        {
+            condition_met = true;
+            break;
+        }
+    }
+    trace_result.context("Failed to write the trace log")?;
+
+    if let Some(mut writer) = wav_writer {
+        write_samples(&mut nes, &mut writer)?;
+        writer
+            .finalize()
+            .context("Failed to finalize the WAV file")?;
+    }
+    if let Some(mut writer) = trace_writer {
+        writer.flush().context("Failed to write the trace log")?;
+    }
+    if let Some(path) = args.screenshot.as_ref() {
+        save_screenshot(&nes, path)?;
+    }
+    if let Some(path) = args.dump_ram.as_ref() {
+        let mut ram: Vec<u8> = nes.memory().cpu_vram.to_vec();
+        ram.extend_from_slice(&nes.memory().prg_ram);
+        std::fs::write(path, ram).with_context(|| format!("Failed to write {:?}", path))?;
+    }
+
+    info!(
+        "Stopped after {} frames at PC ${:04X}{}",
+        frame_count,
+        nes.cpu.program_counter,
+        if condition_met { ", condition met" } else { "" }
+    );
+    Ok(condition_met || args.until.is_empty())
+}
+
+fn write_samples(nes: &mut NES, writer: &mut WavWriter<BufWriter<File>>) -> anyhow::Result<()> {
+    if let Some(resampler) = nes.apu_mut().resampler.as_mut() {
+        for sample in resampler.drain_samples() {
+            writer
+                .write_sample(sample)
+                .context("Failed to write audio samples")?;
+        }
+    }
+    Ok(())
+}
+
+fn save_screenshot(nes: &NES, path: &Path) -> anyhow::Result<()> {
+    let pixels: Vec<u8> = nes
+        .renderer()
+        .pixels
+        .iter()
+        .flat_map(|color: &RGB| [color.0, color.1, color.2])
+        .collect();
+    image::save_buffer(
+        path,
+        &pixels,
+        SCREEN_WIDTH as u32,
+        SCREEN_HEIGHT as u32,
+        image::ExtendedColorType::Rgb8,
+    )
+    .with_context(|| format!("Failed to save screenshot {:?}", path))
+}
+
+/// `shot.png` -> `shot-000120.png`
+pub(crate) fn numbered_path(path: &Path, frame: usize) -> PathBuf {
+    let stem: String = path
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    let mut file_name: String = format!("{}-{:06}", stem, frame);
+    if let Some(extension) = path.extension() {
+        file_name = format!("{}.{}", file_name, extension.to_string_lossy());
+    }
+    path.with_file_name(file_name)
+}
@@ -45,6 +45,7 @@ pub enum InputType {
 }
 
 #[derive(Debug, Copy, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct InputMapping {
     pub up: Input,
     pub down: Input,
@@ -57,6 +58,10 @@ pub struct InputMapping {
     pub pause: Input,
     pub rewind: Input,
     pub fast_forward: Input,
+    pub save_state: Input,
+    pub load_state: Input,
+    pub next_slot: Input,
+    pub previous_slot: Input,
 }
 
 impl InputMapping {
@@ -70,6 +75,12 @@ impl InputMapping {
             a: Input::Key(egui::Key::A),
             start: Input::Key(egui::Key::Enter),
             select: Input::Key(egui::Key::Space),
+            rewind: Input::Key(egui::Key::Backspace),
+            fast_forward: Input::Key(egui::Key::Backslash),
+            save_state: Input::Key(egui::Key::F5),
+            load_state: Input::Key(egui::Key::F9),
+            next_slot: Input::Key(egui::Key::CloseBracket),
+            previous_slot: Input::Key(egui::Key::OpenBracket),
             ..Default::default()
         }
     }
@@ -115,18 +126,31 @@ impl InputManager {
         }
     }
 
-    pub fn pause_pressed(&self) -> bool {
-        self.pressed_input
-            .contains(&self.keyboard_input_mapping.0.pause)
+    fn hotkey_in(&self, inputs: &HashSet<Input>, hotkey: impl Fn(&InputMapping) -> Input) -> bool {
+        hotkey(&self.keyboard_input_mapping.0).specified_and(|i: Input| inputs.contains(&i))
             || self
                 .selected_controllers
                 .0
                 .map(|c: Uuid| self.controller_input_mapping.get(&c).unwrap())
                 .is_some_and(|c: &ControllerConfig| -> bool {
-                    self.pressed_input.contains(&c.input_mapping.pause)
+                    hotkey(&c.input_mapping).specified_and(|i: Input| inputs.contains(&i))
                 })
     }
 
+    /// Whether a system hotkey, like `|mapping| mapping.pause`, was pressed this frame
+    pub fn hotkey_pressed(&self, hotkey: impl Fn(&InputMapping) -> Input) -> bool {
+        self.hotkey_in(&self.pressed_input, hotkey)
+    }
+
+    /// Whether a system hotkey, like `|mapping| mapping.rewind`, is currently held down
+    pub fn hotkey_held(&self, hotkey: impl Fn(&InputMapping) -> Input) -> bool {
+        self.hotkey_in(&self.held_input, hotkey)
+    }
+
+    pub fn pause_pressed(&self) -> bool {
+        self.hotkey_pressed(|mapping: &InputMapping| mapping.pause)
+    }
+
     pub fn get_button_state(&self) -> NesButtonState {
         let k_con1: InputMapping = self.keyboard_input_mapping.0;
         let c_con1: Option<&ControllerConfig> = self
@@ -244,6 +268,7 @@ impl InputManager {
                         ..
                     } => {
                         self.held_input.insert(Input::Key(*key));
+                        self.pressed_input.insert(Input::Key(*key));
                     }
                     egui::Event::Key {
                         key,
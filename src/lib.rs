@@ -9,12 +9,18 @@ pub mod prelude {
     pub use log::{debug, error, info, trace, warn};
 }
 pub mod app;
+pub mod audio;
 #[cfg(debug_assertions)]
 pub mod debug;
+pub mod debugger;
 pub mod events;
+pub mod headless;
 pub mod input;
 pub mod logging;
+pub mod save_slots;
 pub mod ui;
+#[cfg(test)]
+mod unit_tests;
 pub mod widgets;
 
 mod temp;
@@ -1,11 +1,26 @@
 use nesmur::{
-    INITIAL_SIZE_HEIGHT, INITIAL_SIZE_WIDTH, PERSISTENT_DATA_PATH, app::App, cli::Cli, prelude::*,
+    INITIAL_SIZE_HEIGHT, INITIAL_SIZE_WIDTH, PERSISTENT_DATA_PATH,
+    app::App,
+    cli::{Cli, Command},
+    headless,
+    prelude::*,
     setup,
 };
 use std::path::PathBuf;
 
 fn main() -> eframe::Result<()> {
-    let _cli: Cli = setup::initial_setup();
+    let cli: Cli = setup::initial_setup();
+    if let Some(Command::Headless(args)) = cli.command.as_ref() {
+        let code: i32 = match headless::run(args) {
+            Ok(true) => 0,
+            Ok(false) => 1,
+            Err(err) => {
+                error!("{:#}", err);
+                2
+            }
+        };
+        std::process::exit(code);
+    }
     info!("Starting Nesmur...");
 
     let options: eframe::NativeOptions = eframe::NativeOptions {
@@ -17,7 +32,13 @@ fn main() -> eframe::Result<()> {
     let ret: Result<(), eframe::Error> = eframe::run_native(
         "NESMUR",
         options,
-        Box::new(|cc: &eframe::CreationContext<'_>| Ok(Box::new(App::new(cc)))),
+        Box::new(|cc: &eframe::CreationContext<'_>| {
+            Ok(Box::new(App::new(
+                cc,
+                cli.audio_backend(),
+                cli.saves_dir.clone(),
+            )))
+        }),
     );
 
     info!("Stopped Nesmur");
@@ -0,0 +1,151 @@
+//! Numbered save-state slots
+//!
+//! Slots are stored per ROM in `<rom name>.states/`, next to the ROM or in the saves directory.
+//! Each slot is a `slot<N>.state` file and a `slot<N>.png` thumbnail of the last frame before it
+//! was saved, and the state file's modification time is the slot's timestamp.
+
+use crate::prelude::*;
+use egui::{ColorImage, TextureHandle, TextureOptions};
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+pub const SLOT_COUNT: usize = 10;
+
+pub struct SlotInfo {
+    pub timestamp: SystemTime,
+    thumbnail: Option<ColorImage>,
+    texture: Option<TextureHandle>,
+}
+
+pub struct SaveSlots {
+    dir: PathBuf,
+    pub selected: usize,
+    pub slots: Vec<Option<SlotInfo>>,
+}
+
+impl SaveSlots {
+    pub fn new(rom_path: &Path, saves_dir: Option<&Path>) -> Self {
+        let mut dir_name: OsString = rom_path.file_stem().unwrap_or_default().to_owned();
+        dir_name.push(".states");
+        let dir: PathBuf = match saves_dir {
+            Some(saves_dir) => saves_dir.join(dir_name),
+            None => rom_path.with_file_name(dir_name),
+        };
+
+        let mut save_slots: SaveSlots = SaveSlots {
+            dir,
+            selected: 0,
+            slots: Vec::with_capacity(SLOT_COUNT),
+        };
+        for slot in 0..SLOT_COUNT {
+            let info: Option<SlotInfo> = save_slots.read_slot_info(slot);
+            save_slots.slots.push(info);
+        }
+        save_slots
+    }
+
+    fn state_path(&self, slot: usize) -> PathBuf {
+        self.dir.join(format!("slot{}.state", slot))
+    }
+
+    fn thumbnail_path(&self, slot: usize) -> PathBuf {
+        self.dir.join(format!("slot{}.png", slot))
+    }
+
+    fn read_slot_info(&self, slot: usize) -> Option<SlotInfo> {
+        let timestamp: SystemTime = std::fs::metadata(self.state_path(slot))
+            .and_then(|metadata: std::fs::Metadata| metadata.modified())
+            .ok()?;
+        let thumbnail: Option<ColorImage> = image::open(self.thumbnail_path(slot))
+            .map(|image: image::DynamicImage| {
+                let image: image::RgbaImage = image.to_rgba8();
+                ColorImage::from_rgba_unmultiplied(
+                    [image.width() as usize, image.height() as usize],
+                    image.as_raw(),
+                )
+            })
+            .ok();
+
+        Some(SlotInfo {
+            timestamp,
+            thumbnail,
+            texture: None,
+        })
+    }
+
+    pub fn read(&self, slot: usize) -> std::io::Result<Vec<u8>> {
+        std::fs::read(self.state_path(slot))
+    }
+
+    pub fn write(
+        &mut self,
+        slot: usize,
+        state: &[u8],
+        thumbnail: Option<ColorImage>,
+    ) -> std::io::Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::write(self.state_path(slot), state)?;
+
+        if let Some(thumbnail) = thumbnail.as_ref() {
+            let result: image::ImageResult<()> = image::save_buffer(
+                self.thumbnail_path(slot),
+                thumbnail.as_raw(),
+                thumbnail.size[0] as u32,
+                thumbnail.size[1] as u32,
+                image::ExtendedColorType::Rgba8,
+            );
+            if let Err(err) = result {
+                error!("Failed to save thumbnail for slot {}: {}", slot, err);
+            }
+        } else if let Err(err) = std::fs::remove_file(self.thumbnail_path(slot))
+            && err.kind() != std::io::ErrorKind::NotFound
+        {
+            // A thumbnail left from an older save would show the wrong picture
+            error!("Failed to remove old thumbnail for slot {}: {}", slot, err);
+        }
+
+        self.slots[slot] = Some(SlotInfo {
+            timestamp: SystemTime::now(),
+            thumbnail,
+            texture: None,
+        });
+        Ok(())
+    }
+
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1) % SLOT_COUNT;
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = (self.selected + SLOT_COUNT - 1) % SLOT_COUNT;
+    }
+
+    /// Uploads the slot's thumbnail the first time it's shown
+    pub fn thumbnail(&mut self, ctx: &egui::Context, slot: usize) -> Option<&TextureHandle> {
+        let info: &mut SlotInfo = self.slots[slot].as_mut()?;
+        if info.texture.is_none() {
+            let thumbnail: ColorImage = info.thumbnail.clone()?;
+            info.texture = Some(ctx.load_texture(
+                format!("save-slot-{}", slot),
+                thumbnail,
+                TextureOptions::NEAREST,
+            ));
+        }
+        info.texture.as_ref()
+    }
+}
+
+pub fn format_age(timestamp: SystemTime) -> String {
+    let seconds: u64 = SystemTime::now()
+        .duration_since(timestamp)
+        .map_or(0, |age: std::time::Duration| age.as_secs());
+    match seconds {
+        0..60 => "just now".to_string(),
+        60..3_600 => format!("{} min ago", seconds / 60),
+        3_600..86_400 => format!("{} h ago", seconds / 3_600),
+        _ => format!("{} days ago", seconds / 86_400),
+    }
+}
@@ -1,7 +1,8 @@
 pub mod nes_manager;
 pub mod thread_com;
 
-use nes::ppu::renderer::RGB;
+use crate::debugger::{StepKind, memory::MemoryRegion};
+use nes::{breakpoint::Breakpoint, ppu::renderer::RGB};
 use std::{
     path::PathBuf,
     thread::{Builder, JoinHandle},
@@ -25,9 +26,20 @@ pub enum NESEvent {
     Stop,
     Pause,
     Resume,
-    Step,
+    Step(StepKind),
     NewFrame(Vec<RGB>),
     SteppingFinished,
+    SaveState(usize),
+    LoadState(usize),
+    Rewind(bool),
+    FastForward(bool),
+    /// Records a movie to the path, from power-on or from the current state
+    RecordMovie(PathBuf, bool),
+    PlayMovie(PathBuf),
+    StopMovie,
+    SetBreakpoints(Vec<Breakpoint>),
+    /// Writes a byte at an offset into a region, without going through the bus
+    PokeMemory(MemoryRegion, usize, u8),
 }
 
 impl std::fmt::Debug for NESEvent {
@@ -40,8 +52,27 @@ impl std::fmt::Debug for NESEvent {
             NESEvent::Stop => write!(f, "Stop"),
             NESEvent::Pause => write!(f, "Pause"),
             NESEvent::Resume => write!(f, "Resume"),
-            NESEvent::Step => write!(f, "Step"),
+            NESEvent::Step(kind) => write!(f, "Step({:?})", kind),
             NESEvent::SteppingFinished => write!(f, "SteppingFinished"),
+            NESEvent::SaveState(slot) => write!(f, "SaveState({})", slot),
+            NESEvent::LoadState(slot) => write!(f, "LoadState({})", slot),
+            NESEvent::Rewind(rewinding) => write!(f, "Rewind({})", rewinding),
+            NESEvent::FastForward(fast_forward) => write!(f, "FastForward({})", fast_forward),
+            NESEvent::RecordMovie(path, from_power_on) => {
+                write!(f, "RecordMovie({:?}, {})", path, from_power_on)
+            }
+            NESEvent::PlayMovie(path) => write!(f, "PlayMovie({:?})", path),
+            NESEvent::StopMovie => write!(f, "StopMovie"),
+            NESEvent::SetBreakpoints(list) => {
+                write!(f, "SetBreakpoints([Breakpoint; {}])", list.len())
+            }
+            NESEvent::PokeMemory(region, offset, value) => {
+                write!(
+                    f,
+                    "PokeMemory({:?}, {:#06X}, {:#04X})",
+                    region, offset, value
+                )
+            }
         }
     }
 }
@@ -1,24 +1,276 @@
 use crate::{
+    audio::SharedAudioBuffer,
+    debugger::{
+        StepKind, Stepper,
+        cpu::{CpuSnapshot, HISTORY_LEN},
+        memory::{self, MemoryRegion, MemorySnapshot},
+        nametable::NametableSnapshot,
+        tiles::TileSnapshot,
+    },
     new_named_thread,
     prelude::*,
+    save_slots::SaveSlots,
     thread_com::{ThreadCom, ThreadComError, ThreadMsg},
 };
 use crossbeam::channel::{self, Receiver, RecvError, Sender, TrySendError};
 use egui::ColorImage;
 use nes::{
-    NES, RcRef,
+    NES, VBLANK_SCANLINE,
+    apu::APU,
+    breakpoint::{Breakpoint, BreakpointHit},
     cartridge::ROM,
-    input_device::{NESDeviceButton, NESDeviceType},
-    ppu::renderer::{RGB, Renderer},
+    cpu::CPU,
+    input_device::{NESDeviceButton, NESDeviceType, joypad::JoypadButton},
+    movie::{Movie, MovieFrame, MoviePlayer, set_joypads},
+    ppu::renderer::RGB,
+    rewind::RewindBuffer,
+    symbols::Symbols,
     tools::NESAccess,
 };
+use serde::{Deserialize, Serialize};
 use std::{
-    cell::Ref,
-    path::PathBuf,
+    cell::RefMut,
+    collections::VecDeque,
+    ffi::OsString,
+    path::{Path, PathBuf},
     thread::{self, JoinHandle},
     time::{Duration, Instant},
 };
 
+/// Samples are only moved into the audio buffer in chunks, to keep the per-step overhead low
+const AUDIO_CHUNK_SIZE: usize = 64;
+/// Battery RAM is written to disk about every 5 seconds, so a crash loses very little progress
+const BATTERY_FLUSH_FRAMES: usize = 300;
+/// How far pacing may fall behind before it gives up on catching up, e.g. after a pause
+const MAX_PACING_LAG: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RewindConfig {
+    pub enabled: bool,
+    /// Frames between snapshots
+    pub interval: usize,
+    /// Memory budget for the snapshots, in MiB
+    pub buffer_size: usize,
+    /// Playback speed while rewinding, relative to normal speed
+    pub speed: f64,
+}
+
+impl Default for RewindConfig {
+    fn default() -> Self {
+        RewindConfig {
+            enabled: true,
+            interval: 2,
+            buffer_size: 64,
+            speed: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FastForwardConfig {
+    /// Emulation speed while fast-forwarding, relative to normal speed
+    pub speed: f64,
+    /// Whether the fast-forward input toggles fast-forwarding instead of having to be held
+    pub toggle: bool,
+}
+
+impl Default for FastForwardConfig {
+    fn default() -> Self {
+        FastForwardConfig {
+            speed: 4.0,
+            toggle: false,
+        }
+    }
+}
+
+/// Keeps emulated frames as long as they are on the real console, scaled by a speed multiplier
+struct FramePacer {
+    frame_rate: f64,
+    next_frame: Instant,
+}
+
+impl FramePacer {
+    fn new(frame_rate: f64) -> Self {
+        FramePacer {
+            frame_rate,
+            next_frame: Instant::now(),
+        }
+    }
+
+    /// Sleeps until the next frame is due, called whenever a frame finished
+    fn wait(&mut self, speed: f64) {
+        self.next_frame += Duration::from_secs_f64(1.0 / (self.frame_rate * speed));
+
+        let now: Instant = Instant::now();
+        if now < self.next_frame {
+            thread::sleep(self.next_frame - now);
+        } else if now - self.next_frame > MAX_PACING_LAG {
+            self.next_frame = now;
+        }
+    }
+}
+
+/// `<saves_dir>/<rom name>.sav` if a saves directory is set, otherwise `<rom name>.sav` next to
+/// the ROM
+pub fn save_file_path(rom_path: &Path, saves_dir: Option<&Path>) -> PathBuf {
+    match saves_dir {
+        Some(saves_dir) => {
+            let mut file_name: OsString = rom_path.file_stem().unwrap_or_default().to_owned();
+            file_name.push(".sav");
+            saves_dir.join(file_name)
+        }
+        None => rom_path.with_extension("sav"),
+    }
+}
+
+fn load_battery_ram(nes: &mut NES, save_path: &Path) {
+    match std::fs::read(save_path) {
+        Ok(data) => {
+            nes.load_battery_ram(&data);
+            info!("Loaded battery save from {:?}", save_path);
+        }
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            debug!("No battery save at {:?}", save_path);
+        }
+        Err(err) => error!("Failed to read battery save {:?}: {}", save_path, err),
+    }
+}
+
+/// Writes the battery RAM to `save_path` if it changed since `last_saved`
+fn flush_battery_ram(nes: &NES, save_path: &Path, last_saved: &mut Vec<u8>) {
+    let Some(data) = nes.battery_ram() else {
+        return;
+    };
+    if data == *last_saved {
+        return;
+    }
+
+    // Write to a temporary file first, so a crash mid-write can't corrupt the previous save
+    let tmp_path: PathBuf = save_path.with_extension("sav.tmp");
+    let result: std::io::Result<()> = save_path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::write(&tmp_path, &data))
+        .and_then(|_| std::fs::rename(&tmp_path, save_path));
+    match result {
+        Ok(_) => {
+            trace!("Flushed battery save to {:?}", save_path);
+            *last_saved = data;
+        }
+        Err(err) => error!("Failed to write battery save {:?}: {}", save_path, err),
+    }
+}
+
+/// Battery saves are turned off while the machine runs from a movie's power-on state, so the
+/// movie can't overwrite them
+fn stop_battery_saves(nes: &NES, save_path: &mut Option<PathBuf>, last_saved: &mut Vec<u8>) {
+    if let Some(save_path) = save_path.take() {
+        flush_battery_ram(nes, &save_path, last_saved);
+        info!("Battery saves are off until the ROM is restarted");
+    }
+}
+
+/// Plugs in new devices, so they are in their power-on state too
+fn reconnect_devices(nes: &mut NES, devices: &[Option<NESDeviceType>; 2]) {
+    for (port, device_type) in (1..=2).zip(devices.iter()) {
+        if let Some(device_type) = device_type {
+            nes.connect_input_device(port, *device_type);
+        }
+    }
+}
+
+/// Sends the finished picture to the UI
+fn send_frame(
+    frame_sender: &Sender<FrameSenderMsg>,
+    nes: &NES,
+    last_frame: &mut Instant,
+    speed: f64,
+) {
+    let now: Instant = Instant::now();
+    let frametime: Duration = now - *last_frame;
+    *last_frame = now;
+
+    let result: Result<(), TrySendError<FrameSenderMsg>> = frame_sender.try_send(
+        FrameSenderMsg::Data(frametime, nes.renderer().pixels.clone()),
+    );
+    match result {
+        Ok(_) => {}
+        // Frames are expected to be dropped when running faster than the UI can show them
+        Err(TrySendError::Full(_)) if speed > 1.0 => {}
+        Err(TrySendError::Full(_)) => {
+            warn!("FrameSender TX channel was full when trying to send new frame data!")
+        }
+        Err(TrySendError::Disconnected(_)) => {
+            error!("FrameSender channel was disconnected when trying to send new frame data!")
+        }
+    };
+}
+
+/// What the debugger windows want snapshots of
+#[derive(Debug, Clone, Copy, Default)]
+struct Watchers {
+    cpu: bool,
+    memory: Option<MemoryRegion>,
+    /// The scanline nametable snapshots are taken at
+    nametables: Option<usize>,
+    tiles: bool,
+}
+
+/// Snapshots sent while running may be dropped, the next frame sends a new one anyway
+fn send_snapshot(thread_com: &ThreadCom, msg: ThreadMsg, wait: bool) {
+    let result: Result<(), ThreadComError> = match wait {
+        true => thread_com.await_send("nes-handle", msg, None),
+        false => thread_com.send("nes-handle", msg),
+    };
+    match result {
+        Ok(_) | Err(ThreadComError::Full) => {}
+        Err(err) => error!("Failed to send a snapshot to 'nes-handle'! - {:?}", err),
+    }
+}
+
+/// Sends a snapshot to every debugger window that is watching
+fn send_debug_snapshots(
+    thread_com: &ThreadCom,
+    nes: &NES,
+    watchers: &Watchers,
+    frame: usize,
+    history: &VecDeque<u16>,
+    wait: bool,
+) {
+    if watchers.cpu {
+        let snapshot: Box<CpuSnapshot> = Box::new(CpuSnapshot::capture(nes, frame, history));
+        send_snapshot(thread_com, ThreadMsg::CpuSnapshot(snapshot), wait);
+    }
+    if let Some(region) = watchers.memory {
+        let snapshot: Box<MemorySnapshot> = Box::new(MemorySnapshot::capture(nes, region));
+        send_snapshot(thread_com, ThreadMsg::MemorySnapshot(snapshot), wait);
+    }
+    if watchers.tiles {
+        let snapshot: Box<TileSnapshot> = Box::new(TileSnapshot::capture(nes));
+        send_snapshot(thread_com, ThreadMsg::TileSnapshot(snapshot), wait);
+    }
+    if watchers.nametables.is_some() {
+        let snapshot: Box<NametableSnapshot> = Box::new(NametableSnapshot::capture(nes));
+        send_snapshot(thread_com, ThreadMsg::NametableSnapshot(snapshot), wait);
+    }
+}
+
+fn write_movie(movie: &Movie, path: &Path) {
+    match std::fs::write(path, movie.to_fm2()) {
+        Ok(_) => info!("Saved {} frame movie to {:?}", movie.frames.len(), path),
+        Err(err) => error!("Failed to save movie to {:?}: {}", path, err),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MovieStatus {
+    Idle,
+    Recording,
+    Playing,
+}
+
 enum FrameSenderMsg {
     Data(Duration, Vec<RGB>),
     Exit,
@@ -92,10 +344,23 @@ impl Drop for FrameSender {
 enum NESMsg {
     Pause,
     Resume,
-    Step(usize),
+    Step(StepKind),
     Exit,
     ConnectDevice(u8, NESDeviceType),
     UpdateDeviceButton(u8, Box<dyn NESDeviceButton>, bool),
+    SaveState(usize),
+    LoadState(Vec<u8>),
+    Rewind(bool),
+    SetSpeed(f64),
+    RecordMovie(PathBuf, bool),
+    PlayMovie(Box<Movie>),
+    StopMovie,
+    WatchCpu(bool),
+    SetBreakpoints(Vec<Breakpoint>),
+    WatchMemory(Option<MemoryRegion>),
+    PokeMemory(MemoryRegion, usize, u8),
+    WatchNametables(Option<usize>),
+    WatchTiles(bool),
 }
 
 impl std::fmt::Debug for NESMsg {
@@ -103,7 +368,7 @@ impl std::fmt::Debug for NESMsg {
         match self {
             NESMsg::Pause => write!(f, "Pause"),
             NESMsg::Resume => write!(f, "Resume"),
-            NESMsg::Step(steps) => write!(f, "Step({})", steps),
+            NESMsg::Step(kind) => write!(f, "Step({:?})", kind),
             NESMsg::Exit => write!(f, "Exit"),
             NESMsg::ConnectDevice(port, device_type) => {
                 write!(f, "ConnectDevice({}, {:?})", port, device_type)
@@ -115,6 +380,31 @@ impl std::fmt::Debug for NESMsg {
                 device_button.get_button_type_string(),
                 pressed
             ),
+            NESMsg::SaveState(slot) => write!(f, "SaveState({})", slot),
+            NESMsg::LoadState(state) => write!(f, "LoadState([u8; {}])", state.len()),
+            NESMsg::Rewind(rewinding) => write!(f, "Rewind({})", rewinding),
+            NESMsg::SetSpeed(speed) => write!(f, "SetSpeed({})", speed),
+            NESMsg::RecordMovie(path, from_power_on) => {
+                write!(f, "RecordMovie({:?}, {})", path, from_power_on)
+            }
+            NESMsg::PlayMovie(movie) => {
+                write!(f, "PlayMovie([MovieFrame; {}])", movie.frames.len())
+            }
+            NESMsg::StopMovie => write!(f, "StopMovie"),
+            NESMsg::WatchCpu(watch) => write!(f, "WatchCpu({})", watch),
+            NESMsg::SetBreakpoints(list) => {
+                write!(f, "SetBreakpoints([Breakpoint; {}])", list.len())
+            }
+            NESMsg::WatchMemory(region) => write!(f, "WatchMemory({:?})", region),
+            NESMsg::PokeMemory(region, offset, value) => {
+                write!(
+                    f,
+                    "PokeMemory({:?}, {:#06X}, {:#04X})",
+                    region, offset, value
+                )
+            }
+            NESMsg::WatchNametables(scanline) => write!(f, "WatchNametables({:?})", scanline),
+            NESMsg::WatchTiles(watch) => write!(f, "WatchTiles({})", watch),
         }
     }
 }
@@ -124,12 +414,29 @@ impl Clone for NESMsg {
         match self {
             NESMsg::Pause => NESMsg::Pause,
             NESMsg::Resume => NESMsg::Resume,
-            NESMsg::Step(steps) => NESMsg::Step(*steps),
+            NESMsg::Step(kind) => NESMsg::Step(*kind),
             NESMsg::Exit => NESMsg::Exit,
             NESMsg::ConnectDevice(port, device_type) => NESMsg::ConnectDevice(*port, *device_type),
             NESMsg::UpdateDeviceButton(port, device_button, pressed) => {
                 NESMsg::UpdateDeviceButton(*port, device_button.box_clone(), *pressed)
             }
+            NESMsg::SaveState(slot) => NESMsg::SaveState(*slot),
+            NESMsg::LoadState(state) => NESMsg::LoadState(state.clone()),
+            NESMsg::Rewind(rewinding) => NESMsg::Rewind(*rewinding),
+            NESMsg::SetSpeed(speed) => NESMsg::SetSpeed(*speed),
+            NESMsg::RecordMovie(path, from_power_on) => {
+                NESMsg::RecordMovie(path.clone(), *from_power_on)
+            }
+            NESMsg::PlayMovie(movie) => NESMsg::PlayMovie(movie.clone()),
+            NESMsg::StopMovie => NESMsg::StopMovie,
+            NESMsg::WatchCpu(watch) => NESMsg::WatchCpu(*watch),
+            NESMsg::SetBreakpoints(list) => NESMsg::SetBreakpoints(list.clone()),
+            NESMsg::WatchMemory(region) => NESMsg::WatchMemory(*region),
+            NESMsg::PokeMemory(region, offset, value) => {
+                NESMsg::PokeMemory(*region, *offset, *value)
+            }
+            NESMsg::WatchNametables(scanline) => NESMsg::WatchNametables(*scanline),
+            NESMsg::WatchTiles(watch) => NESMsg::WatchTiles(*watch),
         }
     }
 }
@@ -159,7 +466,7 @@ impl NESMessenger {
                     Ok(message) => match message {
                         NESMsg::Pause => send_msg(&thread_com, ThreadMsg::Pause),
                         NESMsg::Resume => send_msg(&thread_com, ThreadMsg::Resume),
-                        NESMsg::Step(steps) => send_msg(&thread_com, ThreadMsg::Step(steps)),
+                        NESMsg::Step(kind) => send_msg(&thread_com, ThreadMsg::Step(kind)),
                         NESMsg::Exit => {
                             trace!("Terminating thread...");
                             break;
@@ -171,6 +478,43 @@ impl NESMessenger {
                             &thread_com,
                             ThreadMsg::UpdateDeviceButton(port, device_button, pressed),
                         ),
+                        NESMsg::SaveState(slot) => {
+                            send_msg(&thread_com, ThreadMsg::SaveState(slot))
+                        }
+                        NESMsg::LoadState(state) => {
+                            send_msg(&thread_com, ThreadMsg::LoadState(state))
+                        }
+                        NESMsg::Rewind(rewinding) => {
+                            send_msg(&thread_com, ThreadMsg::Rewind(rewinding))
+                        }
+                        NESMsg::SetSpeed(speed) => {
+                            send_msg(&thread_com, ThreadMsg::SetSpeed(speed))
+                        }
+                        NESMsg::RecordMovie(path, from_power_on) => {
+                            send_msg(&thread_com, ThreadMsg::RecordMovie(path, from_power_on))
+                        }
+                        NESMsg::PlayMovie(movie) => {
+                            send_msg(&thread_com, ThreadMsg::PlayMovie(movie))
+                        }
+                        NESMsg::StopMovie => send_msg(&thread_com, ThreadMsg::StopMovie),
+                        NESMsg::WatchCpu(watch) => {
+                            send_msg(&thread_com, ThreadMsg::WatchCpu(watch))
+                        }
+                        NESMsg::SetBreakpoints(list) => {
+                            send_msg(&thread_com, ThreadMsg::SetBreakpoints(list))
+                        }
+                        NESMsg::WatchMemory(region) => {
+                            send_msg(&thread_com, ThreadMsg::WatchMemory(region))
+                        }
+                        NESMsg::PokeMemory(region, offset, value) => {
+                            send_msg(&thread_com, ThreadMsg::PokeMemory(region, offset, value))
+                        }
+                        NESMsg::WatchNametables(scanline) => {
+                            send_msg(&thread_com, ThreadMsg::WatchNametables(scanline))
+                        }
+                        NESMsg::WatchTiles(watch) => {
+                            send_msg(&thread_com, ThreadMsg::WatchTiles(watch))
+                        }
                     },
                     Err(_) => error!("NESMessenger Channel was dropped!"),
                 }
@@ -212,10 +556,36 @@ pub struct NESManager {
     frametimes: Vec<f64>,
     frametimes_index: usize,
     pub screen_texture: egui::TextureHandle,
+    audio_buffer: SharedAudioBuffer,
+    sample_rate: u32,
+    pub saves_dir: Option<PathBuf>,
+    pub save_slots: Option<SaveSlots>,
+    /// Used when the next ROM is started
+    pub rewind_config: RewindConfig,
+    pub fast_forward_config: FastForwardConfig,
+    pub movie_status: MovieStatus,
+    /// Thumbnail for the next save state
+    last_frame: Option<ColorImage>,
+    /// What the NES sends snapshots of for the debugger
+    watchers: Watchers,
+    pub cpu_snapshot: Option<CpuSnapshot>,
+    /// A step was requested and hasn't finished yet
+    pub stepping: bool,
+    /// Kept between ROMs, and handed to every NES that starts
+    pub breakpoints: Vec<Breakpoint>,
+    breakpoint_hit: Option<BreakpointHit>,
+    memory_snapshot: Option<MemorySnapshot>,
+    nametable_snapshot: Option<NametableSnapshot>,
+    tile_snapshot: Option<TileSnapshot>,
 }
 
 impl NESManager {
-    pub fn new(texture_handle: egui::TextureHandle) -> Self {
+    pub fn new(
+        texture_handle: egui::TextureHandle,
+        audio_buffer: SharedAudioBuffer,
+        sample_rate: u32,
+        saves_dir: Option<PathBuf>,
+    ) -> Self {
         NESManager {
             nes_thread: None,
             thread_com: ThreadCom::new(Some(10)),
@@ -225,6 +595,22 @@ impl NESManager {
             frametimes: Vec::with_capacity(120),
             frametimes_index: 0,
             screen_texture: texture_handle,
+            audio_buffer,
+            sample_rate,
+            saves_dir,
+            save_slots: None,
+            rewind_config: RewindConfig::default(),
+            fast_forward_config: FastForwardConfig::default(),
+            movie_status: MovieStatus::Idle,
+            last_frame: None,
+            watchers: Watchers::default(),
+            cpu_snapshot: None,
+            stepping: false,
+            breakpoints: Vec::new(),
+            breakpoint_hit: None,
+            memory_snapshot: None,
+            nametable_snapshot: None,
+            tile_snapshot: None,
         }
     }
 
@@ -234,37 +620,65 @@ impl NESManager {
             "Ran `NESManager.start_nes()` when an NES instance is currently running!"
         );
         self.nes_messenger = Some(NESMessenger::new(&self.thread_com));
+        self.save_slots = Some(SaveSlots::new(&rom_path, self.saves_dir.as_deref()));
         let thread_com: ThreadCom = self.thread_com.clone();
+        let audio_buffer: SharedAudioBuffer = self.audio_buffer.clone();
+        let sample_rate: u32 = self.sample_rate;
+        let saves_dir: Option<PathBuf> = self.saves_dir.clone();
+        let rewind_config: RewindConfig = self.rewind_config;
+        let mut watchers: Watchers = self.watchers;
+        let breakpoints: Vec<Breakpoint> = self.breakpoints.clone();
 
         self.nes_thread = Some(new_named_thread("nes", move || {
             let frame_sender_obj: FrameSender = FrameSender::new(&thread_com);
             let frame_sender: Sender<FrameSenderMsg> = frame_sender_obj.tx.clone();
 
-            let rom_bytes: Vec<u8> = std::fs::read(rom_path).unwrap();
+            let rom_bytes: Vec<u8> = std::fs::read(&rom_path).unwrap();
             let rom: ROM = ROM::new(&rom_bytes).unwrap();
             let mut nes: NES = NES::new(rom);
+            nes.apu_mut().set_sample_rate(Some(sample_rate as f64));
+            nes.breakpoints_mut().set(breakpoints);
+            let bank_size: usize = nes.mapper().prg_bank_size();
+            match Symbols::load_for_rom(&rom_path, bank_size) {
+                Ok(symbols) => nes.bus_mut().symbols = symbols,
+                Err(err) => error!("Failed to load symbols: {}", err),
+            }
             let mut last_frame: Instant = Instant::now();
+            // Movies that start from power-on start from here
+            let power_on: Vec<u8> = nes.save_state();
 
-            let cb_frame_sender: Sender<FrameSenderMsg> = frame_sender.clone();
-            nes.render_callback(move |renderer: RcRef<Renderer>, _, _| {
-                let renderer: Ref<Renderer> = renderer.borrow();
+            let mut save_path: Option<PathBuf> = nes.rom().uses_bat_mem.then(|| save_file_path(&rom_path, saves_dir.as_deref()));
+            if let Some(save_path) = save_path.as_ref() {
+                load_battery_ram(&mut nes, save_path);
+            }
+            let mut last_saved: Vec<u8> = nes.battery_ram().unwrap_or_default();
+            // Counts frames at the start of vblank, the same way `NES::step_frame` does, so it
+            // keeps going while a ROM runs with NMIs disabled
+            let mut frame_count: usize = 0;
+            let mut last_flush_frame: usize = 0;
 
-                let now: Instant = Instant::now();
-                let frametime: Duration = now - last_frame;
-                last_frame = now;
+            let mut rewind: Option<RewindBuffer> = rewind_config.enabled.then(|| RewindBuffer::new(rewind_config.buffer_size * 1024 * 1024));
+            let rewind_interval: usize = rewind_config.interval.max(1);
+            let frame_rate: f64 = nes.rom().region.frame_rate();
+            let rewind_frametime: Duration = Duration::from_secs_f64(rewind_interval as f64 / (frame_rate * rewind_config.speed.max(0.01)));
+            let mut last_snapshot_frame: usize = 0;
+            let mut rewinding: bool = false;
+            let mut last_rewind_frame: Instant = Instant::now();
 
-                let result: Result<(), TrySendError<FrameSenderMsg>> = cb_frame_sender.try_send(FrameSenderMsg::Data(frametime, renderer.pixels.clone()));
-                match result {
-                    Ok(_) => {},
-                    Err(TrySendError::Full(_)) => warn!("FrameSender TX channel was full when trying to send new frame data!"),
-                    Err(TrySendError::Disconnected(_)) => error!("FrameSender channel was disconnected when trying to send new frame data!"),
-                };
-            });
+            let mut pacer: FramePacer = FramePacer::new(frame_rate);
+            let mut speed: f64 = 1.0;
+
+            let rom_filename: String = rom_path.file_name().unwrap_or_default().to_string_lossy().to_string();
+            let mut devices: [Option<NESDeviceType>; 2] = [None, None];
+            // Buttons held by the player, which movie playback overrides
+            let mut joypads: [JoypadButton; 2] = [JoypadButton::empty(); 2];
+            let mut recording: Option<(Movie, PathBuf)> = None;
+            let mut player: Option<MoviePlayer> = None;
 
             let mut paused: bool = false;
-            let mut stepping: bool = false;
-            // i32 just to prevent potential crashes from Subtracting With Overflow panics
-            let mut steps_left: i32 = 0;
+            let mut stepper: Option<Stepper> = None;
+            // Where the last instructions started, for the debugger
+            let mut history: VecDeque<u16> = VecDeque::with_capacity(HISTORY_LEN);
             'nes_loop: loop {
                 if !thread_com.is_rx_empty("nes") {
                     let messages: Vec<ThreadMsg> = thread_com.get_waiting_messages("nes");
@@ -279,52 +693,315 @@ impl NESManager {
                             ThreadMsg::Pause => {
                                 trace!("Paused");
                                 paused = true;
+                                send_debug_snapshots(&thread_com, &nes, &watchers, frame_count, &history, true);
                             }
                             ThreadMsg::Resume => {
                                 trace!("Resumed");
                                 paused = false;
+                                // Resuming cancels a step that is still running, e.g. a run to cursor
+                                if stepper.take().is_some() {
+                                    let result: Result<(), ThreadComError> = thread_com.await_send("nes-handle", ThreadMsg::SteppingFinished, None);
+                                    if let Err(err) = result {
+                                        error!("Failed to send ThreadMsg::SteppingFinished message to 'nes-handle'! - {:?}", err);
+                                    }
+                                }
+                            }
+                            ThreadMsg::Step(kind) => {
+                                trace!("Stepping: {:?}", kind);
+                                stepper = Some(Stepper::new(*kind, &nes));
+                            }
+                            ThreadMsg::WatchCpu(watch) => {
+                                watchers.cpu = *watch;
+                                history.clear();
+                                if *watch {
+                                    let snapshot: Box<CpuSnapshot> = Box::new(CpuSnapshot::capture(&nes, frame_count, &history));
+                                    send_snapshot(&thread_com, ThreadMsg::CpuSnapshot(snapshot), true);
+                                }
+                            }
+                            ThreadMsg::SetBreakpoints(list) => {
+                                nes.breakpoints_mut().set(list.clone());
+                            }
+                            ThreadMsg::WatchMemory(region) => {
+                                watchers.memory = *region;
+                                if let Some(region) = *region {
+                                    let snapshot: Box<MemorySnapshot> = Box::new(MemorySnapshot::capture(&nes, region));
+                                    send_snapshot(&thread_com, ThreadMsg::MemorySnapshot(snapshot), true);
+                                }
+                            }
+                            ThreadMsg::PokeMemory(region, offset, value) => {
+                                memory::poke(&nes, *region, *offset, *value);
+                                if paused {
+                                    send_debug_snapshots(&thread_com, &nes, &watchers, frame_count, &history, true);
+                                }
+                            }
+                            ThreadMsg::WatchNametables(scanline) => {
+                                watchers.nametables = *scanline;
+                                if scanline.is_some() {
+                                    let snapshot: Box<NametableSnapshot> = Box::new(NametableSnapshot::capture(&nes));
+                                    send_snapshot(&thread_com, ThreadMsg::NametableSnapshot(snapshot), true);
+                                }
                             }
-                            ThreadMsg::Step(steps) => {
-                                stepping = true;
-                                steps_left += *steps as i32;
-                                // trace!("Stepping {}(+{}) steps", steps_left, steps);
+                            ThreadMsg::WatchTiles(watch) => {
+                                watchers.tiles = *watch;
+                                if *watch {
+                                    let snapshot: Box<TileSnapshot> = Box::new(TileSnapshot::capture(&nes));
+                                    send_snapshot(&thread_com, ThreadMsg::TileSnapshot(snapshot), true);
+                                }
                             }
                             ThreadMsg::ConnectDevice(port, device_type) => {
                                 nes.connect_input_device(*port, *device_type);
+                                devices[*port as usize - 1] = Some(*device_type);
                                 trace!("Connected {:?} to port {}", device_type, port);
                             }
                             ThreadMsg::UpdateDeviceButton(port, device_button, pressed) => {
+                                if let Some(button) = device_button.as_any().downcast_ref::<JoypadButton>() {
+                                    let index: usize = (*port == 2 && nes.device2.is_some()) as usize;
+                                    joypads[index].set(*button, *pressed);
+                                }
+                                // Movies latch the buttons at the start of every frame instead
+                                if player.is_some() || recording.is_some() {
+                                    continue;
+                                }
+
                                 if *port == 2 && nes.device2.is_some() {
                                     nes.device2_mut().set_button_pressed_status(device_button.box_clone(), *pressed);
                                 } else if nes.device1.is_some() {
                                     nes.device1_mut().set_button_pressed_status(device_button.box_clone(), *pressed);
                                 }
                             }
+                            ThreadMsg::SaveState(slot) => {
+                                let result: Result<(), ThreadComError> = thread_com.await_send("nes-handle", ThreadMsg::StateSaved(*slot, nes.save_state()), None);
+                                if let Err(err) = result {
+                                    error!("Failed to send ThreadMsg::StateSaved message to 'nes-handle'! - {:?}", err);
+                                }
+                            }
+                            ThreadMsg::LoadState(_) if recording.is_some() || player.is_some() => {
+                                warn!("Can't load a save state while a movie is recording or playing");
+                            }
+                            ThreadMsg::LoadState(state) => {
+                                let result: Result<(), ThreadComError> = thread_com.await_send("nes-handle", ThreadMsg::StateLoaded(nes.load_state(state)), None);
+                                if let Err(err) = result {
+                                    error!("Failed to send ThreadMsg::StateLoaded message to 'nes-handle'! - {:?}", err);
+                                }
+                                history.clear();
+                                if paused {
+                                    send_debug_snapshots(&thread_com, &nes, &watchers, frame_count, &history, true);
+                                }
+                            }
+                            ThreadMsg::Rewind(held) => {
+                                trace!("Rewinding: {}", held);
+                                rewinding = *held;
+                            }
+                            ThreadMsg::SetSpeed(new_speed) => {
+                                trace!("Speed set to {}x", new_speed);
+                                speed = new_speed.max(0.01);
+                            }
+                            ThreadMsg::RecordMovie(..) | ThreadMsg::PlayMovie(_) if recording.is_some() || player.is_some() => {
+                                warn!("A movie is already recording or playing");
+                            }
+                            ThreadMsg::RecordMovie(path, from_power_on) => {
+                                if *from_power_on {
+                                    stop_battery_saves(&nes, &mut save_path, &mut last_saved);
+                                    reconnect_devices(&mut nes, &devices);
+                                    if let Err(err) = nes.load_state(&power_on) {
+                                        error!("Failed to power cycle for the movie: {}", err);
+                                        continue;
+                                    }
+                                }
+
+                                set_joypads(&mut nes, joypads);
+                                let start_state: Option<Vec<u8>> = (!from_power_on).then(|| nes.save_state());
+                                let mut movie: Movie = Movie::new(&nes.rom(), &rom_filename, start_state);
+                                movie.joypad2 = devices[1].is_some();
+                                movie.frames.push(MovieFrame::new(joypads));
+                                recording = Some((movie, path.clone()));
+                                if let Some(rewind) = rewind.as_mut() {
+                                    rewind.clear();
+                                }
+                                info!("Recording movie to {:?}", path);
+                            }
+                            ThreadMsg::PlayMovie(movie) => {
+                                if movie.start_state.is_none() {
+                                    stop_battery_saves(&nes, &mut save_path, &mut last_saved);
+                                    reconnect_devices(&mut nes, &devices);
+                                }
+
+                                match MoviePlayer::start(&mut nes, (**movie).clone(), &power_on) {
+                                    Ok(mut movie_player) => {
+                                        movie_player.next_frame(&mut nes);
+                                        player = Some(movie_player);
+                                        if let Some(rewind) = rewind.as_mut() {
+                                            rewind.clear();
+                                        }
+                                        info!("Playing {} frame movie", movie.frames.len());
+                                    }
+                                    Err(err) => {
+                                        error!("Failed to start movie: {}", err);
+                                        let result: Result<(), ThreadComError> = thread_com.await_send("nes-handle", ThreadMsg::MovieFinished, None);
+                                        if let Err(err) = result {
+                                            error!("Failed to send ThreadMsg::MovieFinished message to 'nes-handle'! - {:?}", err);
+                                        }
+                                    }
+                                }
+                            }
+                            ThreadMsg::StopMovie => {
+                                if let Some((movie, path)) = recording.take() {
+                                    set_joypads(&mut nes, joypads);
+                                    write_movie(&movie, &path);
+                                }
+                                if player.take().is_some() {
+                                    set_joypads(&mut nes, joypads);
+                                    info!("Stopped movie playback");
+                                }
+                            }
                             _ => error!("NES received a '{:?}' message, which it cannot proccess. Ignoring message", message),
                         };
                     }
                 }
 
-                if !paused || stepping {
-                    let nes_running: bool = nes.step(|_| {});
+                // Snapshots are taken right after a frame finished, so loading one shows that frame
+                let movie_active: bool = recording.is_some() || player.is_some();
+                if rewinding && !paused && !movie_active && let Some(rewind) = rewind.as_mut() {
+                    let now: Instant = Instant::now();
+                    let next_rewind_frame: Instant = last_rewind_frame + rewind_frametime;
+                    if now < next_rewind_frame {
+                        thread::sleep(next_rewind_frame - now);
+                        continue 'nes_loop;
+                    }
+
+                    if let Some(state) = rewind.pop() {
+                        if let Err(err) = nes.load_state(&state) {
+                            error!("Failed to rewind: {}", err);
+                        }
+                        last_snapshot_frame = frame_count;
+
+                        let result: Result<(), TrySendError<FrameSenderMsg>> = frame_sender.try_send(FrameSenderMsg::Data(now - last_rewind_frame, nes.renderer().pixels.clone()));
+                        if let Err(TrySendError::Disconnected(_)) = result {
+                            error!("FrameSender channel was disconnected when trying to send rewound frame data!");
+                        }
+                    }
+                    last_rewind_frame = now;
+                    continue 'nes_loop;
+                }
+
+                if !paused || stepper.is_some() {
+                    let last_scanline: usize = nes.ppu().scanline;
+                    let nes_running: bool = nes.step(|cpu: &mut CPU| {
+                        if watchers.cpu {
+                            if history.len() == HISTORY_LEN {
+                                history.pop_front();
+                            }
+                            history.push_back(cpu.program_counter);
+                        }
+                    });
                     if !nes_running {
                         error!("The NES stopped on its own!");
                         break 'nes_loop;
                     }
-                    if stepping {
-                        steps_left -= 1;
-                        if steps_left <= 0 {
-                            stepping = false;
-                            let result: Result<(), ThreadComError> = thread_com.await_send("nes-handle", ThreadMsg::SteppingFinished, None);
-                            if let Err(err) = result {
-                                error!("Failed to send ThreadMsg::SteppingFinished message to 'nes-handle'! - {:?}", err);
+
+                    let scanline: usize = nes.ppu().scanline;
+                    let new_frame: bool = last_scanline < VBLANK_SCANLINE && scanline >= VBLANK_SCANLINE;
+                    if new_frame {
+                        frame_count += 1;
+                        send_frame(&frame_sender, &nes, &mut last_frame, speed);
+                    }
+
+                    if let Some(capture_at) = watchers.nametables
+                        && stepper.is_none()
+                        && scanline == capture_at
+                        && last_scanline != capture_at
+                    {
+                        let snapshot: Box<NametableSnapshot> = Box::new(NametableSnapshot::capture(&nes));
+                        send_snapshot(&thread_com, ThreadMsg::NametableSnapshot(snapshot), false);
+                    }
+
+                    let mut apu: RefMut<APU> = nes.apu_mut();
+                    if let Some(resampler) = apu.resampler.as_mut()
+                        && resampler.pending_samples() >= AUDIO_CHUNK_SIZE
+                    {
+                        // Sped up audio would only overrun the buffer, so it's muted instead
+                        let samples: std::vec::Drain<f32> = resampler.drain_samples();
+                        if speed <= 1.0 {
+                            for sample in samples {
+                                audio_buffer.push(sample);
                             }
-                            trace!("Finished stepping");
                         }
                     }
+                    drop(apu);
+
+                    if let Some(save_path) = save_path.as_ref()
+                        && frame_count - last_flush_frame >= BATTERY_FLUSH_FRAMES
+                    {
+                        last_flush_frame = frame_count;
+                        flush_battery_ram(&nes, save_path, &mut last_saved);
+                    }
+
+                    if let Some(rewind) = rewind.as_mut()
+                        && frame_count - last_snapshot_frame >= rewind_interval
+                    {
+                        last_snapshot_frame = frame_count;
+                        rewind.push(nes.save_state());
+                    }
+
+                    if new_frame {
+                        // The same boundary playback applies the next frame's input at
+                        if let Some((movie, _)) = recording.as_mut() {
+                            set_joypads(&mut nes, joypads);
+                            movie.frames.push(MovieFrame::new(joypads));
+                        }
+                        if let Some(movie_player) = player.as_mut() {
+                            match movie_player.is_finished() {
+                                true => {
+                                    player = None;
+                                    set_joypads(&mut nes, joypads);
+                                    info!("Movie finished");
+                                    let result: Result<(), ThreadComError> = thread_com.await_send("nes-handle", ThreadMsg::MovieFinished, None);
+                                    if let Err(err) = result {
+                                        error!("Failed to send ThreadMsg::MovieFinished message to 'nes-handle'! - {:?}", err);
+                                    }
+                                }
+                                false => movie_player.next_frame(&mut nes),
+                            }
+                        }
+
+                        if stepper.is_none() {
+                            // Nametables are captured at their own scanline instead
+                            let frame_watchers: Watchers = Watchers { nametables: None, ..watchers };
+                            send_debug_snapshots(&thread_com, &nes, &frame_watchers, frame_count, &history, false);
+                            pacer.wait(speed);
+                        }
+                    }
+
+                    if let Some(hit) = nes.take_breakpoint_hit() {
+                        paused = true;
+                        stepper = None;
+                        let result: Result<(), ThreadComError> = thread_com.await_send("nes-handle", ThreadMsg::BreakpointHit(hit), None);
+                        if let Err(err) = result {
+                            error!("Failed to send ThreadMsg::BreakpointHit message to 'nes-handle'! - {:?}", err);
+                        }
+                        send_debug_snapshots(&thread_com, &nes, &watchers, frame_count, &history, true);
+                        trace!("Hit a breakpoint");
+                    } else if let Some(active) = stepper.as_mut()
+                        && active.finished(&nes)
+                    {
+                        stepper = None;
+                        let result: Result<(), ThreadComError> = thread_com.await_send("nes-handle", ThreadMsg::SteppingFinished, None);
+                        if let Err(err) = result {
+                            error!("Failed to send ThreadMsg::SteppingFinished message to 'nes-handle'! - {:?}", err);
+                        }
+                        send_debug_snapshots(&thread_com, &nes, &watchers, frame_count, &history, true);
+                        trace!("Finished stepping");
+                    }
                 }
             }
 
+            if let Some(save_path) = save_path.as_ref() {
+                flush_battery_ram(&nes, save_path, &mut last_saved);
+            }
+            if let Some((movie, path)) = recording.take() {
+                write_movie(&movie, &path);
+            }
+
             frame_sender.send(FrameSenderMsg::Exit).expect("FrameSender channel was disconnected before it was closed properly!");
             trace!("Terminating thread...");
         }).unwrap());
@@ -359,6 +1036,17 @@ impl NESManager {
         if let Err(err) = nes_messenger.thread_handle.take().unwrap().join() {
             error!("The 'nes-messenger' thread panicked: {:?}", err);
         }
+
+        self.audio_buffer.clear();
+        self.save_slots = None;
+        self.last_frame = None;
+        self.movie_status = MovieStatus::Idle;
+        self.cpu_snapshot = None;
+        self.stepping = false;
+        self.breakpoint_hit = None;
+        self.memory_snapshot = None;
+        self.nametable_snapshot = None;
+        self.tile_snapshot = None;
     }
 
     pub fn handle_nes_messages(&mut self) {
@@ -367,12 +1055,21 @@ impl NESManager {
         }
 
         let messages: Vec<ThreadMsg> = self.thread_com.get_waiting_messages("nes-handle");
-        for message in messages.iter() {
+        // Only the newest frame is uploaded when the NES runs faster than the UI
+        let newest_frame: Option<usize> = messages
+            .iter()
+            .rposition(|message: &ThreadMsg| matches!(message, ThreadMsg::NewFrame(..)));
+        for (index, message) in messages.iter().enumerate() {
             match message {
                 ThreadMsg::NewFrame(frametime, frame) => {
                     // debug!("New frame data received");
                     // self.event_loop_proxy.send_event(NesmurEvent::NES(NESEvent::NewFrame(pixels.to_owned()))).unwrap();
 
+                    self.update_frametimes(*frametime);
+                    if Some(index) != newest_frame {
+                        continue;
+                    }
+
                     let mut pixels: Vec<u8> = Vec::with_capacity(frame.len() * 3);
                     for color in frame {
                         pixels.push(color.0);
@@ -381,26 +1078,50 @@ impl NESManager {
                         pixels.push(0xFF);
                     }
 
-                    self.screen_texture.set(
-                        ColorImage::from_rgba_unmultiplied([256, 240], &pixels),
-                        egui::TextureOptions::NEAREST,
-                    );
+                    let image: ColorImage = ColorImage::from_rgba_unmultiplied([256, 240], &pixels);
+                    self.last_frame = Some(image.clone());
+                    self.screen_texture
+                        .set(image, egui::TextureOptions::NEAREST);
+                }
 
-                    let frametime: f64 = (*frametime).as_micros() as f64 / 1000.0;
-                    if self.frametimes.len() != self.frametimes.capacity() {
-                        self.frametimes.push(frametime);
-                    } else {
-                        self.frametimes[self.frametimes_index] = frametime;
+                ThreadMsg::StateSaved(slot, state) => {
+                    let Some(save_slots) = self.save_slots.as_mut() else {
+                        continue;
+                    };
+                    match save_slots.write(*slot, state, self.last_frame.clone()) {
+                        Ok(_) => info!("Saved state to slot {}", slot),
+                        Err(err) => error!("Failed to save state to slot {}: {}", slot, err),
                     }
-                    self.frametimes_index =
-                        (self.frametimes_index + 1) % self.frametimes.capacity();
-                    self.frametime =
-                        self.frametimes.iter().sum::<f64>() / self.frametimes.len() as f64;
-                    self.framerate = 1000.0 / self.frametime;
                 }
 
+                ThreadMsg::StateLoaded(result) => match result {
+                    Ok(_) => info!("Loaded save state"),
+                    Err(err) => error!("Failed to load save state: {}", err),
+                },
+
+                ThreadMsg::MovieFinished => self.movie_status = MovieStatus::Idle,
+
                 ThreadMsg::SteppingFinished => {
                     // self.event_loop_proxy.send_event(NesmurEvent::NES(NESEvent::SteppingFinished)).unwrap();
+                    self.stepping = false;
+                }
+
+                ThreadMsg::CpuSnapshot(snapshot) => self.cpu_snapshot = Some((**snapshot).clone()),
+
+                ThreadMsg::MemorySnapshot(snapshot) => {
+                    self.memory_snapshot = Some((**snapshot).clone())
+                }
+                ThreadMsg::NametableSnapshot(snapshot) => {
+                    self.nametable_snapshot = Some((**snapshot).clone())
+                }
+                ThreadMsg::TileSnapshot(snapshot) => {
+                    self.tile_snapshot = Some((**snapshot).clone())
+                }
+
+                ThreadMsg::BreakpointHit(hit) => {
+                    info!("Hit breakpoint at ${:04X}", hit.program_counter);
+                    self.stepping = false;
+                    self.breakpoint_hit = Some(hit.clone());
                 }
 
                 _ => error!(
@@ -411,6 +1132,18 @@ impl NESManager {
         }
     }
 
+    fn update_frametimes(&mut self, frametime: Duration) {
+        let frametime: f64 = frametime.as_micros() as f64 / 1000.0;
+        if self.frametimes.len() != self.frametimes.capacity() {
+            self.frametimes.push(frametime);
+        } else {
+            self.frametimes[self.frametimes_index] = frametime;
+        }
+        self.frametimes_index = (self.frametimes_index + 1) % self.frametimes.capacity();
+        self.frametime = self.frametimes.iter().sum::<f64>() / self.frametimes.len() as f64;
+        self.framerate = 1000.0 / self.frametime;
+    }
+
     fn send_nes_message(&self, msg: NESMsg) {
         if self.nes_thread.is_none() || self.nes_messenger.is_none() {
             return;
@@ -443,8 +1176,135 @@ impl NESManager {
         self.send_nes_message(NESMsg::Resume);
     }
 
-    pub fn step(&self, steps: usize) {
-        self.send_nes_message(NESMsg::Step(steps));
+    pub fn step(&mut self, kind: StepKind) {
+        self.send_nes_message(NESMsg::Step(kind));
+        self.stepping = true;
+    }
+
+    /// The NES only sends CPU snapshots while something shows them
+    pub fn watch_cpu(&mut self, watch: bool) {
+        if watch == self.watchers.cpu {
+            return;
+        }
+        self.watchers.cpu = watch;
+        self.send_nes_message(NESMsg::WatchCpu(watch));
+        if !watch {
+            self.cpu_snapshot = None;
+        }
+    }
+
+    pub fn set_breakpoints(&mut self, breakpoints: Vec<Breakpoint>) {
+        self.send_nes_message(NESMsg::SetBreakpoints(breakpoints.clone()));
+        self.breakpoints = breakpoints;
+    }
+
+    /// A breakpoint the NES paused on since the last call
+    pub fn take_breakpoint_hit(&mut self) -> Option<BreakpointHit> {
+        self.breakpoint_hit.take()
+    }
+
+    /// The NES only sends memory snapshots of the region something shows
+    pub fn watch_memory(&mut self, region: Option<MemoryRegion>) {
+        if region == self.watchers.memory {
+            return;
+        }
+        self.watchers.memory = region;
+        self.send_nes_message(NESMsg::WatchMemory(region));
+        self.memory_snapshot = None;
+    }
+
+    /// The newest memory snapshot since the last call
+    pub fn take_memory_snapshot(&mut self) -> Option<MemorySnapshot> {
+        self.memory_snapshot.take()
+    }
+
+    pub fn poke_memory(&self, region: MemoryRegion, offset: usize, value: u8) {
+        self.send_nes_message(NESMsg::PokeMemory(region, offset, value));
+    }
+
+    /// The NES only sends nametable snapshots while something shows them, taken as the PPU
+    /// reaches `scanline`
+    pub fn watch_nametables(&mut self, scanline: Option<usize>) {
+        if scanline == self.watchers.nametables {
+            return;
+        }
+        self.watchers.nametables = scanline;
+        self.send_nes_message(NESMsg::WatchNametables(scanline));
+        self.nametable_snapshot = None;
+    }
+
+    /// The newest nametable snapshot since the last call
+    pub fn take_nametable_snapshot(&mut self) -> Option<NametableSnapshot> {
+        self.nametable_snapshot.take()
+    }
+
+    /// The NES only sends pattern table and palette snapshots while something shows them
+    pub fn watch_tiles(&mut self, watch: bool) {
+        if watch == self.watchers.tiles {
+            return;
+        }
+        self.watchers.tiles = watch;
+        self.send_nes_message(NESMsg::WatchTiles(watch));
+        self.tile_snapshot = None;
+    }
+
+    /// The newest pattern table and palette snapshot since the last call
+    pub fn take_tile_snapshot(&mut self) -> Option<TileSnapshot> {
+        self.tile_snapshot.take()
+    }
+
+    pub fn save_state(&self, slot: usize) {
+        self.send_nes_message(NESMsg::SaveState(slot));
+    }
+
+    /// Records every frame's input to an `.fm2` file at `path` until `stop_movie()`
+    pub fn record_movie(&mut self, path: PathBuf, from_power_on: bool) {
+        self.send_nes_message(NESMsg::RecordMovie(path, from_power_on));
+        self.movie_status = MovieStatus::Recording;
+    }
+
+    pub fn play_movie(&mut self, path: &Path) {
+        let movie: Result<Movie, String> = std::fs::read_to_string(path)
+            .map_err(|err: std::io::Error| err.to_string())
+            .and_then(|text: String| {
+                Movie::from_fm2(&text).map_err(|err: nes::movie::MovieError| err.to_string())
+            });
+        match movie {
+            Ok(movie) => {
+                self.send_nes_message(NESMsg::PlayMovie(Box::new(movie)));
+                self.movie_status = MovieStatus::Playing;
+            }
+            Err(err) => error!("Failed to read movie {:?}: {}", path, err),
+        }
+    }
+
+    pub fn stop_movie(&mut self) {
+        self.send_nes_message(NESMsg::StopMovie);
+        self.movie_status = MovieStatus::Idle;
+    }
+
+    /// Runs at the fast-forward speed while `fast_forward` is set, otherwise at normal speed
+    pub fn set_fast_forward(&self, fast_forward: bool) {
+        let speed: f64 = match fast_forward {
+            true => self.fast_forward_config.speed,
+            false => 1.0,
+        };
+        self.send_nes_message(NESMsg::SetSpeed(speed));
+    }
+
+    /// Plays frames backwards while `rewinding` is set
+    pub fn set_rewinding(&self, rewinding: bool) {
+        self.send_nes_message(NESMsg::Rewind(rewinding));
+    }
+
+    pub fn load_state(&self, slot: usize) {
+        let Some(save_slots) = self.save_slots.as_ref() else {
+            return;
+        };
+        match save_slots.read(slot) {
+            Ok(state) => self.send_nes_message(NESMsg::LoadState(state)),
+            Err(err) => error!("Failed to read save state from slot {}: {}", slot, err),
+        }
     }
 
     pub fn connect_device(&self, port: u8, device_type: NESDeviceType) {
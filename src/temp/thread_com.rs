@@ -1,12 +1,23 @@
+use crate::debugger::{
+    StepKind,
+    cpu::CpuSnapshot,
+    memory::{MemoryRegion, MemorySnapshot},
+    nametable::NametableSnapshot,
+    tiles::TileSnapshot,
+};
 use crossbeam::channel::{
     self, Receiver, RecvTimeoutError, SendTimeoutError, Sender, TryRecvError, TrySendError,
 };
 use nes::{
+    breakpoint::{Breakpoint, BreakpointHit},
     input_device::{NESDeviceButton, NESDeviceType},
+    movie::Movie,
     ppu::renderer::RGB,
+    save_state::SaveStateError,
 };
 use std::{
     collections::VecDeque,
+    path::PathBuf,
     sync::{Arc, Mutex, MutexGuard},
     time::{Duration, Instant},
 };
@@ -24,12 +35,44 @@ pub enum ThreadComError {
 pub enum ThreadMsg {
     Pause,
     Resume,
-    Step(usize),
+    Step(StepKind),
     NewFrame(Duration, Vec<RGB>),
     Stop,
     SteppingFinished,
     ConnectDevice(u8, NESDeviceType),
     UpdateDeviceButton(u8, Box<dyn NESDeviceButton>, bool),
+    /// Asks the NES for a save state for the given slot
+    SaveState(usize),
+    /// The save state for a slot, sent back by the NES
+    StateSaved(usize, Vec<u8>),
+    LoadState(Vec<u8>),
+    StateLoaded(Result<(), SaveStateError>),
+    /// Whether the NES should play backwards
+    Rewind(bool),
+    /// Emulation speed relative to the console's frame rate
+    SetSpeed(f64),
+    /// Starts recording a movie to the path, from power-on or from the current state
+    RecordMovie(PathBuf, bool),
+    PlayMovie(Box<Movie>),
+    StopMovie,
+    /// Movie playback ended, sent back by the NES
+    MovieFinished,
+    /// Whether the NES should send CPU snapshots for the debugger
+    WatchCpu(bool),
+    CpuSnapshot(Box<CpuSnapshot>),
+    SetBreakpoints(Vec<Breakpoint>),
+    /// The NES paused itself on a breakpoint
+    BreakpointHit(BreakpointHit),
+    /// Which region the NES should send memory snapshots of, if any
+    WatchMemory(Option<MemoryRegion>),
+    MemorySnapshot(Box<MemorySnapshot>),
+    PokeMemory(MemoryRegion, usize, u8),
+    /// The scanline the NES should send nametable snapshots at, if any
+    WatchNametables(Option<usize>),
+    NametableSnapshot(Box<NametableSnapshot>),
+    /// Whether the NES should send pattern table and palette snapshots
+    WatchTiles(bool),
+    TileSnapshot(Box<TileSnapshot>),
 }
 
 impl std::fmt::Debug for ThreadMsg {
@@ -46,7 +89,7 @@ impl std::fmt::Debug for ThreadMsg {
             ThreadMsg::Stop => write!(f, "Stop"),
             ThreadMsg::Pause => write!(f, "Pause"),
             ThreadMsg::Resume => write!(f, "Resume"),
-            ThreadMsg::Step(steps) => write!(f, "Step({})", steps),
+            ThreadMsg::Step(kind) => write!(f, "Step({:?})", kind),
             ThreadMsg::SteppingFinished => write!(f, "SteppingFinished"),
             ThreadMsg::ConnectDevice(port, device_type) => {
                 write!(f, "ConnectDevice({}, {:?})", port, device_type)
@@ -58,6 +101,52 @@ impl std::fmt::Debug for ThreadMsg {
                 device_button.get_button_type_string(),
                 pressed
             ),
+            ThreadMsg::SaveState(slot) => write!(f, "SaveState({})", slot),
+            ThreadMsg::StateSaved(slot, state) => {
+                write!(f, "StateSaved({}, [u8; {}])", slot, state.len())
+            }
+            ThreadMsg::LoadState(state) => write!(f, "LoadState([u8; {}])", state.len()),
+            ThreadMsg::StateLoaded(result) => write!(f, "StateLoaded({:?})", result),
+            ThreadMsg::Rewind(rewinding) => write!(f, "Rewind({})", rewinding),
+            ThreadMsg::SetSpeed(speed) => write!(f, "SetSpeed({})", speed),
+            ThreadMsg::RecordMovie(path, from_power_on) => {
+                write!(f, "RecordMovie({:?}, {})", path, from_power_on)
+            }
+            ThreadMsg::PlayMovie(movie) => {
+                write!(f, "PlayMovie([MovieFrame; {}])", movie.frames.len())
+            }
+            ThreadMsg::StopMovie => write!(f, "StopMovie"),
+            ThreadMsg::MovieFinished => write!(f, "MovieFinished"),
+            ThreadMsg::WatchCpu(watch) => write!(f, "WatchCpu({})", watch),
+            ThreadMsg::CpuSnapshot(snapshot) => {
+                write!(f, "CpuSnapshot(PC: {:04X})", snapshot.program_counter)
+            }
+            ThreadMsg::SetBreakpoints(list) => {
+                write!(f, "SetBreakpoints([Breakpoint; {}])", list.len())
+            }
+            ThreadMsg::BreakpointHit(hit) => write!(f, "BreakpointHit({:?})", hit),
+            ThreadMsg::WatchMemory(region) => write!(f, "WatchMemory({:?})", region),
+            ThreadMsg::MemorySnapshot(snapshot) => write!(
+                f,
+                "MemorySnapshot({:?}, [u8; {}])",
+                snapshot.region,
+                snapshot.data.len()
+            ),
+            ThreadMsg::PokeMemory(region, offset, value) => {
+                write!(
+                    f,
+                    "PokeMemory({:?}, {:#06X}, {:#04X})",
+                    region, offset, value
+                )
+            }
+            ThreadMsg::WatchNametables(scanline) => write!(f, "WatchNametables({:?})", scanline),
+            ThreadMsg::NametableSnapshot(snapshot) => {
+                write!(f, "NametableSnapshot(scanline: {})", snapshot.scanline)
+            }
+            ThreadMsg::WatchTiles(watch) => write!(f, "WatchTiles({})", watch),
+            ThreadMsg::TileSnapshot(snapshot) => {
+                write!(f, "TileSnapshot([u8; {}])", snapshot.chr.len())
+            }
         }
     }
 }
@@ -67,7 +156,7 @@ impl Clone for ThreadMsg {
         match self {
             ThreadMsg::Pause => ThreadMsg::Pause,
             ThreadMsg::Resume => ThreadMsg::Resume,
-            ThreadMsg::Step(steps) => ThreadMsg::Step(*steps),
+            ThreadMsg::Step(kind) => ThreadMsg::Step(*kind),
             ThreadMsg::NewFrame(duration, pixels) => ThreadMsg::NewFrame(*duration, pixels.clone()),
             ThreadMsg::Stop => ThreadMsg::Stop,
             ThreadMsg::SteppingFinished => ThreadMsg::SteppingFinished,
@@ -77,6 +166,33 @@ impl Clone for ThreadMsg {
             ThreadMsg::UpdateDeviceButton(port, device_button, pressed) => {
                 ThreadMsg::UpdateDeviceButton(*port, device_button.box_clone(), *pressed)
             }
+            ThreadMsg::SaveState(slot) => ThreadMsg::SaveState(*slot),
+            ThreadMsg::StateSaved(slot, state) => ThreadMsg::StateSaved(*slot, state.clone()),
+            ThreadMsg::LoadState(state) => ThreadMsg::LoadState(state.clone()),
+            ThreadMsg::StateLoaded(result) => ThreadMsg::StateLoaded(result.clone()),
+            ThreadMsg::Rewind(rewinding) => ThreadMsg::Rewind(*rewinding),
+            ThreadMsg::SetSpeed(speed) => ThreadMsg::SetSpeed(*speed),
+            ThreadMsg::RecordMovie(path, from_power_on) => {
+                ThreadMsg::RecordMovie(path.clone(), *from_power_on)
+            }
+            ThreadMsg::PlayMovie(movie) => ThreadMsg::PlayMovie(movie.clone()),
+            ThreadMsg::StopMovie => ThreadMsg::StopMovie,
+            ThreadMsg::MovieFinished => ThreadMsg::MovieFinished,
+            ThreadMsg::WatchCpu(watch) => ThreadMsg::WatchCpu(*watch),
+            ThreadMsg::CpuSnapshot(snapshot) => ThreadMsg::CpuSnapshot(snapshot.clone()),
+            ThreadMsg::SetBreakpoints(list) => ThreadMsg::SetBreakpoints(list.clone()),
+            ThreadMsg::BreakpointHit(hit) => ThreadMsg::BreakpointHit(hit.clone()),
+            ThreadMsg::WatchMemory(region) => ThreadMsg::WatchMemory(*region),
+            ThreadMsg::MemorySnapshot(snapshot) => ThreadMsg::MemorySnapshot(snapshot.clone()),
+            ThreadMsg::PokeMemory(region, offset, value) => {
+                ThreadMsg::PokeMemory(*region, *offset, *value)
+            }
+            ThreadMsg::WatchNametables(scanline) => ThreadMsg::WatchNametables(*scanline),
+            ThreadMsg::NametableSnapshot(snapshot) => {
+                ThreadMsg::NametableSnapshot(snapshot.clone())
+            }
+            ThreadMsg::WatchTiles(watch) => ThreadMsg::WatchTiles(*watch),
+            ThreadMsg::TileSnapshot(snapshot) => ThreadMsg::TileSnapshot(snapshot.clone()),
         }
     }
 }
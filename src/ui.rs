@@ -2,7 +2,9 @@ use crate::{
     app::App,
     events::{AppEvent, ResetTarget},
     input::Input,
+    nes_manager::MovieStatus,
     prelude::*,
+    save_slots::{self, SLOT_COUNT},
 };
 use egui::{
     Image, Ui, ViewportBuilder, ViewportId, containers::menu, include_image, load::SizedTexture,
@@ -24,8 +26,30 @@ impl App {
             self.controller_config(ctx);
         }
 
+        self.nes_manager.watch_cpu(self.cpu_debugger.open);
+        if self.cpu_debugger.open {
+            self.cpu_debugger(ctx);
+        }
+        self.nes_manager
+            .watch_memory(self.memory_editor.open.then_some(self.memory_editor.region));
+        if self.memory_editor.open {
+            self.memory_editor(ctx);
+        }
+        self.nes_manager.watch_nametables(
+            self.nametable_viewer
+                .open
+                .then_some(self.nametable_viewer.scanline),
+        );
+        if self.nametable_viewer.open {
+            self.nametable_viewer(ctx);
+        }
+        self.nes_manager.watch_tiles(self.tile_viewer.open);
+        if self.tile_viewer.open {
+            self.tile_viewer(ctx);
+        }
+
         if self.show_reset_app_data {
-            let mut show: bool = self.show_reset_app_data.clone();
+            let mut show: bool = self.show_reset_app_data;
             self.reset_app_data(ctx, &mut show);
             self.show_reset_app_data = show;
         }
@@ -35,6 +59,14 @@ impl App {
         egui::TopBottomPanel::top("menu_bar").show(ctx, |ui: &mut Ui| {
             menu::MenuBar::new().ui(ui, |ui: &mut Ui| {
                 self.menu_bar_file(ui);
+                ui.menu_button("Tools", |ui: &mut Ui| {
+                    ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
+
+                    ui.checkbox(&mut self.cpu_debugger.open, "CPU Debugger");
+                    ui.checkbox(&mut self.memory_editor.open, "Memory Editor");
+                    ui.checkbox(&mut self.nametable_viewer.open, "Nametable Viewer");
+                    ui.checkbox(&mut self.tile_viewer.open, "Tile Viewer");
+                });
                 #[cfg(debug_assertions)]
                 ui.menu_button("Debug", |ui: &mut Ui| {
                     ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
@@ -82,12 +114,18 @@ impl App {
                 debug!("Loading ROM from path: {:?}", path);
                 self.new_event(AppEvent::NES(crate::NESEvent::Start(path)));
             }
+            ui.add_enabled_ui(self.nes_manager.save_slots.is_some(), |ui: &mut Ui| {
+                ui.menu_button("States", |ui: &mut Ui| self.menu_states(ui));
+                ui.menu_button("Movie", |ui: &mut Ui| self.menu_movie(ui));
+            });
             ui.separator();
 
             ui.menu_button("Preferences", |ui: &mut Ui| {
                 if ui.button("Controllers").clicked() {
                     self.show_controller_config = !self.show_controller_config
                 }
+                ui.menu_button("Rewind", |ui: &mut Ui| self.menu_rewind(ui));
+                ui.menu_button("Fast-forward", |ui: &mut Ui| self.menu_fast_forward(ui));
             });
 
             if ui.button("Reset app data").clicked() {
@@ -101,6 +139,132 @@ impl App {
         });
     }
 
+    fn menu_rewind(&mut self, ui: &mut Ui) {
+        let config: &mut crate::nes_manager::RewindConfig = &mut self.nes_manager.rewind_config;
+        ui.checkbox(&mut config.enabled, "Enabled");
+        ui.add_enabled_ui(config.enabled, |ui: &mut Ui| {
+            ui.add(egui::Slider::new(&mut config.speed, 0.25..=4.0).text("Speed"));
+            ui.add(
+                egui::Slider::new(&mut config.interval, 1..=10).text("Frames between snapshots"),
+            );
+            ui.add(
+                egui::Slider::new(&mut config.buffer_size, 8..=1024)
+                    .logarithmic(true)
+                    .suffix(" MiB")
+                    .text("Buffer size"),
+            );
+        });
+        ui.label("Changes apply to the next ROM that is loaded");
+    }
+
+    fn menu_fast_forward(&mut self, ui: &mut Ui) {
+        let config: &mut crate::nes_manager::FastForwardConfig =
+            &mut self.nes_manager.fast_forward_config;
+        let speed_changed: bool = ui
+            .add(
+                egui::Slider::new(&mut config.speed, 1.5..=16.0)
+                    .logarithmic(true)
+                    .suffix("x")
+                    .text("Speed"),
+            )
+            .changed();
+        ui.checkbox(&mut config.toggle, "Toggle instead of hold");
+
+        if speed_changed && self.is_fast_forwarding {
+            self.nes_manager.set_fast_forward(true);
+        }
+    }
+
+    fn menu_movie(&mut self, ui: &mut Ui) {
+        let idle: bool = self.nes_manager.movie_status == MovieStatus::Idle;
+        ui.add_enabled_ui(idle, |ui: &mut Ui| {
+            for (label, from_power_on) in
+                [("Record from power-on", true), ("Record from here", false)]
+            {
+                if ui.button(label).clicked()
+                    && let Some(path) = rfd::FileDialog::new()
+                        .add_filter("FCEUX movie", &["fm2"])
+                        .save_file()
+                {
+                    self.new_event(AppEvent::NES(crate::NESEvent::RecordMovie(
+                        path.with_extension("fm2"),
+                        from_power_on,
+                    )));
+                }
+            }
+            if ui.button("Play").clicked()
+                && let Some(path) = rfd::FileDialog::new()
+                    .add_filter("FCEUX movie", &["fm2"])
+                    .pick_file()
+            {
+                self.new_event(AppEvent::NES(crate::NESEvent::PlayMovie(path)));
+            }
+        });
+
+        let stop_label: &str = match self.nes_manager.movie_status {
+            MovieStatus::Recording => "Stop recording",
+            _ => "Stop playback",
+        };
+        if ui
+            .add_enabled(!idle, egui::Button::new(stop_label))
+            .clicked()
+        {
+            self.new_event(AppEvent::NES(crate::NESEvent::StopMovie));
+        }
+    }
+
+    fn menu_states(&mut self, ui: &mut Ui) {
+        let Some(save_slots) = self.nes_manager.save_slots.as_mut() else {
+            return;
+        };
+
+        let mut event: Option<crate::NESEvent> = None;
+        egui::Grid::new("save_slots_grid").show(ui, |ui: &mut Ui| {
+            for slot in 0..SLOT_COUNT {
+                match save_slots.thumbnail(ui.ctx(), slot) {
+                    Some(texture) => {
+                        ui.add(
+                            Image::from_texture(SizedTexture::from_handle(texture))
+                                .fit_to_exact_size(egui::vec2(64.0, 60.0)),
+                        );
+                    }
+                    None => {
+                        ui.allocate_space(egui::vec2(64.0, 60.0));
+                    }
+                }
+
+                let label: String = match save_slots.slots[slot].as_ref() {
+                    Some(info) => {
+                        format!("Slot {} ({})", slot, save_slots::format_age(info.timestamp))
+                    }
+                    None => format!("Slot {} (empty)", slot),
+                };
+                if ui
+                    .selectable_label(save_slots.selected == slot, label)
+                    .clicked()
+                {
+                    save_slots.selected = slot;
+                }
+
+                if ui.button("Save").clicked() {
+                    event = Some(crate::NESEvent::SaveState(slot));
+                }
+                if ui
+                    .add_enabled(save_slots.slots[slot].is_some(), egui::Button::new("Load"))
+                    .clicked()
+                {
+                    event = Some(crate::NESEvent::LoadState(slot));
+                }
+                ui.end_row();
+            }
+        });
+
+        if let Some(event) = event {
+            self.new_event(AppEvent::NES(event));
+            ui.close();
+        }
+    }
+
     fn reset_app_data(&mut self, ctx: &egui::Context, show: &mut bool) {
         egui::Window::new("Are you sure?")
             .open(show)
@@ -261,6 +425,10 @@ impl App {
                         define_key_mapping!(sys self, ui, input: maybe_input, key: rewind);
                         // TODO: Do some token tree magic to replace spaces with _ and -
                         define_key_mapping!(sys self, ui, input: maybe_input, key: fast_forward);
+                        define_key_mapping!(sys self, ui, input: maybe_input, key: save_state);
+                        define_key_mapping!(sys self, ui, input: maybe_input, key: load_state);
+                        define_key_mapping!(sys self, ui, input: maybe_input, key: next_slot);
+                        define_key_mapping!(sys self, ui, input: maybe_input, key: previous_slot);
 
                         ui.label("");
                         ui.label("");
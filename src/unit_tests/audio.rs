@@ -0,0 +1,88 @@
+use crate::audio::{
+    AudioBackend, AudioBuffer, DEFAULT_SAMPLE_RATE, SharedAudioBuffer, wav::WavBackend,
+};
+use hound::{SampleFormat, WavReader};
+use std::{fs::File, io::BufReader, path::PathBuf, sync::Arc};
+
+#[test]
+fn test_buffer_push_pop() {
+    let buffer: AudioBuffer = AudioBuffer::new(4);
+    assert!(buffer.is_empty());
+    assert_eq!(buffer.pop(), None);
+
+    buffer.push(0.25);
+    buffer.push(-0.5);
+    assert_eq!(buffer.len(), 2);
+    assert_eq!(buffer.pop(), Some(0.25));
+    assert_eq!(buffer.pop(), Some(-0.5));
+    assert_eq!(buffer.pop(), None);
+}
+
+#[test]
+fn test_buffer_overwrites_oldest() {
+    let buffer: AudioBuffer = AudioBuffer::new(3);
+    for sample in [0.1, 0.2, 0.3, 0.4, 0.5] {
+        buffer.push(sample);
+    }
+    assert_eq!(buffer.len(), 3);
+    assert_eq!(buffer.pop(), Some(0.3));
+    assert_eq!(buffer.pop(), Some(0.4));
+    assert_eq!(buffer.pop(), Some(0.5));
+}
+
+#[test]
+fn test_buffer_clear() {
+    let buffer: AudioBuffer = AudioBuffer::new(4);
+    buffer.push(0.1);
+    buffer.push(0.2);
+    buffer.clear();
+    assert!(buffer.is_empty());
+    assert_eq!(buffer.pop(), None);
+}
+
+#[test]
+fn test_buffer_volume() {
+    let buffer: AudioBuffer = AudioBuffer::new(4);
+    assert_eq!(buffer.volume(), 1.0);
+
+    buffer.set_volume(0.5);
+    buffer.push(0.5);
+    assert_eq!(buffer.pop(), Some(0.25));
+
+    buffer.set_volume(2.0);
+    assert_eq!(buffer.volume(), 1.0);
+    buffer.set_volume(-1.0);
+    assert_eq!(buffer.volume(), 0.0);
+    buffer.push(0.5);
+    assert_eq!(buffer.pop(), Some(0.0));
+}
+
+#[test]
+fn test_wav_backend() {
+    let path: PathBuf =
+        std::env::temp_dir().join(format!("nesmur_audio_{}.wav", std::process::id()));
+    let buffer: SharedAudioBuffer = Arc::new(AudioBuffer::new(16));
+    let samples: [f32; 4] = [0.0, 0.5, -0.5, 1.0];
+
+    let backend: WavBackend = WavBackend::new(&path, buffer.clone()).unwrap();
+    assert_eq!(backend.name(), "wav");
+    assert_eq!(backend.sample_rate(), DEFAULT_SAMPLE_RATE);
+    for sample in samples {
+        buffer.push(sample);
+    }
+    // Dropping the backend drains what's left and finalizes the file
+    drop(backend);
+    assert!(buffer.is_empty());
+
+    let mut reader: WavReader<BufReader<File>> = WavReader::open(&path).unwrap();
+    assert_eq!(reader.spec().channels, 1);
+    assert_eq!(reader.spec().sample_rate, DEFAULT_SAMPLE_RATE);
+    assert_eq!(reader.spec().sample_format, SampleFormat::Float);
+    let written: Vec<f32> = reader
+        .samples::<f32>()
+        .map(|sample: Result<f32, hound::Error>| sample.unwrap())
+        .collect();
+    assert_eq!(written, samples);
+
+    std::fs::remove_file(&path).unwrap();
+}
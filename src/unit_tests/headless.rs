@@ -0,0 +1,62 @@
+use crate::headless::{StopCondition, numbered_path};
+use std::path::{Path, PathBuf};
+
+#[test]
+fn test_stop_condition_program_counter() {
+    assert_eq!(
+        "pc=C000".parse::<StopCondition>(),
+        Ok(StopCondition::ProgramCounter(0xC000))
+    );
+    assert_eq!(
+        "pc=$e5b6".parse::<StopCondition>(),
+        Ok(StopCondition::ProgramCounter(0xE5B6))
+    );
+    assert_eq!(
+        "pc=0x8000".parse::<StopCondition>(),
+        Ok(StopCondition::ProgramCounter(0x8000))
+    );
+}
+
+#[test]
+fn test_stop_condition_memory() {
+    assert_eq!(
+        "6000=80".parse::<StopCondition>(),
+        Ok(StopCondition::Equal(0x6000, 0x80))
+    );
+    assert_eq!(
+        "$6000!=$80".parse::<StopCondition>(),
+        Ok(StopCondition::NotEqual(0x6000, 0x80))
+    );
+    assert_eq!(
+        "0x10 = 0x0".parse::<StopCondition>(),
+        Ok(StopCondition::Equal(0x0010, 0x00))
+    );
+}
+
+#[test]
+fn test_stop_condition_bad_input() {
+    assert!("pc=XYZ".parse::<StopCondition>().is_err());
+    assert!("pc=".parse::<StopCondition>().is_err());
+    // Out of range for an address or a byte
+    assert!("pc=10000".parse::<StopCondition>().is_err());
+    assert!("6000=100".parse::<StopCondition>().is_err());
+    assert!("G000=00".parse::<StopCondition>().is_err());
+    assert!("6000!=".parse::<StopCondition>().is_err());
+    assert!("6000".parse::<StopCondition>().is_err());
+}
+
+#[test]
+fn test_numbered_path() {
+    assert_eq!(
+        numbered_path(Path::new("shots/shot.png"), 120),
+        PathBuf::from("shots/shot-000120.png")
+    );
+    assert_eq!(
+        numbered_path(Path::new("shot"), 7),
+        PathBuf::from("shot-000007")
+    );
+    assert_eq!(
+        numbered_path(Path::new("shot.final.png"), 1_234_567),
+        PathBuf::from("shot.final-1234567.png")
+    );
+}
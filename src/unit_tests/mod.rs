@@ -0,0 +1,3 @@
+pub mod audio;
+pub mod headless;
+pub mod save_slots;
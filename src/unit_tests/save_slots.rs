@@ -0,0 +1,20 @@
+use crate::save_slots::SaveSlots;
+use egui::{Color32, ColorImage};
+use std::path::PathBuf;
+
+#[test]
+fn test_write_without_thumbnail_removes_old_one() {
+    let dir: PathBuf = std::env::temp_dir().join(format!("nesmur_slots_{}", std::process::id()));
+    let thumbnail_path: PathBuf = dir.join("game.states").join("slot3.png");
+    let mut save_slots: SaveSlots = SaveSlots::new(&dir.join("game.nes"), None);
+
+    let thumbnail: ColorImage = ColorImage::filled([4, 4], Color32::RED);
+    save_slots.write(3, &[0x01], Some(thumbnail)).unwrap();
+    assert!(thumbnail_path.exists());
+
+    save_slots.write(3, &[0x02], None).unwrap();
+    assert!(!thumbnail_path.exists());
+    assert_eq!(save_slots.read(3).unwrap(), vec![0x02]);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
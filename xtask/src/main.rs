@@ -8,9 +8,9 @@ use std::{
 use tokio::sync::broadcast::{self, Receiver, Sender};
 use warp::{Filter, Rejection, http::Response};
 
-const CUSTOM_DOCS_PATH: &'static str = concat!(env!("CARGO_MANIFEST_DIR"), "/../docs/rustdocs/");
-const CARGO_DOCS_PATH: &'static str = concat!(env!("CARGO_MANIFEST_DIR"), "/../target/doc/");
-const ROOT_PATH: &'static str = concat!(env!("CARGO_MANIFEST_DIR"), "/../");
+const CUSTOM_DOCS_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../docs/rustdocs/");
+const CARGO_DOCS_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../target/doc/");
+const ROOT_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../");
 
 #[tokio::main(flavor = "current_thread")]
 async fn main() {
@@ -22,8 +22,8 @@ async fn main() {
     let mut docs_run: bool = false;
 
     // TODO: Better arg parsing, or just use `clap`
-    for i in 0..args.len() {
-        match args[i].as_str() {
+    for arg in args.iter() {
+        match arg.as_str() {
             "docs" => docs = true,
             "gen" => docs_gen = true,
             "open" => docs_open = true,
@@ -110,23 +110,22 @@ async fn run_docs_server() {
         std::thread::spawn(move || {
             let mut watcher: RecommendedWatcher =
                 notify::recommended_watcher(move |res: Result<Event>| {
-                    if let Ok(event) = res {
-                        if matches!(
+                    if let Ok(event) = res
+                        && matches!(
                             event.kind,
                             EventKind::Modify(_) | EventKind::Create(_) | EventKind::Remove(_)
-                        ) {
-                            if event.paths.iter().any(|path: &PathBuf| -> bool {
-                                match path.extension().and_then(|e: &std::ffi::OsStr| e.to_str()) {
-                                    Some(ext) => {
-                                        matches!(ext, "html" | "css" | "js" | "jsx" | "ts" | "tsx")
-                                    }
-                                    None => false,
+                        )
+                        && event.paths.iter().any(|path: &PathBuf| -> bool {
+                            match path.extension().and_then(|e: &std::ffi::OsStr| e.to_str()) {
+                                Some(ext) => {
+                                    matches!(ext, "html" | "css" | "js" | "jsx" | "ts" | "tsx")
                                 }
-                            }) {
-                                drop(tx.send("reload".to_string()));
-                                println!("Reloading...");
+                                None => false,
                             }
-                        }
+                        })
+                    {
+                        drop(tx.send("reload".to_string()));
+                        println!("Reloading...");
                     }
                 })
                 .expect("Failed to create file watcher");
@@ -177,33 +176,33 @@ async fn run_docs_server() {
                             .body("Redirecting to /nesmur/index.html".to_string())
                             .unwrap(),
                     );
-                } else if path_str.ends_with(".html") {
-                    if let Ok(content) = fs::read_to_string(&full_path) {
-                        let injected: String = {
-                            let script: String = format!(
-                                r#"<script>
+                } else if path_str.ends_with(".html")
+                    && let Ok(content) = fs::read_to_string(&full_path)
+                {
+                    let injected: String = {
+                        let script: String = format!(
+                            r#"<script>
                                     const ws = new WebSocket("ws://localhost:{}/livereload");
                                     ws.onmessage = () => window.location.reload();
                                 </script>"#,
-                                port
-                            );
-
-                            if content.contains("</body>") {
-                                content.replace("</body>", &format!("{}\n</body>", script))
-                            } else if content.contains("</html>") {
-                                content.replace("</html>", &format!("{}\n</html>", script))
-                            } else {
-                                format!("{}\n{}", content, script)
-                            }
-                        };
-
-                        return Ok::<_, Rejection>(
-                            Response::builder()
-                                .header("content-type", "text/html; charset=utf8")
-                                .body(injected)
-                                .unwrap(),
+                            port
                         );
-                    }
+
+                        if content.contains("</body>") {
+                            content.replace("</body>", &format!("{}\n</body>", script))
+                        } else if content.contains("</html>") {
+                            content.replace("</html>", &format!("{}\n</html>", script))
+                        } else {
+                            format!("{}\n{}", content, script)
+                        }
+                    };
+
+                    return Ok::<_, Rejection>(
+                        Response::builder()
+                            .header("content-type", "text/html; charset=utf8")
+                            .body(injected)
+                            .unwrap(),
+                    );
                 }
 
                 Err(warp::reject::not_found())